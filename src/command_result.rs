@@ -0,0 +1,141 @@
+use std::fmt;
+
+/// Tipo de comando ejecutado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    CreateTable,
+    CreateIndex,
+    DropTable,
+    AlterTable,
+    Explain,
+}
+
+impl CommandKind {
+    /// Indica si el comando modifica las tablas o el esquema
+    #[must_use]
+    pub fn is_write(self) -> bool {
+        match self {
+            CommandKind::Select | CommandKind::Explain => false,
+            CommandKind::Insert
+            | CommandKind::Update
+            | CommandKind::Delete
+            | CommandKind::CreateTable
+            | CommandKind::CreateIndex
+            | CommandKind::DropTable
+            | CommandKind::AlterTable => true,
+        }
+    }
+
+    /// Indica si el resumen del comando informa la cantidad de filas
+    #[must_use]
+    pub fn reports_rows(self) -> bool {
+        matches!(
+            self,
+            CommandKind::Select | CommandKind::Insert | CommandKind::Update | CommandKind::Delete
+        )
+    }
+}
+
+impl fmt::Display for CommandKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CommandKind::Select => "SELECT",
+            CommandKind::Insert => "INSERT",
+            CommandKind::Update => "UPDATE",
+            CommandKind::Delete => "DELETE",
+            CommandKind::CreateTable => "CREATE TABLE",
+            CommandKind::CreateIndex => "CREATE INDEX",
+            CommandKind::DropTable => "DROP TABLE",
+            CommandKind::AlterTable => "ALTER TABLE",
+            CommandKind::Explain => "EXPLAIN",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Resultado de ejecutar un comando
+#[derive(Debug, PartialEq)]
+pub struct CommandResult {
+    /// Tipo de comando ejecutado
+    pub command: CommandKind,
+    /// Total de filas afectadas, o devueltas en el caso del SELECT
+    pub affected_rows: usize,
+    /// Filas afectadas en cada tabla, en el orden en que se procesaron
+    pub table_rows: Vec<(String, usize)>,
+    /// Salida del SELECT o del RETURNING. Si el SELECT escribe sus filas a medida que las
+    /// produce no se guarda
+    pub output: Option<String>,
+}
+
+impl CommandResult {
+    /// Crea un resultado sin filas afectadas
+    #[must_use]
+    pub fn new(command: CommandKind) -> Self {
+        Self {
+            command,
+            affected_rows: 0,
+            table_rows: Vec::new(),
+            output: None,
+        }
+    }
+
+    /// Suma las filas afectadas de una tabla
+    pub fn add_table_rows(&mut self, table: &str, rows: usize) {
+        self.affected_rows += rows;
+        self.table_rows.push((table.to_string(), rows));
+    }
+
+    /// Devuelve las filas afectadas de una tabla, si fue procesada
+    #[must_use]
+    pub fn rows_in_table(&self, table: &str) -> Option<usize> {
+        self.table_rows
+            .iter()
+            .find(|(name, _)| name == table)
+            .map(|(_, rows)| *rows)
+    }
+
+    /// Indica si el comando modifica las tablas
+    #[must_use]
+    pub fn is_write(&self) -> bool {
+        self.command.is_write()
+    }
+}
+
+/// Formato del resumen que imprime el CLI, por ejemplo `DELETE 1`.
+/// Los comandos que modifican el esquema no informan filas
+impl fmt::Display for CommandResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.command.reports_rows() {
+            write!(f, "{} {}", self.command, self.affected_rows)
+        } else {
+            write!(f, "{}", self.command)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_result_counts_and_display() {
+        let mut result = CommandResult::new(CommandKind::Delete);
+        result.add_table_rows("clientes", 2);
+        result.add_table_rows("ordenes", 0);
+
+        assert_eq!(result.affected_rows, 2);
+        assert_eq!(result.rows_in_table("clientes"), Some(2));
+        assert_eq!(result.rows_in_table("ordenes"), Some(0));
+        assert_eq!(result.rows_in_table("otra"), None);
+        assert!(result.is_write());
+        assert_eq!(result.to_string(), "DELETE 2");
+        assert_eq!(
+            CommandResult::new(CommandKind::DropTable).to_string(),
+            "DROP TABLE"
+        );
+    }
+}
@@ -1,29 +1,382 @@
-use crate::{condition::Condition, order::OrderBy};
+use crate::{
+    condition::Condition,
+    errors::ErrorType,
+    order::OrderBy,
+    parameters::{Bindings, Placeholder},
+    schema::{ColumnDefinition, TableSchema},
+    window::WindowFunction,
+};
 use std::{collections::HashMap, str::FromStr};
 
 /// Representacion de los distintos comandos
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Commands {
     Insert {
         tables: Vec<String>,
         headers: Vec<String>,
-        values: Vec<String>,
+        source: InsertSource,
+        on_conflict: Option<OnConflict>,
+        returning: Option<Vec<String>>,
     },
     Update {
         tables: Vec<String>,
-        updates: HashMap<String, String>,
+        updates: HashMap<String, Value>,
         where_st: Option<Condition>,
+        returning: Option<Vec<String>>,
     },
     Delete {
         tables: Vec<String>,
         where_st: Option<Condition>,
+        returning: Option<Vec<String>>,
     },
     Select {
         headers: Vec<String>,
         tables: Vec<String>,
         where_st: Option<Condition>,
         order: Option<Vec<OrderBy>>,
+        limit: Option<Value<usize>>,
+        windows: Vec<WindowFunction>,
     },
+    Compound {
+        first: Box<Commands>,
+        operations: Vec<(SetOperation, Commands)>,
+        order: Option<Vec<OrderBy>>,
+        limit: Option<Value<usize>>,
+    },
+    CreateTable {
+        table: String,
+        schema: TableSchema,
+        if_not_exists: bool,
+    },
+    CreateIndex {
+        table: String,
+        column: String,
+        if_not_exists: bool,
+    },
+    DropTable {
+        tables: Vec<String>,
+        if_exists: bool,
+    },
+    AlterTable {
+        table: String,
+        operation: AlterOperation,
+    },
+    /// Muestra el plan de la consulta, con `analyze` ademas la ejecuta y mide cada nodo
+    Explain { query: Box<Commands>, analyze: bool },
+}
+
+/// Valor escrito en la query: un literal, un parametro que se reemplaza antes de ejecutarla
+/// o una referencia `EXCLUDED.columna`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value<T = String> {
+    Literal(T),
+    Parameter(Placeholder),
+    /// valor de la columna en la fila que se intento insertar, solo en ON CONFLICT DO UPDATE
+    Excluded(String),
+}
+
+impl<T: Clone> Value<T> {
+    /// Devuelve el literal, falla si es un parametro sin valor
+    #[allow(clippy::missing_errors_doc)]
+    pub fn literal(&self) -> Result<T, ErrorType> {
+        match self {
+            Value::Literal(value) => Ok(value.clone()),
+            Value::Parameter(placeholder) => Err(placeholder.unbound()),
+            Value::Excluded(column) => Err(ErrorType::InvalidSyntax(format!(
+                "EXCLUDED.{column} can only be used in ON CONFLICT DO UPDATE"
+            ))),
+        }
+    }
+}
+
+impl<T> Value<T> {
+    #[must_use]
+    pub fn placeholder(&self) -> Option<&Placeholder> {
+        match self {
+            Value::Literal(_) | Value::Excluded(_) => None,
+            Value::Parameter(placeholder) => Some(placeholder),
+        }
+    }
+}
+
+/// Un literal es igual al texto con el mismo valor, un parametro no es igual a ningun texto
+impl PartialEq<&str> for Value {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(self, Value::Literal(value) if value == other)
+    }
+}
+
+/// Devuelve los valores a actualizar, falla si alguno es un parametro sin valor
+#[allow(clippy::missing_errors_doc)]
+pub fn literal_updates(
+    updates: &HashMap<String, Value>,
+) -> Result<HashMap<String, String>, ErrorType> {
+    updates
+        .iter()
+        .map(|(column, value)| Ok((column.clone(), value.literal()?)))
+        .collect()
+}
+
+/// Devuelve el limite, falla si es un parametro sin valor
+#[allow(clippy::missing_errors_doc)]
+pub fn literal_limit(limit: Option<&Value<usize>>) -> Result<Option<usize>, ErrorType> {
+    limit.map(Value::literal).transpose()
+}
+
+/// Reemplaza el parametro por su valor. Los valores que se guardan en la tabla no pueden
+/// tener ',' ni saltos de linea
+fn bind_stored(value: &mut Value, bindings: &mut Bindings) -> Result<(), ErrorType> {
+    if let Value::Parameter(placeholder) = value {
+        let bound = bindings.value(placeholder)?;
+        if bound.contains([',', '\n', '\r']) {
+            return Err(ErrorType::InvalidSyntax(format!(
+                "Value for parameter {placeholder} can't contain ',' or line breaks"
+            )));
+        }
+        *value = Value::Literal(bound);
+    }
+    Ok(())
+}
+
+/// Reemplaza el parametro del LIMIT por su valor, que debe ser un numero
+fn bind_limit(limit: &mut Value<usize>, bindings: &mut Bindings) -> Result<(), ErrorType> {
+    if let Value::Parameter(placeholder) = limit {
+        let bound = bindings.value(placeholder)?;
+        let bound = bound
+            .parse()
+            .map_err(|_| ErrorType::InvalidSyntax(format!("Invalid LIMIT value: {bound}")))?;
+        *limit = Value::Literal(bound);
+    }
+    Ok(())
+}
+
+/// Origen de las filas a insertar: valores escritos en la query o el resultado de un select
+#[derive(Debug, Clone)]
+pub enum InsertSource {
+    Values(Vec<Vec<Value>>),
+    Query(Box<Commands>),
+}
+
+/// Accion a realizar cuando la fila a insertar choca con una existente
+#[derive(Debug, Clone)]
+pub enum ConflictAction {
+    Nothing,
+    Update(HashMap<String, Value>),
+}
+
+/// Clausula ON CONFLICT: columnas que identifican a la fila y accion ante un choque
+#[derive(Debug, Clone)]
+pub struct OnConflict {
+    pub columns: Vec<String>,
+    pub action: ConflictAction,
+}
+
+/// Modificacion del esquema de una tabla
+#[derive(Debug, Clone)]
+pub enum AlterOperation {
+    AddColumn(ColumnDefinition),
+    DropColumn(String),
+    RenameColumn { from: String, to: String },
+    RenameTable(String),
+}
+
+/// Representacion de los operadores de conjuntos entre selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+/// Operacion de conjuntos, con `all` no se eliminan las filas duplicadas
+#[derive(Debug, Clone)]
+pub struct SetOperation {
+    pub operator: SetOperator,
+    pub all: bool,
+}
+
+impl Commands {
+    /// Devuelve las condiciones del comando y de sus subconsultas, en el orden de la query
+    fn conditions(&self) -> Vec<&Condition> {
+        match self {
+            Commands::Select { where_st, .. }
+            | Commands::Update { where_st, .. }
+            | Commands::Delete { where_st, .. } => where_st.iter().collect(),
+            Commands::Compound {
+                first, operations, ..
+            } => {
+                let mut conditions = first.conditions();
+                for (_, select) in operations {
+                    conditions.extend(select.conditions());
+                }
+                conditions
+            }
+            Commands::Insert {
+                source: InsertSource::Query(query),
+                ..
+            }
+            | Commands::Explain { query, .. } => query.conditions(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn conditions_mut(&mut self) -> Vec<&mut Condition> {
+        match self {
+            Commands::Select { where_st, .. }
+            | Commands::Update { where_st, .. }
+            | Commands::Delete { where_st, .. } => where_st.iter_mut().collect(),
+            Commands::Compound {
+                first, operations, ..
+            } => {
+                let mut conditions = first.conditions_mut();
+                for (_, select) in operations {
+                    conditions.extend(select.conditions_mut());
+                }
+                conditions
+            }
+            Commands::Insert {
+                source: InsertSource::Query(query),
+                ..
+            }
+            | Commands::Explain { query, .. } => query.conditions_mut(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Devuelve los parametros fuera de las condiciones: valores a insertar o actualizar
+    /// y limites, del comando y de sus subconsultas
+    fn value_placeholders(&self) -> Vec<&Placeholder> {
+        match self {
+            Commands::Insert {
+                source,
+                on_conflict,
+                ..
+            } => {
+                let mut placeholders = match source {
+                    InsertSource::Values(rows) => rows
+                        .iter()
+                        .flatten()
+                        .filter_map(Value::placeholder)
+                        .collect(),
+                    InsertSource::Query(query) => query.value_placeholders(),
+                };
+                if let Some(OnConflict {
+                    action: ConflictAction::Update(updates),
+                    ..
+                }) = on_conflict
+                {
+                    placeholders.extend(updates.values().filter_map(Value::placeholder));
+                }
+                placeholders
+            }
+            Commands::Update { updates, .. } => {
+                updates.values().filter_map(Value::placeholder).collect()
+            }
+            Commands::Select { limit, .. } => limit.iter().filter_map(Value::placeholder).collect(),
+            Commands::Compound {
+                first,
+                operations,
+                limit,
+                ..
+            } => {
+                let mut placeholders = first.value_placeholders();
+                for (_, select) in operations {
+                    placeholders.extend(select.value_placeholders());
+                }
+                placeholders.extend(limit.iter().filter_map(Value::placeholder));
+                placeholders
+            }
+            Commands::Explain { query, .. } => query.value_placeholders(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn bind_values(&mut self, bindings: &mut Bindings) -> Result<(), ErrorType> {
+        match self {
+            Commands::Insert {
+                source,
+                on_conflict,
+                ..
+            } => {
+                match source {
+                    InsertSource::Values(rows) => {
+                        for value in rows.iter_mut().flatten() {
+                            bind_stored(value, bindings)?;
+                        }
+                    }
+                    InsertSource::Query(query) => query.bind_values(bindings)?,
+                }
+                if let Some(OnConflict {
+                    action: ConflictAction::Update(updates),
+                    ..
+                }) = on_conflict
+                {
+                    for value in updates.values_mut() {
+                        bind_stored(value, bindings)?;
+                    }
+                }
+                Ok(())
+            }
+            Commands::Update { updates, .. } => {
+                for value in updates.values_mut() {
+                    bind_stored(value, bindings)?;
+                }
+                Ok(())
+            }
+            Commands::Select {
+                limit: Some(limit), ..
+            } => bind_limit(limit, bindings),
+            Commands::Compound {
+                first,
+                operations,
+                limit,
+                ..
+            } => {
+                first.bind_values(bindings)?;
+                for (_, select) in operations {
+                    select.bind_values(bindings)?;
+                }
+                match limit {
+                    Some(limit) => bind_limit(limit, bindings),
+                    None => Ok(()),
+                }
+            }
+            Commands::Explain { query, .. } => query.bind_values(bindings),
+            _ => Ok(()),
+        }
+    }
+
+    /// Devuelve los parametros del comando y de sus subconsultas
+    #[must_use]
+    pub fn placeholders(&self) -> Vec<&Placeholder> {
+        let mut placeholders: Vec<&Placeholder> = self
+            .conditions()
+            .into_iter()
+            .flat_map(Condition::placeholders)
+            .collect();
+        placeholders.extend(self.value_placeholders());
+        placeholders
+    }
+
+    /// Verifica que no queden parametros sin valor antes de ejecutar el comando
+    #[allow(clippy::missing_errors_doc)]
+    pub fn check_bound(&self) -> Result<(), ErrorType> {
+        match self.placeholders().first() {
+            Some(placeholder) => Err(placeholder.unbound()),
+            None => Ok(()),
+        }
+    }
+
+    /// Reemplaza los parametros del comando por sus valores, falla si falta algun valor o si
+    /// sobra alguno
+    #[allow(clippy::missing_errors_doc)]
+    pub fn bind(&mut self, bindings: &mut Bindings) -> Result<(), ErrorType> {
+        for condition in self.conditions_mut() {
+            condition.bind(bindings)?;
+        }
+        self.bind_values(bindings)?;
+        bindings.finish()
+    }
 }
 
 /// Recibe un string y retorna su correspondiente Commmand inicializado
@@ -35,22 +388,52 @@ impl FromStr for Commands {
             "INSERT" => Ok(Commands::Insert {
                 tables: Vec::new(),
                 headers: Vec::new(),
-                values: Vec::new(),
+                source: InsertSource::Values(Vec::new()),
+                on_conflict: None,
+                returning: None,
             }),
             "UPDATE" => Ok(Commands::Update {
                 tables: Vec::new(),
                 updates: HashMap::new(),
                 where_st: None,
+                returning: None,
             }),
             "DELETE" => Ok(Commands::Delete {
                 tables: Vec::new(),
                 where_st: None,
+                returning: None,
             }),
             "SELECT" => Ok(Commands::Select {
                 headers: Vec::new(),
                 tables: Vec::new(),
                 where_st: None,
                 order: None,
+                limit: None,
+                windows: Vec::new(),
+            }),
+            "CREATE" => Ok(Commands::CreateTable {
+                table: String::new(),
+                schema: TableSchema::new(Vec::new()),
+                if_not_exists: false,
+            }),
+            "DROP" => Ok(Commands::DropTable {
+                tables: Vec::new(),
+                if_exists: false,
+            }),
+            "ALTER" => Ok(Commands::AlterTable {
+                table: String::new(),
+                operation: AlterOperation::RenameTable(String::new()),
+            }),
+            "EXPLAIN" => Ok(Commands::Explain {
+                query: Box::new(Commands::Select {
+                    headers: Vec::new(),
+                    tables: Vec::new(),
+                    where_st: None,
+                    order: None,
+                    limit: None,
+                    windows: Vec::new(),
+                }),
+                analyze: false,
             }),
             _ => {
                 Err("INVALID_SYNTAX: The Query Command doesn't match any of the available options")
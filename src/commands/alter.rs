@@ -0,0 +1,524 @@
+use super::foreign_keys::{
+    referencing_tables, update_references, validate_references, ForeignKeyCheck,
+};
+use super::unique_keys::UniqueKeys;
+use super::utils_commands::{column_position, create_aux_file, open_csv_reader};
+use crate::command_result::{CommandKind, CommandResult};
+use crate::command_types::AlterOperation;
+use crate::errors::ErrorType;
+use crate::index::{rebuild_indexes, remove_indexes, rename_indexes};
+use crate::schema::{ColumnDefinition, TableSchema};
+use std::{
+    fs,
+    io::{BufRead, Write},
+    path::Path,
+};
+
+/// Reescribe la tabla en un archivo auxiliar con el nuevo header, transformando cada fila
+fn rewrite_table<F>(
+    table: &String,
+    db_path: &str,
+    new_header: &[String],
+    mut transform: F,
+) -> Result<(), ErrorType>
+where
+    F: FnMut(Vec<String>) -> Result<Vec<String>, ErrorType>,
+{
+    let (mut reader, _, _, _) = open_csv_reader(table, db_path)
+        .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+    let (mut aux_file, csv_table, aux_table) = create_aux_file(table, db_path, new_header)
+        .map_err(|e| ErrorType::OtherError(format!("creating auxiliary file {e} failed")))?;
+
+    let mut buffer = String::new();
+    while reader
+        .read_line(&mut buffer)
+        .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?
+        > 0
+    {
+        let row: Vec<String> = buffer.trim().split(',').map(ToString::to_string).collect();
+        let row = match transform(row) {
+            Ok(row) => row,
+            Err(e) => {
+                // descarto el archivo auxiliar para dejar la tabla intacta
+                let _ = fs::remove_file(&aux_table);
+                return Err(e);
+            }
+        };
+        writeln!(aux_file, "{}", row.join(","))
+            .map_err(|e| ErrorType::OtherError(format!("writing to auxiliary file {e} failed")))?;
+        buffer.clear(); // Limpiar buffer para la próxima línea
+    }
+
+    fs::rename(&aux_table, &csv_table)
+        .map_err(|e| ErrorType::OtherError(format!("renaming file {e} failed")))
+}
+
+/// Verifica que la columna no exista en la tabla
+fn check_new_column(table_header: &[String], column: &str) -> Result<(), ErrorType> {
+    if table_header.iter().any(|header| header == column) {
+        return Err(ErrorType::InvalidColumn(format!(
+            "Column '{column}' already exists in the table"
+        )));
+    }
+    Ok(())
+}
+
+/// Agrega la columna al final, completando las filas existentes con el valor por defecto
+/// o con valores generados si es autoincremental. Las filas resultantes tienen que cumplir
+/// las restricciones de la nueva columna. Devuelve la columna con su proximo valor
+fn add_column(
+    table: &String,
+    table_header: &[String],
+    column: &ColumnDefinition,
+    schema: Option<&TableSchema>,
+    db_path: &str,
+) -> Result<ColumnDefinition, ErrorType> {
+    check_new_column(table_header, &column.name)?;
+    let default = column.default.clone().unwrap_or_default();
+    if !column.data_type.is_valid(&default) {
+        return Err(ErrorType::InvalidColumn(format!(
+            "Invalid default '{default}' for column '{}' of type {}",
+            column.name, column.data_type
+        )));
+    }
+
+    let mut new_header = table_header.to_vec();
+    new_header.push(column.name.to_string());
+    // en tablas sin esquema solo se verifican las restricciones de la nueva columna
+    let mut new_schema = schema
+        .cloned()
+        .unwrap_or_else(|| TableSchema::new(Vec::new()));
+    new_schema.columns.push(column.clone());
+    new_schema.validate_keys()?;
+    // solo se verifican las claves de la nueva columna, las demas ya se cumplen
+    let key_schema = TableSchema::new(vec![column.clone()]);
+    validate_references(table, &key_schema, db_path)?;
+    let key_index = [(column.name.to_string(), table_header.len())].into();
+    let mut unique_keys = UniqueKeys::new(table, Some(&key_schema), &key_index)?;
+    let foreign_keys = ForeignKeyCheck::new(table, Some(&key_schema), &key_index, db_path)?;
+
+    let mut column = column.clone();
+    rewrite_table(table, db_path, &new_header, |mut row| {
+        row.resize(table_header.len(), String::new());
+        row.push(column.next_value().unwrap_or_else(|| default.to_string()));
+        new_schema.check_row(table, &new_header, &row)?;
+        unique_keys.insert(&row)?;
+        foreign_keys.check(&row)?;
+        Ok(row)
+    })?;
+    Ok(column)
+}
+
+/// Elimina la columna de todas las filas
+fn drop_column(
+    table: &String,
+    table_header: &[String],
+    column: &str,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    let index = column_position(table_header, column)?;
+    if table_header.len() == 1 {
+        return Err(ErrorType::InvalidColumn(format!(
+            "Can't drop '{column}', the table must have at least one column"
+        )));
+    }
+
+    let mut new_header = table_header.to_vec();
+    new_header.remove(index);
+
+    rewrite_table(table, db_path, &new_header, |mut row| {
+        if index < row.len() {
+            row.remove(index);
+        }
+        Ok(row)
+    })
+}
+
+/// Cambia el nombre de la columna en el header
+fn rename_column(
+    table: &String,
+    table_header: &[String],
+    from: &str,
+    to: &str,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    let index = column_position(table_header, from)?;
+    check_new_column(table_header, to)?;
+
+    let mut new_header = table_header.to_vec();
+    new_header[index] = to.to_string();
+
+    rewrite_table(table, db_path, &new_header, Ok)
+}
+
+/// Cambia el nombre del archivo de la tabla, de su esquema y de sus indices
+fn rename_table(
+    table: &str,
+    new_name: &str,
+    schema: Option<&TableSchema>,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    let new_table = format!("{db_path}/{new_name}.csv");
+    if Path::new(&new_table).exists() {
+        return Err(ErrorType::InvalidTable(format!(
+            "Table '{new_name}' already exists"
+        )));
+    }
+
+    fs::rename(format!("{db_path}/{table}.csv"), new_table)
+        .map_err(|e| ErrorType::OtherError(format!("renaming table {e} failed")))?;
+    if schema.is_some() {
+        fs::rename(
+            TableSchema::path(table, db_path),
+            TableSchema::path(new_name, db_path),
+        )
+        .map_err(|e| ErrorType::OtherError(format!("renaming schema {e} failed")))?;
+    }
+    rename_indexes(table, new_name, None, db_path)
+}
+
+pub fn alter_table(
+    table: &String,
+    operation: &AlterOperation,
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let (_, table_header, _, mut schema) = open_csv_reader(table, db_path)
+        .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+
+    // las tablas con esquema lo mantienen sincronizado con el header
+    match operation {
+        AlterOperation::AddColumn(column) => {
+            let column = add_column(table, &table_header, column, schema.as_ref(), db_path)?;
+            if let Some(schema) = &mut schema {
+                schema.columns.push(column);
+            }
+        }
+        AlterOperation::DropColumn(column) => {
+            // una columna referenciada por otra tabla no se puede eliminar
+            for (child, key) in referencing_tables(table, db_path)? {
+                if key.references.contains(column) {
+                    return Err(ErrorType::InvalidColumn(format!(
+                        "Can't drop '{column}', it is referenced by table '{child}'"
+                    )));
+                }
+            }
+            drop_column(table, &table_header, column, db_path)?;
+            remove_indexes(table, Some(column), db_path)?;
+            if let Some(schema) = &mut schema {
+                schema
+                    .columns
+                    .retain(|definition| &definition.name != column);
+                // las restricciones de tabla sobre la columna se eliminan con ella
+                schema.keys.retain(|key| !key.columns.contains(column));
+                schema
+                    .foreign_keys
+                    .retain(|key| !key.columns.contains(column));
+                // tambien los CHECK de otras columnas que la comparan
+                for definition in &mut schema.columns {
+                    if definition
+                        .check
+                        .as_ref()
+                        .is_some_and(|check| check.condition.columns().contains(&column))
+                    {
+                        definition.check = None;
+                    }
+                }
+            }
+        }
+        AlterOperation::RenameColumn { from, to } => {
+            rename_column(table, &table_header, from, to, db_path)?;
+            rename_indexes(table, table, Some((from, to)), db_path)?;
+            if let Some(schema) = &mut schema {
+                for definition in &mut schema.columns {
+                    if &definition.name == from {
+                        definition.name = to.to_string();
+                    }
+                    // el CHECK se vuelve a escribir con el nuevo nombre
+                    if let Some(check) = definition
+                        .check
+                        .as_mut()
+                        .filter(|check| check.condition.columns().contains(&from))
+                    {
+                        check.condition.rename_column(from, to);
+                        check.expression = check.condition.to_string();
+                    }
+                }
+                let foreign_key_columns = schema
+                    .columns
+                    .iter_mut()
+                    .filter_map(|column| column.references.as_mut())
+                    .chain(schema.foreign_keys.iter_mut())
+                    .flat_map(|key| &mut key.columns);
+                let key_columns = schema.keys.iter_mut().flat_map(|key| &mut key.columns);
+                for name in key_columns.chain(foreign_key_columns) {
+                    if name == from {
+                        *name = to.to_string();
+                    }
+                }
+            }
+            update_references(table, db_path, |key| {
+                for name in &mut key.references {
+                    if name == from {
+                        *name = to.to_string();
+                    }
+                }
+            })?;
+        }
+        AlterOperation::RenameTable(new_name) => {
+            rename_table(table, new_name, schema.as_ref(), db_path)?;
+            update_references(table, db_path, |key| key.table = new_name.to_string())?;
+            return Ok(CommandResult::new(CommandKind::AlterTable));
+        }
+    }
+
+    if let Some(schema) = schema {
+        schema.save(table, db_path)?;
+    }
+    // la tabla se reescribio, las posiciones de las filas cambiaron
+    rebuild_indexes(table, db_path)?;
+    Ok(CommandResult::new(CommandKind::AlterTable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_column_definition;
+    use crate::schema::{DataType, UniqueConstraint};
+
+    fn setup_table(db_path: &str) -> String {
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = format!("{db_path}/clientes.csv");
+        fs::write(&csv_path, "id,nombre\n1,Juan\n2,Maria\n")
+            .expect("Unable to create test table file");
+        csv_path
+    }
+
+    #[test]
+    fn test_alter_table_add_column_with_default() {
+        let db_path = "./test_alter_db1";
+        let csv_path = setup_table(db_path);
+
+        let mut column = ColumnDefinition::new("ciudad", DataType::Text);
+        column.default = Some("Rosario".to_string());
+        let operation = AlterOperation::AddColumn(column.clone());
+        assert!(alter_table(&"clientes".to_string(), &operation, db_path).is_ok());
+
+        let content = fs::read_to_string(&csv_path).expect("Failed to read test table file");
+        assert_eq!(
+            content,
+            "id,nombre,ciudad\n1,Juan,Rosario\n2,Maria,Rosario\n"
+        );
+
+        // la columna ya existe
+        let result = alter_table(&"clientes".to_string(), &operation, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_drop_and_rename_column() {
+        let db_path = "./test_alter_db2";
+        let csv_path = setup_table(db_path);
+        let table = "clientes".to_string();
+
+        let operation = AlterOperation::RenameColumn {
+            from: "nombre".to_string(),
+            to: "nombres".to_string(),
+        };
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+        let operation = AlterOperation::DropColumn("id".to_string());
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+
+        let content = fs::read_to_string(&csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "nombres\nJuan\nMaria\n");
+
+        let operation = AlterOperation::DropColumn("nombres".to_string());
+        let result = alter_table(&table, &operation, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_rename_table() {
+        let db_path = "./test_alter_db3";
+        setup_table(db_path);
+        fs::write(format!("{db_path}/ordenes.csv"), "id\n").expect("Unable to create table");
+
+        let operation = AlterOperation::RenameTable("ordenes".to_string());
+        let result = alter_table(&"clientes".to_string(), &operation, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidTable(_))));
+
+        let operation = AlterOperation::RenameTable("compradores".to_string());
+        assert!(alter_table(&"clientes".to_string(), &operation, db_path).is_ok());
+        assert!(Path::new(&format!("{db_path}/compradores.csv")).exists());
+        assert!(!Path::new(&format!("{db_path}/clientes.csv")).exists());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_keeps_schema_in_sync() {
+        let db_path = "./test_alter_db4";
+        setup_table(db_path);
+        let schema = TableSchema::new(vec![
+            ColumnDefinition::new("id", DataType::Integer),
+            ColumnDefinition::new("nombre", DataType::Text),
+        ]);
+        schema
+            .save("clientes", db_path)
+            .expect("Failed to save schema");
+        let table = "clientes".to_string();
+
+        // el valor por defecto tiene que respetar el tipo de la columna
+        let mut edad = ColumnDefinition::new("edad", DataType::Integer);
+        edad.default = Some("veinte".to_string());
+        let result = alter_table(&table, &AlterOperation::AddColumn(edad.clone()), db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        edad.default = Some("20".to_string());
+        assert!(alter_table(&table, &AlterOperation::AddColumn(edad), db_path).is_ok());
+        let operation = AlterOperation::RenameColumn {
+            from: "nombre".to_string(),
+            to: "nombres".to_string(),
+        };
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+        let operation = AlterOperation::RenameTable("compradores".to_string());
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+
+        let schema = TableSchema::load("compradores", db_path)
+            .expect("Failed to load schema")
+            .expect("Schema not found");
+        let names: Vec<&str> = schema.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["id", "nombres", "edad"]);
+        assert_eq!(schema.data_type("edad"), Some(DataType::Integer));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_add_not_null_column_requires_default() {
+        let db_path = "./test_alter_db5";
+        let csv_path = setup_table(db_path);
+        let table = "clientes".to_string();
+
+        let mut edad = ColumnDefinition::new("edad", DataType::Integer);
+        edad.not_null = true;
+        let result = alter_table(&table, &AlterOperation::AddColumn(edad.clone()), db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+        let content = fs::read_to_string(&csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre\n1,Juan\n2,Maria\n");
+        assert!(!Path::new(&format!("{db_path}/clientes_temp.csv")).exists());
+
+        edad.default = Some("18".to_string());
+        assert!(alter_table(&table, &AlterOperation::AddColumn(edad), db_path).is_ok());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_keys_follow_columns() {
+        let db_path = "./test_alter_db6";
+        setup_table(db_path);
+        let mut schema = TableSchema::new(vec![
+            parse_column_definition("id INTEGER PRIMARY KEY").unwrap(),
+            ColumnDefinition::new("nombre", DataType::Text),
+        ]);
+        schema.keys.push(UniqueConstraint {
+            columns: vec!["id".to_string(), "nombre".to_string()],
+            primary_key: false,
+        });
+        schema
+            .save("clientes", db_path)
+            .expect("Failed to save schema");
+        let table = "clientes".to_string();
+
+        // una columna UNIQUE no puede repetir el valor por defecto en todas las filas
+        let mut codigo = parse_column_definition("codigo TEXT UNIQUE").unwrap();
+        codigo.default = Some("X".to_string());
+        let result = alter_table(&table, &AlterOperation::AddColumn(codigo), db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        // solo puede haber una clave primaria
+        let mut otra = parse_column_definition("otra INTEGER PRIMARY KEY").unwrap();
+        otra.default = Some("1".to_string());
+        let result = alter_table(&table, &AlterOperation::AddColumn(otra), db_path);
+        assert!(result.is_err());
+
+        let operation = AlterOperation::RenameColumn {
+            from: "nombre".to_string(),
+            to: "nombres".to_string(),
+        };
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+        let schema = TableSchema::load("clientes", db_path).unwrap().unwrap();
+        assert_eq!(schema.keys[0].columns, ["id", "nombres"]);
+
+        let operation = AlterOperation::DropColumn("nombres".to_string());
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+        let schema = TableSchema::load("clientes", db_path).unwrap().unwrap();
+        assert!(schema.keys.is_empty());
+        assert!(schema.columns[0].primary_key);
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_checks_follow_columns() {
+        let db_path = "./test_alter_db8";
+        setup_table(db_path);
+        TableSchema::new(vec![
+            parse_column_definition("id INTEGER CHECK (id > 0 AND nombre != 'Ana')").unwrap(),
+            parse_column_definition("nombre TEXT CHECK (nombre != 'Pedro')").unwrap(),
+        ])
+        .save("clientes", db_path)
+        .expect("Failed to save schema");
+        let table = "clientes".to_string();
+
+        let operation = AlterOperation::RenameColumn {
+            from: "nombre".to_string(),
+            to: "nombres".to_string(),
+        };
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+        let schema = TableSchema::load("clientes", db_path).unwrap().unwrap();
+        let check = schema.columns[1].check.as_ref().unwrap();
+        assert_eq!(check.expression, "nombres != 'Pedro'");
+        assert_eq!(check.condition.columns(), ["nombres"]);
+        let check = schema.columns[0].check.as_ref().unwrap();
+        assert_eq!(check.expression, "id > 0 AND nombres != 'Ana'");
+        let header = ["id".to_string(), "nombres".to_string()];
+        let row = ["3".to_string(), "Pedro".to_string()];
+        assert!(schema.check_row("clientes", &header, &row).is_err());
+
+        // el CHECK de id compara la columna eliminada, se elimina con ella
+        let operation = AlterOperation::DropColumn("nombres".to_string());
+        assert!(alter_table(&table, &operation, db_path).is_ok());
+        let schema = TableSchema::load("clientes", db_path).unwrap().unwrap();
+        assert!(schema.columns[0].check.is_none());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_alter_table_add_auto_increment_column() {
+        let db_path = "./test_alter_db7";
+        let csv_path = setup_table(db_path);
+        TableSchema::new(vec![
+            ColumnDefinition::new("id", DataType::Integer),
+            ColumnDefinition::new("nombre", DataType::Text),
+        ])
+        .save("clientes", db_path)
+        .expect("Failed to save schema");
+
+        let numero = parse_column_definition("numero SERIAL").unwrap();
+        let operation = AlterOperation::AddColumn(numero);
+        assert!(alter_table(&"clientes".to_string(), &operation, db_path).is_ok());
+
+        let content = fs::read_to_string(&csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre,numero\n1,Juan,1\n2,Maria,2\n");
+        let schema = TableSchema::load("clientes", db_path).unwrap().unwrap();
+        assert_eq!(schema.columns[2].auto_increment, Some(3));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
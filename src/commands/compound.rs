@@ -0,0 +1,287 @@
+use super::executor::{execute_plan, write_plan};
+use super::planner::LogicalPlan;
+use crate::command_result::{CommandKind, CommandResult};
+use crate::command_types::{Commands, SetOperation, SetOperator};
+use crate::errors::ErrorType;
+use crate::order::OrderBy;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+type ResultSet = (Vec<String>, Vec<Vec<String>>);
+
+/// Ejecuta una consulta que debe producir un unico resultado, por lo que un select
+/// debe leer de una unica tabla. Devuelve los headers y las filas sin imprimirlas
+pub fn query_result_set(command: &Commands, db_path: &str) -> Result<ResultSet, ErrorType> {
+    execute_plan(LogicalPlan::query(command, db_path)?, db_path)
+}
+
+/// Elimina las filas repetidas manteniendo el orden de aparicion
+fn distinct(rows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .filter(|row| seen.insert(row.clone()))
+        .collect()
+}
+
+/// Cuenta cuantas veces aparece cada fila
+fn count_rows(rows: &[Vec<String>]) -> HashMap<&Vec<String>, usize> {
+    let mut counts = HashMap::new();
+    for row in rows {
+        *counts.entry(row).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Combina las filas de ambos selects segun la operacion
+pub fn apply_set_operation(
+    mut left: Vec<Vec<String>>,
+    right: Vec<Vec<String>>,
+    operation: &SetOperation,
+) -> Vec<Vec<String>> {
+    match (operation.operator, operation.all) {
+        (SetOperator::Union, true) => {
+            left.extend(right);
+            left
+        }
+        (SetOperator::Union, false) => {
+            left.extend(right);
+            distinct(left)
+        }
+        (SetOperator::Intersect, true) => {
+            let mut counts = count_rows(&right);
+            left.into_iter()
+                .filter(|row| match counts.get_mut(row) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        true
+                    }
+                    _ => false,
+                })
+                .collect()
+        }
+        (SetOperator::Intersect, false) => {
+            let right: HashSet<Vec<String>> = right.into_iter().collect();
+            distinct(left)
+                .into_iter()
+                .filter(|row| right.contains(row))
+                .collect()
+        }
+        (SetOperator::Except, true) => {
+            let mut counts = count_rows(&right);
+            left.into_iter()
+                .filter(|row| match counts.get_mut(row) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                })
+                .collect()
+        }
+        (SetOperator::Except, false) => {
+            let right: HashSet<Vec<String>> = right.into_iter().collect();
+            distinct(left)
+                .into_iter()
+                .filter(|row| !right.contains(row))
+                .collect()
+        }
+    }
+}
+
+/// Ejecuta los selects combinados juntando el resultado en el output, pensado para los tests
+#[allow(clippy::missing_errors_doc)]
+pub fn compound(
+    first: &Commands,
+    operations: &[(SetOperation, Commands)],
+    order: &Option<Vec<OrderBy>>,
+    limit: Option<usize>,
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let mut output = Vec::new();
+    let mut result = compound_to(first, operations, order, limit, db_path, &mut output)?;
+    result.output = Some(String::from_utf8_lossy(&output).into_owned());
+    Ok(result)
+}
+
+/// Ejecuta los selects combinados escribiendo el resultado en `out`
+#[allow(clippy::missing_errors_doc)]
+pub fn compound_to(
+    first: &Commands,
+    operations: &[(SetOperation, Commands)],
+    order: &Option<Vec<OrderBy>>,
+    limit: Option<usize>,
+    db_path: &str,
+    out: &mut dyn Write,
+) -> Result<CommandResult, ErrorType> {
+    let plan = LogicalPlan::compound(first, operations, order, limit, db_path)?;
+
+    // el resultado combinado no pertenece a una tabla en particular
+    let mut result = CommandResult::new(CommandKind::Select);
+    result.affected_rows = write_plan(plan, db_path, out)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{ComparisonOp, Condition};
+    use crate::order::Direction;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    fn setup_table(db_path: &str, table_name: &str, rows: &[&str]) {
+        let csv_path = format!("{}/{}.csv", db_path, table_name);
+        let mut file = File::create(&csv_path).expect("Unable to create test table file");
+        writeln!(file, "id,nombre").expect("Unable to write headers");
+        for row in rows {
+            writeln!(file, "{}", row).expect("Unable to write row data");
+        }
+    }
+
+    fn select_all(table: &str, where_st: Option<Condition>) -> Commands {
+        Commands::Select {
+            headers: vec!["*".to_string()],
+            tables: vec![table.to_string()],
+            where_st,
+            order: None,
+            limit: None,
+            windows: Vec::new(),
+        }
+    }
+
+    fn operation(operator: SetOperator, all: bool) -> SetOperation {
+        SetOperation { operator, all }
+    }
+
+    #[test]
+    fn test_union_removes_duplicates_and_union_all_keeps_them() {
+        let db_path = "./test_compound_db1";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, "a", &["1,Juan", "2,Maria", "1,Juan"]);
+        setup_table(db_path, "b", &["2,Maria", "3,Ana"]);
+
+        let union = compound(
+            &select_all("a", None),
+            &[(operation(SetOperator::Union, false), select_all("b", None))],
+            &None,
+            None,
+            db_path,
+        );
+        assert_eq!(
+            union.unwrap().output.unwrap(),
+            "id,nombre\n1,Juan\n2,Maria\n3,Ana\n"
+        );
+
+        let union_all = compound(
+            &select_all("a", None),
+            &[(operation(SetOperator::Union, true), select_all("b", None))],
+            &None,
+            None,
+            db_path,
+        );
+        assert_eq!(
+            union_all.unwrap().output.unwrap(),
+            "id,nombre\n1,Juan\n2,Maria\n1,Juan\n2,Maria\n3,Ana\n"
+        );
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_intersect_and_except() {
+        let db_path = "./test_compound_db2";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, "a", &["1,Juan", "2,Maria", "2,Maria", "3,Ana"]);
+        setup_table(db_path, "b", &["2,Maria", "3,Ana"]);
+
+        let intersect = compound(
+            &select_all("a", None),
+            &[(
+                operation(SetOperator::Intersect, false),
+                select_all("b", None),
+            )],
+            &None,
+            None,
+            db_path,
+        );
+        assert_eq!(
+            intersect.unwrap().output.unwrap(),
+            "id,nombre\n2,Maria\n3,Ana\n"
+        );
+
+        let except_all = compound(
+            &select_all("a", None),
+            &[(operation(SetOperator::Except, true), select_all("b", None))],
+            &None,
+            None,
+            db_path,
+        );
+        assert_eq!(
+            except_all.unwrap().output.unwrap(),
+            "id,nombre\n1,Juan\n2,Maria\n"
+        );
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_compound_order_and_limit_apply_to_combined_result() {
+        let db_path = "./test_compound_db3";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, "a", &["3,Ana", "1,Juan"]);
+        setup_table(db_path, "b", &["2,Maria", "4,Jose"]);
+
+        let where_st = Some(Condition::Comparison(
+            "id".to_string(),
+            ComparisonOp::Lt,
+            "4".to_string(),
+        ));
+        let order = Some(vec![OrderBy {
+            column: "id".to_string(),
+            direction: Direction::Descending,
+        }]);
+        let result = compound(
+            &select_all("a", None),
+            &[(
+                operation(SetOperator::Union, false),
+                select_all("b", where_st),
+            )],
+            &order,
+            Some(2),
+            db_path,
+        );
+        assert_eq!(
+            result.unwrap().output.unwrap(),
+            "id,nombre\n3,Ana\n2,Maria\n"
+        );
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_compound_column_count_mismatch() {
+        let db_path = "./test_compound_db4";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, "a", &["1,Juan"]);
+        setup_table(db_path, "b", &["2,Maria"]);
+
+        let only_id = Commands::Select {
+            headers: vec!["id".to_string()],
+            tables: vec!["b".to_string()],
+            where_st: None,
+            order: None,
+            limit: None,
+            windows: Vec::new(),
+        };
+        let result = compound(
+            &select_all("a", None),
+            &[(operation(SetOperator::Union, false), only_id)],
+            &None,
+            None,
+            db_path,
+        );
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
@@ -0,0 +1,176 @@
+use super::foreign_keys::validate_references;
+use crate::command_result::{CommandKind, CommandResult};
+use crate::errors::ErrorType;
+use crate::index::TableIndex;
+use crate::schema::TableSchema;
+use std::{fs, path::Path};
+
+/// Crea el archivo de la tabla con el header de las columnas definidas y guarda su esquema
+pub fn create_table(
+    table: &str,
+    schema: &TableSchema,
+    if_not_exists: bool,
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let csv_table = format!("{db_path}/{table}.csv");
+    let result = CommandResult::new(CommandKind::CreateTable);
+
+    if Path::new(&csv_table).exists() {
+        if if_not_exists {
+            return Ok(result);
+        }
+        return Err(ErrorType::InvalidTable(format!(
+            "Table '{table}' already exists"
+        )));
+    }
+
+    for column in &schema.columns {
+        // el valor por defecto se guarda tal cual en cada fila del csv
+        if let Some(default) = column
+            .default
+            .as_ref()
+            .filter(|d| !column.data_type.is_valid(d) || d.contains([',', '\n', '\r']))
+        {
+            return Err(ErrorType::InvalidColumn(format!(
+                "Invalid default '{default}' for column '{}' of type {}",
+                column.name, column.data_type
+            )));
+        }
+        // el CHECK solo puede comparar columnas de la tabla
+        let check_columns = column
+            .check
+            .iter()
+            .flat_map(|check| check.condition.columns());
+        for name in check_columns {
+            if schema.column(name).is_none() {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Column '{name}' in CHECK of column '{}' not found in the table",
+                    column.name
+                )));
+            }
+        }
+    }
+
+    validate_references(table, schema, db_path)?;
+
+    let header: Vec<&str> = schema
+        .columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect();
+    fs::write(&csv_table, format!("{}\n", header.join(",")))
+        .map_err(|e| ErrorType::OtherError(format!("creating table {e} failed")))?;
+
+    schema.save(table, db_path)?;
+
+    Ok(result)
+}
+
+/// Arma el indice de la columna leyendo la tabla y lo guarda junto a ella
+pub fn create_index(
+    table: &str,
+    column: &str,
+    if_not_exists: bool,
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let result = CommandResult::new(CommandKind::CreateIndex);
+    if !Path::new(&format!("{db_path}/{table}.csv")).exists() {
+        return Err(ErrorType::InvalidTable(format!(
+            "Table '{table}' doesn't exist"
+        )));
+    }
+    if Path::new(&TableIndex::path(table, column, db_path)).exists() {
+        if if_not_exists {
+            return Ok(result);
+        }
+        return Err(ErrorType::InvalidColumn(format!(
+            "Index on column '{column}' of table '{table}' already exists"
+        )));
+    }
+
+    TableIndex::build(table, column, db_path)?.save(table, db_path)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_column_definition;
+    use crate::schema::{ColumnDefinition, DataType, UniqueConstraint};
+
+    #[test]
+    fn test_create_table() {
+        let db_path = "./test_create_db1";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+
+        let mut schema = TableSchema::new(vec![
+            ColumnDefinition::new("id", DataType::Integer),
+            ColumnDefinition::new("nombre", DataType::Text),
+        ]);
+        schema.keys.push(UniqueConstraint {
+            columns: vec!["id".to_string()],
+            primary_key: true,
+        });
+        assert!(create_table("clientes", &schema, false, db_path).is_ok());
+
+        let content =
+            fs::read_to_string(format!("{db_path}/clientes.csv")).expect("Failed to read table");
+        assert_eq!(content, "id,nombre\n");
+        let loaded = TableSchema::load("clientes", db_path).expect("Failed to load schema");
+        assert_eq!(loaded.as_ref(), Some(&schema));
+
+        // si ya existe solo falla sin IF NOT EXISTS
+        let result = create_table("clientes", &schema, false, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidTable(_))));
+        assert!(create_table("clientes", &TableSchema::new(Vec::new()), true, db_path).is_ok());
+
+        let content =
+            fs::read_to_string(format!("{db_path}/clientes.csv")).expect("Failed to read table");
+        assert_eq!(content, "id,nombre\n");
+
+        // el CHECK compara una columna que no existe
+        let schema = TableSchema::new(vec![
+            parse_column_definition("a INTEGER CHECK (zzz > 0)").unwrap()
+        ]);
+        let result = create_table("x", &schema, false, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        // el valor por defecto partiria las filas del csv
+        let mut a = ColumnDefinition::new("a", DataType::Text);
+        a.default = Some("x,y".to_string());
+        let result = create_table("u2", &TableSchema::new(vec![a]), false, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+        assert!(!Path::new(&format!("{db_path}/x.csv")).exists());
+        assert!(!Path::new(&format!("{db_path}/u2.csv")).exists());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_create_index() {
+        let db_path = "./test_create_db2";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        fs::write(format!("{db_path}/ordenes.csv"), "id,id_cliente\n1,2\n")
+            .expect("Failed to write table");
+
+        assert!(create_index("ordenes", "id_cliente", false, db_path).is_ok());
+        assert!(Path::new(&format!("{db_path}/ordenes.id_cliente.idx")).exists());
+
+        // si ya existe solo falla sin IF NOT EXISTS
+        assert!(matches!(
+            create_index("ordenes", "id_cliente", false, db_path),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(create_index("ordenes", "id_cliente", true, db_path).is_ok());
+        assert!(matches!(
+            create_index("ordenes", "noexiste", false, db_path),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            create_index("noexiste", "id", false, db_path),
+            Err(ErrorType::InvalidTable(_))
+        ));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
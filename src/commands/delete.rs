@@ -1,50 +1,129 @@
+use super::foreign_keys::{referencing_tables, DeletePlan};
 use super::utils_commands::{
-    create_aux_file, create_row_values_map, create_table_list, open_csv_reader, should_filter,
+    compile_where, create_aux_file, create_table_list, open_csv_reader, should_filter,
+    ReturningRows,
 };
-use crate::condition::Condition;
+use crate::command_result::{CommandKind, CommandResult};
+use crate::condition::{Condition, Simplified};
 use crate::errors::ErrorType;
-use std::{io::BufRead, io::Write};
+use crate::index::{index_scan, rebuild_indexes};
+use std::io::{BufRead, Seek, Write};
 
 pub fn delete(
     tables: &[String],
     where_st: &Option<Condition>,
+    returning: &Option<Vec<String>>,
     db_path: &str,
-) -> Result<Option<String>, ErrorType> {
+) -> Result<CommandResult, ErrorType> {
     let table_list = create_table_list(tables, db_path)?;
+    let mut result = CommandResult::new(CommandKind::Delete);
+    let mut output = String::new();
+
+    // si el where siempre se cumple se borran todas las filas y si nunca se cumple no se
+    // recorre la tabla
+    let (simplified, always_false) = match where_st.clone().map(Condition::simplify) {
+        Some(Simplified::Condition(condition)) => (Some(condition), false),
+        Some(Simplified::Constant(value)) => (None, !value),
+        None => (None, false),
+    };
 
     for table in table_list {
+        let (_, table_header, _, _) = open_csv_reader(&table, db_path)
+            .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+        // las columnas del where se verifican aunque la simplificacion las descarte
+        if let Some(condition) = where_st {
+            condition.check_columns(&table_header)?;
+        }
+        let where_st = &simplified;
+
+        if always_false {
+            if let Some(returning_rows) = ReturningRows::new(&table_header, returning)? {
+                returning_rows.print(&mut output);
+            }
+            result.add_table_rows(&table, 0);
+            continue;
+        }
+
+        // si otras tablas la referencian se borra en memoria junto a las filas que la referencian
+        if !referencing_tables(&table, db_path)?.is_empty() {
+            let mut returning_rows = ReturningRows::new(&table_header, returning)?;
+
+            let mut plan = DeletePlan::new(db_path);
+            let deleted = plan.delete(&table, where_st)?;
+            plan.write()?;
+
+            if let Some(returning_rows) = &mut returning_rows {
+                for row in &deleted {
+                    returning_rows.push(row);
+                }
+                returning_rows.print(&mut output);
+            }
+            result.add_table_rows(&table, deleted.len());
+            continue;
+        }
+
         // abro archivo y auxiliar
-        let (mut reader, table_header, _) = open_csv_reader(&table, db_path)
+        let (mut reader, table_header, _, schema) = open_csv_reader(&table, db_path)
             .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+        let predicate = compile_where(where_st, &table_header, schema.as_ref())?;
+
+        let mut returning_rows = ReturningRows::new(&table_header, returning)?;
 
         let (mut aux_file, csv_table, aux_table) = create_aux_file(&table, db_path, &table_header)
             .map_err(|e| ErrorType::OtherError(format!("creating auxiliary file {e} failed")))?;
 
+        // con un indice sobre el where solo se evaluan las filas candidatas
+        let candidates = index_scan(&table, where_st, db_path)?;
+        let mut offset = reader
+            .stream_position()
+            .map_err(|e| ErrorType::OtherError(format!("Reading line failed {e}")))?;
+
         // evaluo la fila por la condicion where
+        let mut deleted_rows = 0;
         let mut buffer = String::new();
-        while reader
-            .read_line(&mut buffer)
-            .map_err(|e| ErrorType::OtherError(format!("Reading line failed {e}")))?
-            > 0
-        {
-            let row_values_map = create_row_values_map(&table_header, &buffer);
+        loop {
+            let bytes = reader
+                .read_line(&mut buffer)
+                .map_err(|e| ErrorType::OtherError(format!("Reading line failed {e}")))?;
+            if bytes == 0 {
+                break;
+            }
+            let fields: Vec<&str> = buffer.trim().split(',').collect();
 
-            let should_delete = should_filter(where_st, &row_values_map);
+            let should_delete = candidates
+                .as_ref()
+                .is_none_or(|candidates| candidates.binary_search(&offset).is_ok())
+                && should_filter(predicate.as_ref(), &fields);
+            offset += bytes as u64;
 
             // si no hay que borrar lo escribo en el auxiliar
+            let t_buffer = buffer.trim();
             if !should_delete {
-                let t_buffer = buffer.trim();
                 writeln!(aux_file, "{t_buffer}").map_err(|e| {
                     ErrorType::OtherError(format!("writing to auxiliary file {e} failed"))
                 })?;
+            } else {
+                deleted_rows += 1;
+                if let Some(returning_rows) = &mut returning_rows {
+                    let deleted_row: Vec<String> =
+                        t_buffer.split(',').map(ToString::to_string).collect();
+                    returning_rows.push(&deleted_row);
+                }
             }
             buffer.clear(); // Limpiar buffer para la próxima línea
         }
 
         std::fs::rename(&aux_table, &csv_table)
             .map_err(|e| ErrorType::OtherError(format!("renaming file {e} failed")))?;
+        rebuild_indexes(&table, db_path)?;
+
+        if let Some(returning_rows) = returning_rows {
+            returning_rows.print(&mut output);
+        }
+        result.add_table_rows(&table, deleted_rows);
     }
-    Ok(None)
+    result.output = returning.as_ref().map(|_| output);
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -86,7 +165,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -104,7 +183,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let delete_result = delete(&tables, &where_cond, db_path);
+        let delete_result = delete(&tables, &where_cond, &None, db_path);
 
         assert!(delete_result.is_ok(), "Delete failed: {:?}", delete_result);
 
@@ -127,7 +206,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -145,7 +224,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let delete_result = delete(&tables, &where_cond, db_path);
+        let delete_result = delete(&tables, &where_cond, &None, db_path);
 
         assert!(delete_result.is_ok(), "Delete failed: {:?}", delete_result);
 
@@ -169,7 +248,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -189,7 +268,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let delete_result = delete(&tables, &where_cond, db_path);
+        let delete_result = delete(&tables, &where_cond, &None, db_path);
 
         assert!(delete_result.is_ok(), "Delete failed: {:?}", delete_result);
 
@@ -211,11 +290,11 @@ mod tests {
         let table_name = "empty_table";
 
         // Crear una tabla vacía
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             None,
         ); // Sin filas
 
@@ -226,7 +305,7 @@ mod tests {
         )); // No hay filas
 
         let tables = vec![table_name.to_string()];
-        let delete_result = delete(&tables, &where_condition, db_path);
+        let delete_result = delete(&tables, &where_condition, &None, db_path);
 
         assert!(delete_result.is_ok(), "Delete failed: {:?}", delete_result);
 
@@ -248,11 +327,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&[
                 &["1".to_string(), "Juan".to_string()],
                 &["2".to_string(), "Maria".to_string()],
@@ -266,7 +345,7 @@ mod tests {
         )); // No hay id 3
 
         let tables = vec![table_name.to_string()];
-        let delete_result = delete(&tables, &where_condition, db_path);
+        let delete_result = delete(&tables, &where_condition, &None, db_path);
 
         assert!(delete_result.is_ok(), "Delete failed: {:?}", delete_result);
 
@@ -289,11 +368,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&[
                 &["1".to_string(), "Juan".to_string()],
                 &["2".to_string(), "Maria".to_string()],
@@ -302,7 +381,7 @@ mod tests {
 
         // sin condición where
         let tables = vec![table_name.to_string()];
-        let delete_result = delete(&tables, &None, db_path); // Sin condición
+        let delete_result = delete(&tables, &None, &None, db_path); // Sin condición
 
         assert!(delete_result.is_ok(), "Delete failed: {:?}", delete_result);
 
@@ -317,4 +396,65 @@ mod tests {
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
     }
+
+    #[test]
+    fn test_delete_with_returning() {
+        let db_path = "./test_delete_db10";
+        let table_name = "test_table";
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(
+            db_path,
+            table_name,
+            &["id".to_string(), "nombre".to_string()],
+            Some(&[
+                &["1".to_string(), "Juan".to_string()],
+                &["2".to_string(), "Maria".to_string()],
+            ]),
+        );
+
+        let where_cond = Some(Condition::Comparison(
+            "id".to_string(),
+            ComparisonOp::Eq,
+            "2".to_string(),
+        ));
+        let returning = Some(vec!["nombre".to_string()]);
+        let returning_ok = returning.clone();
+
+        let tables = vec![table_name.to_string()];
+        let delete_result = delete(&tables, &where_cond, &returning, db_path);
+        assert_eq!(
+            delete_result.unwrap().output,
+            Some("nombre\nMaria\n".to_string())
+        );
+
+        // una columna inexistente en el RETURNING es un error
+        let returning = Some(vec!["apellido".to_string()]);
+        let delete_result = delete(&tables, &None, &returning, db_path);
+        assert!(matches!(delete_result, Err(ErrorType::InvalidColumn(_))));
+
+        // y tambien en el where, sin tocar la tabla
+        let where_cond = Some(Condition::Comparison(
+            "apellido".to_string(),
+            ComparisonOp::Eq,
+            "Perez".to_string(),
+        ));
+        let delete_result = delete(&tables, &where_cond, &None, db_path);
+        assert!(matches!(delete_result, Err(ErrorType::InvalidColumn(_))));
+
+        // un where que nunca se cumple no recorre la tabla
+        let where_cond = Some(Condition::Comparison(
+            "1".to_string(),
+            ComparisonOp::Eq,
+            "2".to_string(),
+        ));
+        let delete_result = delete(&tables, &where_cond, &returning_ok, db_path).unwrap();
+        assert_eq!(delete_result.output, Some("nombre\n".to_string()));
+        let content = fs::read_to_string(format!("{db_path}/{table_name}.csv")).unwrap();
+        assert_eq!(content, "id,nombre\n1,Juan\n");
+        assert!(!std::path::Path::new(&format!("{db_path}/{table_name}_temp.csv")).exists());
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
 }
@@ -0,0 +1,73 @@
+use super::foreign_keys::referencing_tables;
+use crate::command_result::{CommandKind, CommandResult};
+use crate::errors::ErrorType;
+use crate::index::remove_indexes;
+use crate::schema::TableSchema;
+use std::{fs, path::Path};
+
+/// Elimina los archivos de las tablas junto a su esquema e indices. Si alguna no existe o esta
+/// referenciada por otra tabla no se elimina ninguna
+pub fn drop_table(
+    tables: &[String],
+    if_exists: bool,
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let mut existing_tables = Vec::new();
+    for table in tables {
+        let csv_table = format!("{db_path}/{table}.csv");
+        if Path::new(&csv_table).exists() {
+            existing_tables.push((table, csv_table));
+        } else if !if_exists {
+            return Err(ErrorType::InvalidTable(format!(
+                "Table '{table}' does not exist"
+            )));
+        }
+    }
+
+    // no se puede eliminar una tabla referenciada por otra que no se elimina
+    for (table, _) in &existing_tables {
+        for (child, _) in referencing_tables(table, db_path)? {
+            if !tables.contains(&child) {
+                return Err(ErrorType::InvalidTable(format!(
+                    "Table '{table}' is referenced by table '{child}'"
+                )));
+            }
+        }
+    }
+
+    for (table, csv_table) in existing_tables {
+        fs::remove_file(&csv_table)
+            .map_err(|e| ErrorType::OtherError(format!("removing table {e} failed")))?;
+
+        let schema_path = TableSchema::path(table, db_path);
+        if Path::new(&schema_path).exists() {
+            fs::remove_file(&schema_path)
+                .map_err(|e| ErrorType::OtherError(format!("removing schema {e} failed")))?;
+        }
+        remove_indexes(table, None, db_path)?;
+    }
+
+    Ok(CommandResult::new(CommandKind::DropTable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_table() {
+        let db_path = "./test_drop_db1";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        fs::write(format!("{db_path}/clientes.csv"), "id,nombre\n").expect("Failed to write");
+
+        let tables = ["clientes".to_string(), "ordenes".to_string()];
+        let result = drop_table(&tables, false, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidTable(_))));
+        assert!(Path::new(&format!("{db_path}/clientes.csv")).exists());
+
+        assert!(drop_table(&tables, true, db_path).is_ok());
+        assert!(!Path::new(&format!("{db_path}/clientes.csv")).exists());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
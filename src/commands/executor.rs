@@ -0,0 +1,592 @@
+use super::compound::apply_set_operation;
+use super::planner::{LogicalPlan, TableScan};
+use super::sort::{sort_memory, ExternalSorter, SortKey, DEFAULT_MERGE_FAN_IN};
+use super::utils_commands::{column_position, compile_where, open_csv_reader, should_filter};
+use crate::errors::ErrorType;
+use crate::index::{index_lookup, read_rows_at};
+use crate::rows::{Column, Rows};
+use crate::schema::{ColumnDefinition, TableSchema};
+use crate::window::apply_window_functions;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    io::{self, BufRead, Seek, Write},
+    iter,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Filas que produce un nodo del plan a medida que se le piden
+type RowIter = Box<dyn Iterator<Item = Result<Vec<String>, ErrorType>>>;
+
+/// Columnas de un nodo del plan, el esquema de la tabla de la que se leyeron y sus filas,
+/// que se producen recien al pedirlas
+struct NodeRows {
+    header: Vec<String>,
+    schema: Option<TableSchema>,
+    rows: RowIter,
+}
+
+/// Columnas y filas que produjo un nodo del plan
+struct PlanRows {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Medicion de un nodo, compartida con los iteradores que producen sus filas
+type SharedStats = Rc<RefCell<NodeStats>>;
+
+/// Medicion de un nodo del plan con EXPLAIN ANALYZE. El tiempo incluye el de sus entradas
+/// pero no el de los nodos que consumen sus filas
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NodeStats {
+    pub rows_in: usize,
+    pub rows_out: usize,
+    /// bytes leidos del csv, solo en la lectura de la tabla
+    pub bytes_read: u64,
+    /// archivos temporales que escribio el ordenamiento por no entrar en memoria
+    pub sort_runs: usize,
+    pub elapsed: Duration,
+    /// tiempo separando los campos de cada fila, en la lectura con filtro
+    pub parse_time: Option<Duration>,
+    /// tiempo evaluando las condiciones
+    pub eval_time: Option<Duration>,
+}
+
+/// Formatea la duracion en milisegundos
+fn millis(duration: Duration) -> String {
+    format!("{:.3} ms", duration.as_secs_f64() * 1000.0)
+}
+
+impl fmt::Display for NodeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(rows in={} out={}", self.rows_in, self.rows_out)?;
+        if self.bytes_read > 0 {
+            write!(f, ", bytes={}", self.bytes_read)?;
+        }
+        if self.sort_runs > 0 {
+            write!(f, ", runs={}", self.sort_runs)?;
+        }
+        write!(f, ", time={}", millis(self.elapsed))?;
+        if let Some(parse_time) = self.parse_time {
+            write!(f, ", parse={}", millis(parse_time))?;
+        }
+        if let Some(eval_time) = self.eval_time {
+            write!(f, ", evaluate={}", millis(eval_time))?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Ejecuta la funcion sumando su duracion al total, solo si se esta midiendo
+fn timed<T>(total: &mut Option<Duration>, f: impl FnOnce() -> T) -> T {
+    match total {
+        Some(total) => {
+            let start = Instant::now();
+            let result = f();
+            *total += start.elapsed();
+            result
+        }
+        None => f(),
+    }
+}
+
+/// Cuenta las filas que produce un nodo y suma el tiempo que tarda en producirlas
+struct Measured {
+    rows: RowIter,
+    node: SharedStats,
+}
+
+impl Iterator for Measured {
+    type Item = Result<Vec<String>, ErrorType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = Instant::now();
+        let row = self.rows.next();
+        let mut node = self.node.borrow_mut();
+        node.elapsed += start.elapsed();
+        if let Some(Ok(_)) = row {
+            node.rows_out += 1;
+        }
+        row
+    }
+}
+
+/// Cuenta en `rows_in` las filas que el nodo le pide a su entrada
+fn counted(
+    rows: RowIter,
+    node: &SharedStats,
+) -> impl Iterator<Item = Result<Vec<String>, ErrorType>> {
+    let node = Rc::clone(node);
+    rows.inspect(move |_| node.borrow_mut().rows_in += 1)
+}
+
+/// Filas que se calculan recien al pedir la primera, para los nodos que necesitan todas las
+/// filas de su entrada antes de producir alguna
+fn deferred(f: impl FnOnce() -> Result<RowIter, ErrorType> + 'static) -> RowIter {
+    let mut f = Some(f);
+    let mut rows: Option<RowIter> = None;
+    Box::new(iter::from_fn(move || {
+        if let Some(f) = f.take() {
+            match f() {
+                Ok(computed) => rows = Some(computed),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        rows.as_mut()?.next()
+    }))
+}
+
+/// Arma las filas de cada nodo del plan como un iterador que le pide filas a su entrada a
+/// medida que se las piden a el, asi un LIMIT deja de leer al alcanzarlo. Solo las ventanas,
+/// el orden y las operaciones de conjuntos juntan todas las filas de su entrada
+struct Executor<'p> {
+    db_path: &'p str,
+    /// con EXPLAIN ANALYZE se mide cada nodo, en el orden en que se recorre el plan
+    stats: Option<Vec<SharedStats>>,
+    /// bytes de filas que el ORDER BY ordena en memoria antes de volcarlas a disco
+    sort_memory: usize,
+    /// archivos temporales que se mezclan a la vez al terminar de ordenar
+    merge_fan_in: usize,
+}
+
+impl<'p> Executor<'p> {
+    fn new(db_path: &'p str, analyze: bool) -> Self {
+        Executor {
+            db_path,
+            stats: analyze.then(Vec::new),
+            sort_memory: sort_memory(),
+            merge_fan_in: DEFAULT_MERGE_FAN_IN,
+        }
+    }
+
+    /// Ejecuta el nodo juntando todas sus filas
+    fn run(&mut self, plan: &LogicalPlan) -> Result<PlanRows, ErrorType> {
+        let NodeRows { header, rows, .. } = self.open(plan)?;
+        Ok(PlanRows {
+            header,
+            rows: rows.collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Mediciones de los nodos, en el orden en que se recorre el plan
+    fn stats(&self) -> Vec<NodeStats> {
+        self.stats
+            .iter()
+            .flatten()
+            .map(|node| node.borrow().clone())
+            .collect()
+    }
+
+    /// Abre el nodo y sus entradas sin producir filas. Si se esta midiendo, el tiempo del
+    /// nodo suma el de abrirlo y el de producir cada fila
+    fn open(&mut self, plan: &LogicalPlan) -> Result<NodeRows, ErrorType> {
+        let node = SharedStats::default();
+        let Some(stats) = &mut self.stats else {
+            return self.open_node(plan, &node);
+        };
+        stats.push(Rc::clone(&node));
+        let start = Instant::now();
+        let mut result = self.open_node(plan, &node)?;
+        node.borrow_mut().elapsed += start.elapsed();
+        result.rows = Box::new(Measured {
+            rows: result.rows,
+            node,
+        });
+        Ok(result)
+    }
+
+    fn open_node(&mut self, plan: &LogicalPlan, node: &SharedStats) -> Result<NodeRows, ErrorType> {
+        let analyze = self.stats.is_some();
+        match plan {
+            LogicalPlan::Scan(scan) => self.scan_table(scan, node),
+            LogicalPlan::Filter { condition, input } => {
+                let input = self.open(input)?;
+                let predicate = condition.compile(&input.header, input.schema.as_ref())?;
+                if analyze {
+                    node.borrow_mut().eval_time = Some(Duration::ZERO);
+                }
+                let stats = Rc::clone(node);
+                let rows = counted(input.rows, node).filter(move |row| {
+                    let Ok(row) = row else { return true };
+                    timed(&mut stats.borrow_mut().eval_time, || {
+                        predicate.evaluate(row)
+                    })
+                });
+                Ok(NodeRows {
+                    rows: Box::new(rows),
+                    ..input
+                })
+            }
+            LogicalPlan::Window { functions, input } => {
+                let input = self.open(input)?;
+                let mut header = input.header.clone();
+                header.extend(functions.iter().map(|window| window.alias.to_string()));
+                // las columnas calculadas se agregan al esquema para compararlas con su tipo
+                let columns: Vec<ColumnDefinition> = functions
+                    .iter()
+                    .filter_map(|window| {
+                        let data_type = window.data_type(input.schema.as_ref())?;
+                        Some(ColumnDefinition::new(&window.alias, data_type))
+                    })
+                    .collect();
+                let schema = match input.schema {
+                    Some(mut schema) => {
+                        schema.columns.extend(columns);
+                        Some(schema)
+                    }
+                    None => (!columns.is_empty()).then(|| TableSchema::new(columns)),
+                };
+                let functions = functions.to_vec();
+                let input_header = input.header;
+                let output_header = header.clone();
+                let input_rows = counted(input.rows, node);
+                let rows = deferred(move || {
+                    let mut window_rows: Vec<HashMap<String, String>> = input_rows
+                        .map(|row| row.map(|row| input_header.iter().cloned().zip(row).collect()))
+                        .collect::<Result<_, _>>()?;
+                    apply_window_functions(&mut window_rows, &functions);
+                    Ok(Box::new(window_rows.into_iter().map(
+                        move |row_values_map| {
+                            Ok(output_header
+                                .iter()
+                                .map(|header| row_values_map[header].to_string())
+                                .collect())
+                        },
+                    )))
+                });
+                Ok(NodeRows {
+                    header,
+                    schema,
+                    rows,
+                })
+            }
+            LogicalPlan::Project { columns, input } => {
+                let input = self.open(input)?;
+                let positions = columns
+                    .iter()
+                    .map(|column| column_position(&input.header, column))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let rows = counted(input.rows, node).map(move |row| {
+                    row.map(|row| positions.iter().map(|&i| row[i].to_string()).collect())
+                });
+                Ok(NodeRows {
+                    header: columns.clone(),
+                    schema: input.schema,
+                    rows: Box::new(rows),
+                })
+            }
+            LogicalPlan::Sort { order, input } => {
+                let input = self.open(input)?;
+                let key = SortKey::new(&input.header, order, input.schema.as_ref())?;
+                let db_path = self.db_path.to_string();
+                let (sort_memory, merge_fan_in) = (self.sort_memory, self.merge_fan_in);
+                let stats = Rc::clone(node);
+                let input_rows = counted(input.rows, node);
+                let rows = deferred(move || {
+                    // las filas se ordenan a medida que llegan, volcando a disco las que no entran
+                    let mut sorter = ExternalSorter::new(key, &db_path, sort_memory, merge_fan_in);
+                    for row in input_rows {
+                        sorter.push(row?)?;
+                    }
+                    stats.borrow_mut().sort_runs = sorter.runs();
+                    Ok(Box::new(sorter.finish()?))
+                });
+                Ok(NodeRows { rows, ..input })
+            }
+            LogicalPlan::Limit { limit, input } => {
+                // al alcanzar el limite no se le piden mas filas a la entrada
+                let input = self.open(input)?;
+                let rows = counted(input.rows, node).take(*limit);
+                Ok(NodeRows {
+                    rows: Box::new(rows),
+                    ..input
+                })
+            }
+            LogicalPlan::SetOperation {
+                operation,
+                left,
+                right,
+            } => {
+                let left = self.open(left)?;
+                let right = self.open(right)?;
+                if left.header.len() != right.header.len() {
+                    return Err(ErrorType::InvalidColumn(
+                        "Each SELECT in a set operation must have the same number of columns"
+                            .into(),
+                    ));
+                }
+                // cada columna conserva su tipo si los dos lados lo declaran igual
+                let columns: Vec<ColumnDefinition> = left
+                    .header
+                    .iter()
+                    .zip(&right.header)
+                    .filter_map(|(left_column, right_column)| {
+                        let data_type = left.schema.as_ref()?.data_type(left_column)?;
+                        (right.schema.as_ref()?.data_type(right_column)? == data_type)
+                            .then(|| ColumnDefinition::new(left_column, data_type))
+                    })
+                    .collect();
+                let schema = (!columns.is_empty()).then(|| TableSchema::new(columns));
+                let operation = (*operation).clone();
+                let stats = Rc::clone(node);
+                let (left_rows, right_rows) = (left.rows, right.rows);
+                let rows = deferred(move || {
+                    let left = left_rows.collect::<Result<Vec<_>, _>>()?;
+                    let right = right_rows.collect::<Result<Vec<_>, _>>()?;
+                    stats.borrow_mut().rows_in = left.len() + right.len();
+                    let rows = apply_set_operation(left, right, &operation);
+                    Ok(Box::new(rows.into_iter().map(Ok)))
+                });
+                Ok(NodeRows {
+                    header: left.header,
+                    schema,
+                    rows,
+                })
+            }
+        }
+    }
+
+    /// Lee las filas de la tabla que cumplen el filtro. Con un indice solo se leen las filas
+    /// candidatas, en el orden de la tabla, y con un limite se deja de leer al alcanzarlo.
+    /// De cada fila solo se pasan las columnas pedidas
+    fn scan_table(&self, scan: &TableScan, node: &SharedStats) -> Result<NodeRows, ErrorType> {
+        let TableScan {
+            table,
+            columns,
+            filter,
+            index,
+            limit,
+        } = scan;
+        let read_error = |e: io::Error| ErrorType::OtherError(format!("Error reading line: {e}"));
+        let (mut reader, table_header, _, schema) = open_csv_reader(table, self.db_path)
+            .map_err(|e| ErrorType::InvalidTable(format!("Error opening table: {e}")))?;
+        let (header, positions) = match columns {
+            Some(columns) => {
+                let positions = columns
+                    .iter()
+                    .map(|column| column_position(&table_header, column))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (columns.clone(), positions)
+            }
+            None => (table_header.clone(), (0..table_header.len()).collect()),
+        };
+        let predicate = compile_where(filter, &table_header, schema.as_ref())?;
+        if self.stats.is_some() {
+            let mut node = node.borrow_mut();
+            // el header ya se leyo al abrir la tabla
+            node.bytes_read = reader.stream_position().map_err(read_error)?;
+            if filter.is_some() {
+                node.parse_time = Some(Duration::ZERO);
+                node.eval_time = Some(Duration::ZERO);
+            }
+        }
+
+        let indexed_rows = match index {
+            Some(comparison) => index_lookup(table, comparison, self.db_path)?
+                .map(|offsets| read_rows_at(table, &offsets, self.db_path))
+                .transpose()?,
+            None => None,
+        };
+        let mut lines: Box<dyn Iterator<Item = Result<String, ErrorType>>> = match indexed_rows {
+            Some(lines) => Box::new(lines.into_iter().map(Ok)),
+            None => Box::new(iter::from_fn(move || {
+                let mut line = String::new();
+                match reader.read_line(&mut line) {
+                    Ok(0) => None,
+                    Ok(_) => Some(Ok(line)),
+                    Err(e) => Some(Err(read_error(e))),
+                }
+            })),
+        };
+
+        let node = Rc::clone(node);
+        let limit = *limit;
+        let mut selected_rows = 0;
+        let rows = iter::from_fn(move || {
+            while limit.is_none_or(|limit| selected_rows < limit) {
+                let line = match lines.next()? {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(e)),
+                };
+                let mut node = node.borrow_mut();
+                node.rows_in += 1;
+                node.bytes_read += line.len() as u64;
+
+                let fields: Vec<&str> =
+                    timed(&mut node.parse_time, || line.trim().split(',').collect());
+                let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+                let selected = timed(&mut node.eval_time, || {
+                    should_filter(predicate.as_ref(), &fields)
+                });
+                if selected {
+                    selected_rows += 1;
+                    return Some(Ok(positions
+                        .iter()
+                        .map(|&i| field(i).to_string())
+                        .collect()));
+                }
+            }
+            None
+        });
+
+        Ok(NodeRows {
+            header,
+            schema,
+            rows: Box::new(rows),
+        })
+    }
+}
+
+/// Optimiza y ejecuta el plan, devuelve los headers y las filas resultantes
+#[allow(clippy::missing_errors_doc)]
+pub fn execute_plan(
+    plan: LogicalPlan,
+    db_path: &str,
+) -> Result<(Vec<String>, Vec<Vec<String>>), ErrorType> {
+    let plan = plan.optimize(db_path)?;
+    let mut executor = Executor::new(db_path, false);
+    let result = executor.run(&plan)?;
+    Ok((result.header, result.rows))
+}
+
+/// Optimiza el plan y devuelve sus filas junto al tipo declarado de cada columna. Las filas
+/// se producen a medida que se recorren
+#[allow(clippy::missing_errors_doc)]
+pub fn query_rows(plan: LogicalPlan, db_path: &str) -> Result<Rows, ErrorType> {
+    let plan = plan.optimize(db_path)?;
+    let NodeRows {
+        header,
+        schema,
+        rows,
+    } = Executor::new(db_path, false).open(&plan)?;
+    let columns = header
+        .into_iter()
+        .map(|name| Column {
+            data_type: schema.as_ref().and_then(|schema| schema.data_type(&name)),
+            name,
+        })
+        .collect();
+    Ok(Rows::new(columns, rows))
+}
+
+/// Optimiza y ejecuta el plan escribiendo el resultado en `out` a medida que se produce:
+/// primero el header y despues cada fila, separando los valores con comas.
+/// Devuelve la cantidad de filas escritas
+#[allow(clippy::missing_errors_doc)]
+pub fn write_plan(
+    plan: LogicalPlan,
+    db_path: &str,
+    out: &mut dyn Write,
+) -> Result<usize, ErrorType> {
+    let write_error = |e: io::Error| ErrorType::OtherError(format!("Error writing result: {e}"));
+    let plan = plan.optimize(db_path)?;
+    let NodeRows { header, rows, .. } = Executor::new(db_path, false).open(&plan)?;
+    writeln!(out, "{}", header.join(",")).map_err(write_error)?;
+    let mut written = 0;
+    for row in rows {
+        writeln!(out, "{}", row?.join(",")).map_err(write_error)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Ejecuta el plan ya optimizado midiendo cada nodo, en el orden en que se recorre el plan
+#[allow(clippy::missing_errors_doc)]
+pub fn analyze_plan(plan: &LogicalPlan, db_path: &str) -> Result<Vec<NodeStats>, ErrorType> {
+    let mut executor = Executor::new(db_path, true);
+    executor.run(plan)?;
+    Ok(executor.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{ComparisonOp, Condition};
+    use crate::order::{Direction, OrderBy};
+    use std::fs;
+
+    #[test]
+    fn test_analyze_plan_counts_rows_and_bytes() {
+        let db_path = "./test_executor_db1";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let content = "id,producto,cantidad\n101,Laptop,1\n102,Mouse,2\n103,Monitor,3\n";
+        fs::write(format!("{db_path}/ordenes.csv"), content).expect("Failed to write table");
+
+        let headers = vec!["producto".to_string()];
+        let where_st = Some(Condition::Comparison(
+            "cantidad".to_string(),
+            ComparisonOp::Gt,
+            "1".to_string(),
+        ));
+        let plan = LogicalPlan::select("ordenes", &headers, &where_st, &None, None, &[], db_path)
+            .unwrap()
+            .optimize(db_path)
+            .unwrap();
+        let stats = analyze_plan(&plan, db_path).unwrap();
+
+        // Project y despues el Scan con el filtro
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].rows_in, stats[0].rows_out), (2, 2));
+        assert_eq!((stats[1].rows_in, stats[1].rows_out), (3, 2));
+        assert_eq!(stats[1].bytes_read, content.len() as u64);
+        assert!(stats[1].parse_time.is_some() && stats[1].eval_time.is_some());
+        assert!(stats[0].parse_time.is_none());
+        assert!(stats[0].elapsed >= stats[1].elapsed);
+
+        // medir no cambia el resultado
+        let plan =
+            LogicalPlan::select("ordenes", &headers, &where_st, &None, None, &[], db_path).unwrap();
+        let (header, rows) = execute_plan(plan, db_path).unwrap();
+        assert_eq!(header, ["producto"]);
+        assert_eq!(rows, [["Mouse"], ["Monitor"]]);
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_sort_spills_to_disk_over_memory() {
+        let db_path = "./test_executor_db2";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let content = "id,producto,cantidad\n101,Laptop,3\n102,Mouse,1\n103,Monitor,2\n\
+                       104,Teclado,5\n105,Cable,4\n";
+        fs::write(format!("{db_path}/ordenes.csv"), content).expect("Failed to write table");
+
+        let headers = vec!["id".to_string(), "cantidad".to_string()];
+        let order = Some(vec![OrderBy {
+            column: "cantidad".to_string(),
+            direction: Direction::Descending,
+        }]);
+        let plan = LogicalPlan::select("ordenes", &headers, &None, &order, Some(4), &[], db_path)
+            .unwrap()
+            .optimize(db_path)
+            .unwrap();
+
+        // sin memoria para ordenar cada fila termina en su propio archivo temporal, y de a
+        // dos archivos la mezcla necesita varias pasadas
+        let mut executor = Executor {
+            db_path,
+            stats: Some(Vec::new()),
+            sort_memory: 0,
+            merge_fan_in: 2,
+        };
+        let result = executor.run(&plan).unwrap();
+        assert_eq!(result.header, ["id", "cantidad"]);
+        assert_eq!(
+            result.rows,
+            [["104", "5"], ["105", "4"], ["101", "3"], ["103", "2"]]
+        );
+        // el limite deja de pedir filas al ordenamiento al alcanzarlo
+        let stats = executor.stats();
+        assert_eq!((stats[0].rows_in, stats[0].rows_out), (4, 4));
+        assert_eq!(
+            (stats[1].rows_in, stats[1].rows_out, stats[1].sort_runs),
+            (5, 4, 5)
+        );
+
+        // los archivos temporales se borran al terminar
+        assert_eq!(fs::read_dir(db_path).unwrap().count(), 1);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
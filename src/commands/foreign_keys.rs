@@ -0,0 +1,600 @@
+use super::unique_keys::row_key;
+use super::utils_commands::{compile_where, open_csv_reader, should_filter, write_table_rows};
+use crate::condition::Condition;
+use crate::errors::ErrorType;
+use crate::schema::{ForeignKey, ReferentialAction, TableSchema};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::BufRead,
+};
+
+/// Devuelve las tablas del directorio que tienen un esquema declarado
+fn schema_tables(db_path: &str) -> Result<Vec<String>, ErrorType> {
+    let paths = fs::read_dir(db_path)
+        .map_err(|e| ErrorType::OtherError(format!("reading directory failed: {e}")))?;
+
+    let mut tables = Vec::new();
+    for path in paths {
+        let path = path
+            .map_err(|e| ErrorType::OtherError(format!("reading path failed: {e}")))?
+            .path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "schema")
+        {
+            if let Some(table) = path.file_stem().and_then(|stem| stem.to_str()) {
+                tables.push(table.to_string());
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// Devuelve las claves foraneas de otras tablas que referencian a la tabla
+pub fn referencing_tables(
+    table: &str,
+    db_path: &str,
+) -> Result<Vec<(String, ForeignKey)>, ErrorType> {
+    let mut references = Vec::new();
+    for child in schema_tables(db_path)? {
+        let Some(schema) = TableSchema::load(&child, db_path)? else {
+            continue;
+        };
+        for key in schema.foreign_key_constraints() {
+            if key.table == table {
+                references.push((child.to_string(), key));
+            }
+        }
+    }
+    Ok(references)
+}
+
+/// Aplica el cambio a las claves foraneas que referencian a la tabla y guarda los esquemas
+pub fn update_references<F>(table: &str, db_path: &str, update: F) -> Result<(), ErrorType>
+where
+    F: Fn(&mut ForeignKey),
+{
+    for child in schema_tables(db_path)? {
+        let Some(mut schema) = TableSchema::load(&child, db_path)? else {
+            continue;
+        };
+        let column_keys = schema
+            .columns
+            .iter_mut()
+            .filter_map(|column| column.references.as_mut());
+        let mut changed = false;
+        for key in column_keys.chain(schema.foreign_keys.iter_mut()) {
+            if key.table == table {
+                update(key);
+                changed = true;
+            }
+        }
+        if changed {
+            schema.save(&child, db_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Verifica que la tabla referenciada exista y que sus columnas sean una PRIMARY KEY o UNIQUE
+pub fn validate_references(
+    table: &str,
+    schema: &TableSchema,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    for key in schema.foreign_key_constraints() {
+        if key.table == table {
+            return Err(ErrorType::InvalidTable(format!(
+                "Table '{table}' can't reference itself in {key}"
+            )));
+        }
+        let parent =
+            TableSchema::load(&key.table, db_path)?.ok_or(ErrorType::InvalidTable(format!(
+                "Referenced table '{}' not found or without schema",
+                key.table
+            )))?;
+
+        let references: HashSet<&String> = key.references.iter().collect();
+        let is_unique = parent.unique_constraints().iter().any(|unique| {
+            unique.columns.len() == references.len()
+                && unique
+                    .columns
+                    .iter()
+                    .all(|column| references.contains(column))
+        });
+        if !is_unique {
+            return Err(ErrorType::InvalidColumn(format!(
+                "Referenced columns ({}) must be a PRIMARY KEY or UNIQUE of table '{}'",
+                key.references.join(", "),
+                key.table
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Devuelve la posicion de cada columna en el header
+fn column_indexes(
+    table: &str,
+    header_index: &HashMap<String, usize>,
+    columns: &[String],
+) -> Result<Vec<usize>, ErrorType> {
+    columns
+        .iter()
+        .map(|column| {
+            header_index
+                .get(column)
+                .copied()
+                .ok_or(ErrorType::InvalidColumn(format!(
+                    "Column '{column}' not found in table '{table}'"
+                )))
+        })
+        .collect()
+}
+
+/// Lee las claves de todas las filas de la tabla en las columnas dadas
+fn load_keys(
+    table: &String,
+    columns: &[String],
+    db_path: &str,
+) -> Result<HashSet<Vec<String>>, ErrorType> {
+    let (mut reader, _, header_index, _) = open_csv_reader(table, db_path)
+        .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+    let indexes = column_indexes(table, &header_index, columns)?;
+
+    let mut keys = HashSet::new();
+    let mut buffer = String::new();
+    while reader
+        .read_line(&mut buffer)
+        .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?
+        > 0
+    {
+        let row: Vec<String> = buffer.trim().split(',').map(ToString::to_string).collect();
+        if let Some(key) = row_key(&indexes, &row) {
+            keys.insert(key);
+        }
+        buffer.clear();
+    }
+    Ok(keys)
+}
+
+/// Claves de las tablas referenciadas por las claves foraneas de una tabla.
+/// Se leen una vez y despues cada fila escrita se verifica contra ellas
+pub struct ForeignKeyCheck {
+    table: String,
+    /// clave foranea, posicion de sus columnas en el header y claves de la tabla referenciada
+    constraints: Vec<(ForeignKey, Vec<usize>, HashSet<Vec<String>>)>,
+}
+
+impl ForeignKeyCheck {
+    pub fn new(
+        table: &str,
+        schema: Option<&TableSchema>,
+        header_index: &HashMap<String, usize>,
+        db_path: &str,
+    ) -> Result<Self, ErrorType> {
+        let mut constraints = Vec::new();
+        for key in schema
+            .map(TableSchema::foreign_key_constraints)
+            .unwrap_or_default()
+        {
+            let indexes = column_indexes(table, header_index, &key.columns)?;
+            let parent_keys = load_keys(&key.table, &key.references, db_path)?;
+            constraints.push((key, indexes, parent_keys));
+        }
+
+        Ok(Self {
+            table: table.to_string(),
+            constraints,
+        })
+    }
+
+    /// Verifica que la fila referencie filas existentes. Las claves con NULL no se verifican
+    pub fn check(&self, row: &[String]) -> Result<(), ErrorType> {
+        for (key, indexes, parent_keys) in &self.constraints {
+            if let Some(row_key) = row_key(indexes, row) {
+                if !parent_keys.contains(&row_key) {
+                    return Err(ErrorType::ConstraintViolation(format!(
+                        "Key ({})=({}) of table '{}' is not present in table '{}'",
+                        key.columns.join(", "),
+                        row_key.join(", "),
+                        self.table,
+                        key.table
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Clave foranea de otra tabla con las claves referenciadas que cambia un UPDATE
+struct Reference {
+    child: String,
+    key: ForeignKey,
+    /// posicion de las columnas referenciadas en el header
+    indexes: Vec<usize>,
+    removed: HashSet<Vec<String>>,
+    added: HashSet<Vec<String>>,
+}
+
+/// Claves referenciadas por otras tablas que un UPDATE cambia. Cambiar una clave que
+/// sigue referenciada no esta permitido, sin importar la accion del ON DELETE
+pub struct ReferencedKeys {
+    table: String,
+    references: Vec<Reference>,
+}
+
+impl ReferencedKeys {
+    pub fn new(
+        table: &str,
+        header_index: &HashMap<String, usize>,
+        db_path: &str,
+    ) -> Result<Self, ErrorType> {
+        let mut references = Vec::new();
+        for (child, key) in referencing_tables(table, db_path)? {
+            let indexes = column_indexes(table, header_index, &key.references)?;
+            references.push(Reference {
+                child,
+                key,
+                indexes,
+                removed: HashSet::new(),
+                added: HashSet::new(),
+            });
+        }
+
+        Ok(Self {
+            table: table.to_string(),
+            references,
+        })
+    }
+
+    /// Indica si ninguna tabla referencia a la tabla
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    /// Registra las claves de una fila antes y despues de actualizarla
+    pub fn update(&mut self, old_row: &[String], new_row: &[String]) {
+        for reference in &mut self.references {
+            let old_key = row_key(&reference.indexes, old_row);
+            let new_key = row_key(&reference.indexes, new_row);
+            if old_key == new_key {
+                continue;
+            }
+            if let Some(old_key) = old_key {
+                reference.removed.insert(old_key);
+            }
+            if let Some(new_key) = new_key {
+                reference.added.insert(new_key);
+            }
+        }
+    }
+
+    /// Verifica que ninguna tabla siga referenciando las claves que dejaron de existir
+    pub fn finish(&self, db_path: &str) -> Result<(), ErrorType> {
+        for Reference {
+            child,
+            key,
+            removed,
+            added,
+            ..
+        } in &self.references
+        {
+            // una clave puede pasar de una fila a otra en el mismo UPDATE
+            let removed: HashSet<&Vec<String>> = removed.difference(added).collect();
+            if removed.is_empty() {
+                continue;
+            }
+            let child_keys = load_keys(child, &key.columns, db_path)?;
+            if let Some(referenced) = removed.into_iter().find(|key| child_keys.contains(*key)) {
+                return Err(ErrorType::ConstraintViolation(format!(
+                    "Key ({})=({}) of table '{}' is still referenced by table '{child}'",
+                    key.references.join(", "),
+                    referenced.join(", "),
+                    self.table
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Contenido de una tabla cargada en memoria
+struct TableRows {
+    header: Vec<String>,
+    header_index: HashMap<String, usize>,
+    rows: Vec<Vec<String>>,
+    schema: Option<TableSchema>,
+    changed: bool,
+}
+
+/// Cambios de un DELETE sobre una tabla referenciada y sobre las tablas que la
+/// referencian. Se arman en memoria y solo se escriben si todas las acciones se cumplen
+pub struct DeletePlan<'a> {
+    db_path: &'a str,
+    tables: HashMap<String, TableRows>,
+}
+
+impl<'a> DeletePlan<'a> {
+    pub fn new(db_path: &'a str) -> Self {
+        Self {
+            db_path,
+            tables: HashMap::new(),
+        }
+    }
+
+    /// Carga la tabla en memoria si todavia no se cargo
+    fn load(&mut self, table: &String) -> Result<&mut TableRows, ErrorType> {
+        if !self.tables.contains_key(table) {
+            let (mut reader, header, header_index, schema) =
+                open_csv_reader(table, self.db_path)
+                    .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+            let mut rows = Vec::new();
+            let mut buffer = String::new();
+            while reader
+                .read_line(&mut buffer)
+                .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?
+                > 0
+            {
+                rows.push(buffer.trim().split(',').map(ToString::to_string).collect());
+                buffer.clear();
+            }
+            let table_rows = TableRows {
+                header,
+                header_index,
+                rows,
+                schema,
+                changed: false,
+            };
+            self.tables.insert(table.to_string(), table_rows);
+        }
+        self.tables
+            .get_mut(table)
+            .ok_or(ErrorType::InvalidTable(format!(
+                "Table '{table}' not found"
+            )))
+    }
+
+    /// Borra las filas que cumplen la condicion y aplica el ON DELETE de las claves
+    /// foraneas que las referencian. Devuelve las filas borradas de la tabla
+    pub fn delete(
+        &mut self,
+        table: &String,
+        where_st: &Option<Condition>,
+    ) -> Result<Vec<Vec<String>>, ErrorType> {
+        let table_rows = self.load(table)?;
+        let predicate = compile_where(where_st, &table_rows.header, table_rows.schema.as_ref())?;
+        let (deleted, kept): (Vec<Vec<String>>, Vec<Vec<String>>) =
+            std::mem::take(&mut table_rows.rows)
+                .into_iter()
+                .partition(|row| should_filter(predicate.as_ref(), row));
+        table_rows.rows = kept;
+        table_rows.changed |= !deleted.is_empty();
+
+        self.on_delete(table, &deleted)?;
+        Ok(deleted)
+    }
+
+    /// Aplica la accion de cada clave foranea que referencia a las filas borradas
+    fn on_delete(&mut self, table: &String, deleted: &[Vec<String>]) -> Result<(), ErrorType> {
+        if deleted.is_empty() {
+            return Ok(());
+        }
+
+        for (child, key) in referencing_tables(table, self.db_path)? {
+            let parent_indexes =
+                column_indexes(table, &self.load(table)?.header_index, &key.references)?;
+            let deleted_keys: HashSet<Vec<String>> = deleted
+                .iter()
+                .filter_map(|row| row_key(&parent_indexes, row))
+                .collect();
+
+            let child_rows = self.load(&child)?;
+            let child_indexes = column_indexes(&child, &child_rows.header_index, &key.columns)?;
+            let references = |row: &Vec<String>| {
+                row_key(&child_indexes, row).is_some_and(|key| deleted_keys.contains(&key))
+            };
+
+            match key.on_delete {
+                ReferentialAction::Restrict => {
+                    if let Some(row) = child_rows.rows.iter().find(|row| references(row)) {
+                        return Err(ErrorType::ConstraintViolation(format!(
+                            "Row ({}) of table '{child}' references a deleted row of table '{table}'",
+                            row.join(",")
+                        )));
+                    }
+                }
+                ReferentialAction::Cascade => {
+                    let (cascaded, kept): (Vec<Vec<String>>, Vec<Vec<String>>) =
+                        std::mem::take(&mut child_rows.rows)
+                            .into_iter()
+                            .partition(references);
+                    child_rows.rows = kept;
+                    child_rows.changed |= !cascaded.is_empty();
+                    self.on_delete(&child, &cascaded)?;
+                }
+                ReferentialAction::SetNull => {
+                    for row in child_rows.rows.iter_mut().filter(|row| references(row)) {
+                        for &index in &child_indexes {
+                            row[index] = String::new();
+                        }
+                        // la columna puede no admitir NULL
+                        if let Some(schema) = &child_rows.schema {
+                            schema.check_row(&child, &child_rows.header, row)?;
+                        }
+                        child_rows.changed = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Escribe las tablas que cambiaron
+    pub fn write(self) -> Result<(), ErrorType> {
+        for (table, table_rows) in self.tables {
+            if table_rows.changed {
+                write_table_rows(&table, self.db_path, &table_rows.header, &table_rows.rows)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::ComparisonOp;
+
+    /// Crea clientes, ordenes que referencian a clientes y envios que referencian a ordenes
+    fn setup_tables(db_path: &str, on_delete: &str) {
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let tables = [
+            ("clientes", "id,nombre\n1,Juan\n2,Maria\n", "id INTEGER PRIMARY KEY\nnombre TEXT\n".to_string()),
+            (
+                "ordenes",
+                "id,id_cliente\n10,1\n11,1\n12,2\n",
+                format!("id INTEGER PRIMARY KEY\nid_cliente INTEGER REFERENCES clientes (id) ON DELETE {on_delete}\n"),
+            ),
+            (
+                "envios",
+                "id,id_orden\n100,10\n101,12\n",
+                "id INTEGER PRIMARY KEY\nid_orden INTEGER REFERENCES ordenes (id) ON DELETE CASCADE\n".to_string(),
+            ),
+        ];
+        for (table, content, schema) in tables {
+            fs::write(format!("{db_path}/{table}.csv"), content).expect("Unable to write table");
+            fs::write(TableSchema::path(table, db_path), schema).expect("Unable to write schema");
+        }
+    }
+
+    fn read_table(db_path: &str, table: &str) -> String {
+        fs::read_to_string(format!("{db_path}/{table}.csv")).expect("Failed to read table")
+    }
+
+    fn where_id(id: &str) -> Option<Condition> {
+        Some(Condition::Comparison(
+            "id".to_string(),
+            ComparisonOp::Eq,
+            id.to_string(),
+        ))
+    }
+
+    #[test]
+    fn test_delete_restrict() {
+        let db_path = "./test_foreign_keys_db1";
+        setup_tables(db_path, "RESTRICT");
+
+        let mut plan = DeletePlan::new(db_path);
+        let result = plan.delete(&"clientes".to_string(), &where_id("1"));
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+        assert_eq!(
+            read_table(db_path, "clientes"),
+            "id,nombre\n1,Juan\n2,Maria\n"
+        );
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_delete_cascade_follows_references() {
+        let db_path = "./test_foreign_keys_db2";
+        setup_tables(db_path, "CASCADE");
+
+        let mut plan = DeletePlan::new(db_path);
+        let deleted = plan
+            .delete(&"clientes".to_string(), &where_id("1"))
+            .unwrap();
+        assert_eq!(deleted, vec![vec!["1".to_string(), "Juan".to_string()]]);
+        plan.write().unwrap();
+
+        assert_eq!(read_table(db_path, "clientes"), "id,nombre\n2,Maria\n");
+        assert_eq!(read_table(db_path, "ordenes"), "id,id_cliente\n12,2\n");
+        assert_eq!(read_table(db_path, "envios"), "id,id_orden\n101,12\n");
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_delete_set_null() {
+        let db_path = "./test_foreign_keys_db3";
+        setup_tables(db_path, "SET NULL");
+
+        let mut plan = DeletePlan::new(db_path);
+        plan.delete(&"clientes".to_string(), &where_id("1"))
+            .unwrap();
+        plan.write().unwrap();
+
+        assert_eq!(
+            read_table(db_path, "ordenes"),
+            "id,id_cliente\n10,\n11,\n12,2\n"
+        );
+        assert_eq!(
+            read_table(db_path, "envios"),
+            "id,id_orden\n100,10\n101,12\n"
+        );
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_foreign_key_check_and_referenced_keys() {
+        let db_path = "./test_foreign_keys_db4";
+        setup_tables(db_path, "RESTRICT");
+
+        let schema = TableSchema::load("ordenes", db_path).unwrap();
+        let header_index = HashMap::from([("id".to_string(), 0), ("id_cliente".to_string(), 1)]);
+        let check =
+            ForeignKeyCheck::new("ordenes", schema.as_ref(), &header_index, db_path).unwrap();
+        let row = |id: &str, id_cliente: &str| [id.to_string(), id_cliente.to_string()];
+        assert!(check.check(&row("13", "2")).is_ok());
+        assert!(check.check(&row("13", "")).is_ok());
+        assert!(matches!(
+            check.check(&row("13", "3")),
+            Err(ErrorType::ConstraintViolation(_))
+        ));
+
+        // cambiar el id de un cliente con ordenes no esta permitido
+        let header_index = HashMap::from([("id".to_string(), 0), ("nombre".to_string(), 1)]);
+        let mut keys = ReferencedKeys::new("clientes", &header_index, db_path).unwrap();
+        assert!(!keys.is_empty());
+        keys.update(&row("1", "Juan"), &row("5", "Juan"));
+        assert!(matches!(
+            keys.finish(db_path),
+            Err(ErrorType::ConstraintViolation(_))
+        ));
+
+        // intercambiar ids deja todas las claves referenciadas
+        let mut keys = ReferencedKeys::new("clientes", &header_index, db_path).unwrap();
+        keys.update(&row("1", "Juan"), &row("2", "Juan"));
+        keys.update(&row("2", "Maria"), &row("1", "Maria"));
+        assert!(keys.finish(db_path).is_ok());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_validate_references() {
+        let db_path = "./test_foreign_keys_db5";
+        setup_tables(db_path, "RESTRICT");
+        let foreign_key = |table: &str, column: &str| {
+            let mut schema = TableSchema::new(Vec::new());
+            schema.foreign_keys.push(ForeignKey {
+                columns: vec!["referencia".to_string()],
+                table: table.to_string(),
+                references: vec![column.to_string()],
+                on_delete: ReferentialAction::Restrict,
+            });
+            schema
+        };
+
+        assert!(validate_references("pagos", &foreign_key("clientes", "id"), db_path).is_ok());
+        assert!(validate_references("pagos", &foreign_key("clientes", "nombre"), db_path).is_err());
+        assert!(validate_references("pagos", &foreign_key("proveedores", "id"), db_path).is_err());
+        assert!(validate_references("pagos", &foreign_key("pagos", "id"), db_path).is_err());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
@@ -1,20 +1,38 @@
-use super::utils_commands::{create_table_list, open_csv_reader};
+use super::compound::query_result_set;
+use super::foreign_keys::{ForeignKeyCheck, ReferencedKeys};
+use super::unique_keys::UniqueKeys;
+use super::update::{check_update_headers, update_row_values};
+use super::utils_commands::{
+    create_aux_file, create_row_values_map, create_table_list, open_csv_reader, ReturningRows,
+};
+use crate::command_result::{CommandKind, CommandResult};
+use crate::command_types::{Commands, ConflictAction, OnConflict, Value};
+use crate::condition::ComparisonOp;
 use crate::errors::ErrorType;
-use std::{collections::HashMap, fs::OpenOptions, io::Write};
-
-/// Genera un Vector de String con los valores de la fila a insertar
+use crate::index::{append_to_indexes, read_rows_at, rebuild_indexes, TableIndex, TableState};
+use crate::schema::TableSchema;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::{BufRead, Seek, Write},
+};
+
+/// Genera un Vector de String con los valores de la fila a insertar.
+/// Las columnas sin valor toman el proximo valor si son autoincrementales, el DEFAULT
+/// declarado en el esquema, o quedan vacias
 fn get_insert_row(
     headers: &[String],
     values: &[String],
     table_header: &[String],
-) -> Result<String, ErrorType> {
-    let mut insert_row: Vec<String> = vec![String::new(); table_header.len()];
+    mut schema: Option<&mut TableSchema>,
+) -> Result<Vec<String>, ErrorType> {
+    let mut insert_row: Vec<Option<String>> = vec![None; table_header.len()];
 
     // Si no se entregaron headers, pongo los valores en el orden de llegada
     if headers.is_empty() {
         for (i, value) in values.iter().enumerate() {
             if i < insert_row.len() {
-                insert_row[i] = value.to_string();
+                insert_row[i] = Some(value.to_string());
             }
         }
     // Si hay headers especificos, coloca los valores en ese orden
@@ -25,7 +43,7 @@ fn get_insert_row(
         }
         for (header, value) in headers.iter().zip(values.iter()) {
             if let Some(&index) = header_index.get(header.as_str()) {
-                insert_row[index] = value.to_string();
+                insert_row[index] = Some(value.to_string());
             } else {
                 return Err(ErrorType::InvalidColumn(format!(
                     "Header '{header}' not found in the CSV file"
@@ -34,13 +52,31 @@ fn get_insert_row(
         }
     }
 
-    // si el indice quedo vacio inserta string vacio
-    for i in insert_row.len()..table_header.len() {
-        if insert_row.get(i).is_none() {
-            insert_row.push(String::new());
-        }
+    // si el indice quedo vacio inserta el valor generado, el valor por defecto o string vacio
+    let mut row = Vec::with_capacity(table_header.len());
+    for (value, header) in insert_row.into_iter().zip(table_header) {
+        let Some(column) = schema
+            .as_deref_mut()
+            .and_then(|schema| schema.column_mut(header))
+        else {
+            row.push(value.unwrap_or_default());
+            continue;
+        };
+        let value = match value {
+            // un NULL explicito en una columna autoincremental tambien genera el valor
+            Some(value) if !(value.is_empty() && column.auto_increment.is_some()) => {
+                column.skip_value(&value);
+                value
+            }
+            _ => column
+                .next_value()
+                .or_else(|| column.default.clone())
+                .unwrap_or_default(),
+        };
+        // las claves se comparan como texto, `01` y `1` tienen que quedar iguales
+        row.push(column.data_type.normalize(&value));
     }
-    Ok(insert_row.join(","))
+    Ok(row)
 }
 
 /// Verifica que no haya mas valores que headers en la tabla
@@ -53,36 +89,401 @@ fn check_insert_headers(values: &[String], table_header: &[String]) -> Result<()
     Ok(())
 }
 
+/// Reemplaza las referencias `EXCLUDED.columna` del DO UPDATE por el valor de la fila
+/// que se intento insertar
+fn resolve_excluded(
+    updates: &HashMap<String, Value>,
+    table_header: &[String],
+    insert_row: &[String],
+) -> Result<HashMap<String, String>, ErrorType> {
+    let mut resolved = HashMap::new();
+    for (column, value) in updates {
+        let value = match value {
+            Value::Excluded(excluded) => {
+                let index = table_header
+                    .iter()
+                    .position(|header| header == excluded)
+                    .ok_or(ErrorType::InvalidColumn(format!(
+                        "Column 'EXCLUDED.{excluded}' not found in the table"
+                    )))?;
+                insert_row[index].to_string()
+            }
+            value => value.literal()?,
+        };
+        resolved.insert(column.to_string(), value);
+    }
+    Ok(resolved)
+}
+
+/// Filas de una tabla listas para escribir
+struct PendingInsert {
+    table: String,
+    /// filas que se agregan al final de la tabla
+    new_rows: Vec<Vec<String>>,
+    /// filas existentes que actualizo el ON CONFLICT, por su posicion en el csv
+    updated_rows: HashMap<u64, Vec<String>>,
+    affected_rows: usize,
+    returning_rows: Option<ReturningRows>,
+    /// esquema con el proximo valor de las columnas autoincrementales, se guarda al escribir
+    schema: Option<TableSchema>,
+}
+
+/// Fila con la que choca una fila a insertar: una existente, por su posicion en el csv,
+/// o una de las filas nuevas
+#[derive(Debug, Clone, Copy)]
+enum ConflictTarget {
+    Existing(u64),
+    New(usize),
+}
+
+/// Busca con el indice de la columna del conflicto las posiciones de las filas que pueden
+/// tener alguna de las claves. None si el conflicto no es sobre una unica columna con un
+/// indice actualizado
+fn conflict_index_lookup(
+    table: &str,
+    columns: &[String],
+    keys: &HashSet<Vec<String>>,
+    db_path: &str,
+) -> Result<Option<Vec<u64>>, ErrorType> {
+    let [column] = columns else {
+        return Ok(None);
+    };
+    if !TableIndex::columns(table, db_path)?.contains(column) {
+        return Ok(None);
+    }
+    let index = TableIndex::load(table, column, db_path)?;
+    if !index.is_current(table, db_path)? {
+        return Ok(None);
+    }
+    let mut offsets = Vec::new();
+    for key in keys {
+        match index.lookup(&ComparisonOp::Eq, &key[0]) {
+            Some(found) => offsets.extend(found),
+            None => return Ok(None),
+        }
+    }
+    offsets.sort_unstable();
+    offsets.dedup();
+    Ok(Some(offsets))
+}
+
+/// Aplica el ON CONFLICT sobre las filas a insertar. Las filas que no chocan con una
+/// existente se insertan, las que chocan se ignoran o actualizan la fila existente.
+/// Las filas resultantes tienen que respetar las demas restricciones de unicidad.
+/// De la tabla solo se guardan las claves de unicidad y las filas que chocan, que se buscan
+/// con el indice de la columna del conflicto si lo hay
+#[allow(clippy::too_many_arguments)]
+fn resolve_conflicts(
+    reader: &mut (impl BufRead + Seek),
+    table_header: &[String],
+    header_index: &HashMap<String, usize>,
+    schema: Option<&TableSchema>,
+    foreign_keys: &ForeignKeyCheck,
+    insert_rows: Vec<Vec<String>>,
+    on_conflict: &OnConflict,
+    pending: &mut PendingInsert,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    let mut key_indexes = Vec::new();
+    for column in &on_conflict.columns {
+        let index = header_index
+            .get(column)
+            .ok_or(ErrorType::InvalidColumn(format!(
+                "Conflict column '{column}' not found in the table"
+            )))?;
+        key_indexes.push(*index);
+    }
+    if let ConflictAction::Update(updates) = &on_conflict.action {
+        check_update_headers(updates, header_index)?;
+    }
+    let row_key = |row: &[String]| -> Vec<String> {
+        key_indexes
+            .iter()
+            .map(|&index| row.get(index).cloned().unwrap_or_default())
+            .collect()
+    };
+    let wanted: HashSet<Vec<String>> = insert_rows.iter().map(|row| row_key(row)).collect();
+    let read_error = |e: std::io::Error| ErrorType::OtherError(format!("Error reading line: {e}"));
+
+    // busco las filas existentes que chocan con alguna fila a insertar
+    let mut unique_keys = UniqueKeys::new(&pending.table, schema, header_index)?;
+    let mut referenced_keys = ReferencedKeys::new(&pending.table, header_index, db_path)?;
+    let mut keys = HashMap::new();
+    let mut existing = HashMap::new();
+    let offsets = if unique_keys.is_empty() {
+        conflict_index_lookup(&pending.table, &on_conflict.columns, &wanted, db_path)?
+    } else {
+        // igual hay que leer la tabla para cargar las claves de unicidad
+        None
+    };
+    if let Some(offsets) = offsets {
+        for (offset, line) in offsets
+            .iter()
+            .zip(read_rows_at(&pending.table, &offsets, db_path)?)
+        {
+            let row: Vec<String> = line.trim().split(',').map(ToString::to_string).collect();
+            let key = row_key(&row);
+            if wanted.contains(&key) {
+                keys.insert(key, ConflictTarget::Existing(*offset));
+                existing.insert(*offset, row);
+            }
+        }
+    } else {
+        let mut offset = reader.stream_position().map_err(read_error)?;
+        let mut buffer = String::new();
+        loop {
+            let bytes = reader.read_line(&mut buffer).map_err(read_error)?;
+            if bytes == 0 {
+                break;
+            }
+            let row: Vec<String> = buffer.trim().split(',').map(ToString::to_string).collect();
+            unique_keys.insert(&row)?;
+            let key = row_key(&row);
+            if wanted.contains(&key) {
+                keys.insert(key, ConflictTarget::Existing(offset));
+                existing.insert(offset, row);
+            }
+            offset += bytes as u64;
+            buffer.clear();
+        }
+    }
+
+    for insert_row in insert_rows {
+        let key = row_key(&insert_row);
+        match (keys.get(&key).copied(), &on_conflict.action) {
+            (Some(_), ConflictAction::Nothing) => continue,
+            (Some(target), ConflictAction::Update(updates)) => {
+                // actualizo la fila con la que choca igual que un UPDATE
+                let current = match target {
+                    ConflictTarget::Existing(offset) => pending
+                        .updated_rows
+                        .get(&offset)
+                        .or_else(|| existing.get(&offset)),
+                    ConflictTarget::New(index) => pending.new_rows.get(index),
+                }
+                .cloned()
+                .unwrap_or_default();
+                let updates = resolve_excluded(updates, table_header, &insert_row)?;
+                let row_values_map = create_row_values_map(table_header, &current.join(","));
+                let mut updated_row = update_row_values(table_header, &row_values_map, &updates);
+                if let Some(schema) = schema {
+                    schema.normalize_row(table_header, &mut updated_row);
+                    schema.check_row(&pending.table, table_header, &updated_row)?;
+                }
+                foreign_keys.check(&updated_row)?;
+                unique_keys.remove(&current);
+                unique_keys.insert(&updated_row)?;
+                if !referenced_keys.is_empty() {
+                    referenced_keys.update(&current, &updated_row);
+                }
+
+                keys.remove(&key);
+                keys.insert(row_key(&updated_row), target);
+                if let Some(returning_rows) = &mut pending.returning_rows {
+                    returning_rows.push(&updated_row);
+                }
+                match target {
+                    ConflictTarget::Existing(offset) => {
+                        pending.updated_rows.insert(offset, updated_row);
+                    }
+                    ConflictTarget::New(index) => pending.new_rows[index] = updated_row,
+                }
+            }
+            (None, _) => {
+                unique_keys.insert(&insert_row)?;
+                if !referenced_keys.is_empty() {
+                    // una fila nueva puede volver a usar una clave que otra fila dejo
+                    referenced_keys.update(&[], &insert_row);
+                }
+                keys.insert(key, ConflictTarget::New(pending.new_rows.len()));
+                if let Some(returning_rows) = &mut pending.returning_rows {
+                    returning_rows.push(&insert_row);
+                }
+                pending.new_rows.push(insert_row);
+            }
+        }
+        pending.affected_rows += 1;
+    }
+    // las claves que cambiaron no pueden seguir referenciadas por otras tablas
+    referenced_keys.finish(db_path)
+}
+
+/// Escribe las filas en la tabla. Si se actualizaron filas existentes se reescribe la
+/// tabla leyendola linea a linea, sino se agregan las filas nuevas al final
+fn write_pending_insert(pending: &PendingInsert, db_path: &str) -> Result<(), ErrorType> {
+    let mut insert_rows = String::new();
+    for row in &pending.new_rows {
+        insert_rows.push_str(&row.join(","));
+        insert_rows.push('\n');
+    }
+
+    if !pending.updated_rows.is_empty() {
+        let (mut reader, table_header, _, _) = open_csv_reader(&pending.table, db_path)
+            .map_err(|e| ErrorType::InvalidTable(format!("Error opening table {e}")))?;
+        let (mut aux_file, csv_table, aux_table) =
+            create_aux_file(&pending.table, db_path, &table_header).map_err(|e| {
+                ErrorType::OtherError(format!("creating auxiliary file {e} failed"))
+            })?;
+        let read_error =
+            |e: std::io::Error| ErrorType::OtherError(format!("Error reading line: {e}"));
+        let write_error = |e: std::io::Error| {
+            ErrorType::OtherError(format!("writing to auxiliary file {e} failed"))
+        };
+
+        let mut offset = reader.stream_position().map_err(read_error)?;
+        let mut buffer = String::new();
+        loop {
+            let bytes = reader.read_line(&mut buffer).map_err(read_error)?;
+            if bytes == 0 {
+                break;
+            }
+            match pending.updated_rows.get(&offset) {
+                Some(row) => writeln!(aux_file, "{}", row.join(",")),
+                None => writeln!(aux_file, "{}", buffer.trim_end_matches(['\n', '\r'])),
+            }
+            .map_err(write_error)?;
+            offset += bytes as u64;
+            buffer.clear();
+        }
+        aux_file
+            .write_all(insert_rows.as_bytes())
+            .map_err(write_error)?;
+
+        std::fs::rename(&aux_table, &csv_table)
+            .map_err(|e| ErrorType::OtherError(format!("renaming file {e} failed")))?;
+        return rebuild_indexes(&pending.table, db_path);
+    }
+
+    // abro el archivo en formato append
+    let csv_table = format!("{db_path}/{}.csv", pending.table);
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&csv_table)
+        .map_err(|e| ErrorType::InvalidTable(format!("opening file for append {e} failed")))?;
+
+    let before = file
+        .metadata()
+        .map(|metadata| TableState::of(&metadata))
+        .map_err(|e| ErrorType::InvalidTable(format!("reading table {e} failed")))?;
+
+    // escribo todas las filas nuevas en la tabla de una vez
+    file.write_all(insert_rows.as_bytes())
+        .map_err(|e| ErrorType::OtherError(format!("writing to file {e} failed")))?;
+    append_to_indexes(&pending.table, &pending.new_rows, before, db_path)
+}
+
 pub fn insert(
     tables: &[String],
     headers: &[String],
-    values: &[String],
+    values: &[Vec<String>],
+    on_conflict: &Option<OnConflict>,
+    returning: &Option<Vec<String>>,
     db_path: &str,
-) -> Result<Option<String>, ErrorType> {
+) -> Result<CommandResult, ErrorType> {
     let table_list = create_table_list(tables, db_path)?;
+    let mut result = CommandResult::new(CommandKind::Insert);
+    let mut output = String::new();
 
+    // primero valido todas las filas en todas las tablas, si alguna falla no se inserta nada
+    let mut pending_inserts = Vec::new();
     for table in table_list {
         // abro archivo
-        let (_, table_header, _) = open_csv_reader(&table, db_path)
+        let (mut reader, table_header, header_index, mut schema) = open_csv_reader(&table, db_path)
             .map_err(|e| ErrorType::InvalidTable(format!("Error opening table {e}")))?;
-        let csv_table = format!("{db_path}/{table}.csv");
 
-        // corroboro headers
-        check_insert_headers(values, &table_header)?;
+        let foreign_keys = ForeignKeyCheck::new(&table, schema.as_ref(), &header_index, db_path)?;
+        let mut insert_rows = Vec::new();
+        for row in values {
+            // corroboro headers y tipos
+            check_insert_headers(row, &table_header)?;
+            let insert_row = get_insert_row(headers, row, &table_header, schema.as_mut())?;
+            if let Some(schema) = &schema {
+                schema.check_row(&table, &table_header, &insert_row)?;
+            }
+            foreign_keys.check(&insert_row)?;
+            insert_rows.push(insert_row);
+        }
+
+        let mut pending = PendingInsert {
+            returning_rows: ReturningRows::new(&table_header, returning)?,
+            table,
+            new_rows: Vec::new(),
+            updated_rows: HashMap::new(),
+            affected_rows: 0,
+            schema: None,
+        };
+        if let Some(on_conflict) = on_conflict {
+            resolve_conflicts(
+                &mut reader,
+                &table_header,
+                &header_index,
+                schema.as_ref(),
+                &foreign_keys,
+                insert_rows,
+                on_conflict,
+                &mut pending,
+                db_path,
+            )?;
+        } else {
+            // cargo las claves existentes una sola vez y verifico cada fila contra ellas
+            let mut unique_keys = UniqueKeys::new(&pending.table, schema.as_ref(), &header_index)?;
+            if !unique_keys.is_empty() {
+                unique_keys.load(&mut reader)?;
+                for row in &insert_rows {
+                    unique_keys.insert(row)?;
+                }
+            }
+            if let Some(returning_rows) = &mut pending.returning_rows {
+                for row in &insert_rows {
+                    returning_rows.push(row);
+                }
+            }
+            pending.affected_rows = insert_rows.len();
+            pending.new_rows = insert_rows;
+        }
+        pending.schema = schema.filter(TableSchema::has_auto_increment);
+        pending_inserts.push(pending);
+    }
+
+    for pending in pending_inserts {
+        write_pending_insert(&pending, db_path)?;
+        if let Some(schema) = &pending.schema {
+            schema.save(&pending.table, db_path)?;
+        }
 
-        let insert_row = get_insert_row(headers, values, &table_header)?;
+        if let Some(returning_rows) = pending.returning_rows {
+            returning_rows.print(&mut output);
+        }
+        result.add_table_rows(&pending.table, pending.affected_rows);
+    }
+    result.output = returning.as_ref().map(|_| output);
+    Ok(result)
+}
 
-        // abro el archivo en formato append
-        let mut file = OpenOptions::new()
-            .append(true)
-            .open(&csv_table)
-            .map_err(|e| ErrorType::InvalidTable(format!("opening file for append {e} failed")))?;
+/// Inserta el resultado de un select. Sin headers explicitos, cada columna del
+/// resultado se ubica en la columna de la tabla con el mismo nombre
+pub fn insert_select(
+    tables: &[String],
+    headers: &[String],
+    query: &Commands,
+    on_conflict: &Option<OnConflict>,
+    returning: &Option<Vec<String>>,
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let (query_headers, rows) = query_result_set(query, db_path)?;
 
-        // escribo la nueva fila en la tabla
-        writeln!(file, "{insert_row}")
-            .map_err(|e| ErrorType::OtherError(format!("writing to file {e} failed")))?;
+    if !headers.is_empty() && headers.len() != query_headers.len() {
+        return Err(ErrorType::InvalidColumn(
+            "Number of headers and selected columns must match".into(),
+        ));
     }
-    Ok(None)
+
+    let headers = if headers.is_empty() {
+        &query_headers
+    } else {
+        headers
+    };
+    insert(tables, headers, &rows, on_conflict, returning, db_path)
 }
 
 #[cfg(test)]
@@ -125,7 +526,7 @@ mod tests {
 
         // Ejecuta el insert
         let tables = vec![table_name.to_string()];
-        let insert_result = insert(&tables, &headers, &values, db_path);
+        let insert_result = insert(&tables, &headers, &[values.to_vec()], &None, &None, db_path);
 
         // Verificar que no hubo errores
         assert!(insert_result.is_ok(), "Insert failed: {:?}", insert_result);
@@ -157,7 +558,7 @@ mod tests {
 
         // Ejecuta el insert en ambas tablas
         let tables = vec![table_name1.to_string(), table_name2.to_string()];
-        let insert_result = insert(&tables, &headers, &values, db_path);
+        let insert_result = insert(&tables, &headers, &[values.to_vec()], &None, &None, db_path);
 
         // Verificar que no hubo errores
         assert!(insert_result.is_ok(), "Insert failed: {:?}", insert_result);
@@ -189,7 +590,7 @@ mod tests {
 
         // Ejecuta el insert
         let tables = vec![table_name.to_string()];
-        let insert_result = insert(&tables, &headers, &values, db_path);
+        let insert_result = insert(&tables, &headers, &[values.to_vec()], &None, &None, db_path);
 
         // Verificar que el insert falla con el error adecuado
         assert!(insert_result.is_err());
@@ -213,7 +614,7 @@ mod tests {
 
         // Ejecuta el insert
         let tables = vec![table_name.to_string()];
-        let result = insert(&tables, &headers, &values, db_path);
+        let result = insert(&tables, &headers, &[values], &None, &None, db_path);
 
         // Verifica que no hubo errores
         assert!(result.is_ok(), "Insert failed: {:?}", result);
@@ -243,7 +644,7 @@ mod tests {
 
         // Ejecuta el insert
         let tables = vec![table_name.to_string()];
-        let result = insert(&tables, &headers, &values, db_path);
+        let result = insert(&tables, &headers, &[values], &None, &None, db_path);
 
         // Verifica que hubo error
         assert!(result.is_err());
@@ -271,7 +672,7 @@ mod tests {
 
         // Ejecuta el insert
         let tables = vec![table_name.to_string()];
-        let result = insert(&tables, &headers, &values, db_path);
+        let result = insert(&tables, &headers, &[values], &None, &None, db_path);
 
         // Verifica que no hubo errores
         assert!(result.is_ok(), "Insert failed: {:?}", result);
@@ -301,7 +702,7 @@ mod tests {
 
         // Ejecuta el insert con headers vacíos
         let tables = vec![table_name.to_string()];
-        let insert_result = insert(&tables, &[], &values, db_path);
+        let insert_result = insert(&tables, &[], &[values], &None, &None, db_path);
 
         // Verificar que no hubo errores
         assert!(insert_result.is_ok(), "Insert failed: {:?}", insert_result);
@@ -334,7 +735,7 @@ mod tests {
 
         // Ejecuta el insert en todas las tablas con el wildcard "*"
         let tables = vec!["*".to_string()];
-        let insert_result = insert(&tables, &[], &values, db_path);
+        let insert_result = insert(&tables, &[], &[values], &None, &None, db_path);
 
         // Verificar que no hubo errores
         assert!(insert_result.is_ok(), "Insert failed: {:?}", insert_result);
@@ -359,4 +760,386 @@ mod tests {
         }
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
     }
+
+    #[test]
+    fn test_insert_multiple_rows() {
+        let db_path = "./test_insert_db9";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string()];
+        let values = vec![
+            vec!["1".to_string(), "Juan".to_string()],
+            vec!["2".to_string(), "Maria".to_string()],
+        ];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, table_name, &headers);
+
+        let tables = vec![table_name.to_string()];
+        let insert_result = insert(&tables, &headers, &values, &None, &None, db_path);
+        assert_eq!(insert_result.unwrap().rows_in_table(table_name), Some(2));
+
+        let csv_table_path = format!("{}/{}.csv", db_path, table_name);
+        let content = fs::read_to_string(csv_table_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre\n1,Juan\n2,Maria\n");
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_multiple_rows_is_all_or_nothing() {
+        let db_path = "./test_insert_db10";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string()];
+        let values = vec![
+            vec!["1".to_string(), "Juan".to_string()],
+            vec!["2".to_string(), "Maria".to_string(), "extra".to_string()],
+        ];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, table_name, &headers);
+
+        // la segunda fila tiene mas valores que columnas, no se debe insertar ninguna
+        let tables = vec![table_name.to_string()];
+        let insert_result = insert(&tables, &[], &values, &None, &None, db_path);
+        assert!(matches!(insert_result, Err(ErrorType::InvalidColumn(_))));
+
+        let csv_table_path = format!("{}/{}.csv", db_path, table_name);
+        let content = fs::read_to_string(csv_table_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre\n");
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_select_maps_columns_by_name() {
+        let db_path = "./test_insert_db11";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, "origen", &["id".to_string(), "nombre".to_string()]);
+        setup_table(
+            db_path,
+            "destino",
+            &["nombre".to_string(), "id".to_string()],
+        );
+        insert(
+            &["origen".to_string()],
+            &[],
+            &[
+                vec!["1".to_string(), "Juan".to_string()],
+                vec!["2".to_string(), "Maria".to_string()],
+            ],
+            &None,
+            &None,
+            db_path,
+        )
+        .expect("Failed to insert test data");
+
+        let query = Commands::Select {
+            headers: vec!["*".to_string()],
+            tables: vec!["origen".to_string()],
+            where_st: None,
+            order: None,
+            limit: None,
+            windows: Vec::new(),
+        };
+        let result = insert_select(&["destino".to_string()], &[], &query, &None, &None, db_path);
+        assert!(result.is_ok(), "Insert failed: {:?}", result);
+
+        let content =
+            fs::read_to_string(format!("{}/destino.csv", db_path)).expect("Failed to read table");
+        assert_eq!(content, "nombre,id\nJuan,1\nMaria,2\n");
+
+        // con headers explicitos la cantidad tiene que coincidir con las columnas seleccionadas
+        let result = insert_select(
+            &["destino".to_string()],
+            &["id".to_string()],
+            &query,
+            &None,
+            &None,
+            db_path,
+        );
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_with_returning_shows_stored_row() {
+        let db_path = "./test_insert_db12";
+        let table_name = "test_table";
+        let table_headers = ["id".to_string(), "nombre".to_string(), "edad".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, table_name, &table_headers);
+
+        let tables = vec![table_name.to_string()];
+        let values = [vec!["Juan".to_string(), "1".to_string()]];
+        let headers = ["nombre".to_string(), "id".to_string()];
+        let returning = Some(vec!["*".to_string()]);
+        let result = insert(&tables, &headers, &values, &None, &returning, db_path);
+
+        assert_eq!(
+            result.unwrap().output,
+            Some("id,nombre,edad\n1,Juan,\n".to_string())
+        );
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_on_conflict_do_nothing() {
+        let db_path = "./test_insert_db13";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(db_path, table_name, &headers);
+        fs::write(&csv_path, "id,nombre\n1,Juan\n").expect("Unable to write test table file");
+
+        let tables = vec![table_name.to_string()];
+        let values = [
+            vec!["1".to_string(), "Pedro".to_string()],
+            vec!["2".to_string(), "Maria".to_string()],
+            vec!["2".to_string(), "Ana".to_string()],
+        ];
+        let on_conflict = Some(OnConflict {
+            columns: vec!["id".to_string()],
+            action: ConflictAction::Nothing,
+        });
+        let result = insert(&tables, &headers, &values, &on_conflict, &None, db_path);
+        assert_eq!(result.unwrap().rows_in_table(table_name), Some(1));
+
+        let content = fs::read_to_string(csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre\n1,Juan\n2,Maria\n");
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_on_conflict_do_update() {
+        let db_path = "./test_insert_db14";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string(), "edad".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(db_path, table_name, &headers);
+        fs::write(&csv_path, "id,nombre,edad\n1,Juan,20\n2,Maria,30\n")
+            .expect("Unable to write test table file");
+
+        let tables = vec![table_name.to_string()];
+        let values = [
+            vec!["1".to_string(), "Pedro".to_string(), "25".to_string()],
+            vec!["3".to_string(), "Ana".to_string(), "40".to_string()],
+        ];
+        let updates = HashMap::from([
+            ("nombre".to_string(), Value::Excluded("nombre".to_string())),
+            ("edad".to_string(), Value::Literal("99".to_string())),
+        ]);
+        let on_conflict = Some(OnConflict {
+            columns: vec!["id".to_string()],
+            action: ConflictAction::Update(updates),
+        });
+        let returning = Some(vec!["id".to_string(), "nombre".to_string()]);
+        let result = insert(
+            &tables,
+            &headers,
+            &values,
+            &on_conflict,
+            &returning,
+            db_path,
+        )
+        .unwrap();
+        assert_eq!(result.affected_rows, 2);
+        assert_eq!(
+            result.output,
+            Some("id,nombre\n1,Pedro\n3,Ana\n".to_string())
+        );
+
+        let content = fs::read_to_string(csv_path).expect("Failed to read test table file");
+        assert_eq!(
+            content,
+            "id,nombre,edad\n1,Pedro,99\n2,Maria,30\n3,Ana,40\n"
+        );
+
+        // las columnas del conflicto tienen que existir en la tabla
+        let on_conflict = Some(OnConflict {
+            columns: vec!["dni".to_string()],
+            action: ConflictAction::Nothing,
+        });
+        let result = insert(&tables, &headers, &values, &on_conflict, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::InvalidColumn(_))));
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_on_conflict_with_index() {
+        let db_path = "./test_insert_db19";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(db_path, table_name, &headers);
+        fs::write(&csv_path, "id,nombre\n1,Juan\n2,Maria\n3,Ana\n")
+            .expect("Unable to write test table file");
+        TableIndex::build(table_name, "id", db_path)
+            .and_then(|index| index.save(table_name, db_path))
+            .unwrap();
+
+        // las filas que chocan se buscan con el indice del id
+        let tables = vec![table_name.to_string()];
+        let values = [
+            vec!["2".to_string(), "Pedro".to_string()],
+            vec!["4".to_string(), "Luz".to_string()],
+            vec!["4".to_string(), "Sol".to_string()],
+        ];
+        let updates = HashMap::from([(
+            "nombre".to_string(),
+            Value::Literal("EXCLUDED.nombre".to_string()),
+        )]);
+        let on_conflict = Some(OnConflict {
+            columns: vec!["id".to_string()],
+            action: ConflictAction::Update(updates),
+        });
+        let result = insert(&tables, &headers, &values, &on_conflict, &None, db_path).unwrap();
+        assert_eq!(result.affected_rows, 3);
+
+        // un literal nunca se toma como referencia a la fila insertada
+        let content = fs::read_to_string(&csv_path).expect("Failed to read test table file");
+        assert_eq!(
+            content,
+            "id,nombre\n1,Juan\n2,EXCLUDED.nombre\n3,Ana\n4,EXCLUDED.nombre\n"
+        );
+        let index = TableIndex::load(table_name, "id", db_path).unwrap();
+        assert!(index.is_current(table_name, db_path).unwrap());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_fills_defaults_and_checks_constraints() {
+        let db_path = "./test_insert_db15";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string(), "edad".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(db_path, table_name, &headers);
+        fs::write(
+            TableSchema::path(table_name, db_path),
+            "id INTEGER NOT NULL\nnombre TEXT DEFAULT 'anonimo'\nedad INTEGER CHECK (edad >= 18)\n",
+        )
+        .expect("Unable to write test schema file");
+
+        let tables = vec![table_name.to_string()];
+        let columns = ["id".to_string(), "edad".to_string()];
+        let values = [vec!["1".to_string(), "20".to_string()]];
+        assert!(insert(&tables, &columns, &values, &None, &None, db_path).is_ok());
+
+        // falta el id, que es NOT NULL
+        let columns = ["nombre".to_string()];
+        let values = [vec!["Juan".to_string()]];
+        let result = insert(&tables, &columns, &values, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        // la edad no cumple el CHECK
+        let values = [vec!["2".to_string(), "Ana".to_string(), "15".to_string()]];
+        let result = insert(&tables, &headers, &values, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        let content = fs::read_to_string(csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre,edad\n1,anonimo,20\n");
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_checks_unique_keys() {
+        let db_path = "./test_insert_db16";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "email".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(db_path, table_name, &headers);
+        fs::write(&csv_path, "id,email\n1,a@mail.com\n").expect("Unable to write test table file");
+        fs::write(
+            TableSchema::path(table_name, db_path),
+            "id INTEGER PRIMARY KEY\nemail TEXT UNIQUE\n",
+        )
+        .expect("Unable to write test schema file");
+
+        let tables = vec![table_name.to_string()];
+        let values = [vec!["1".to_string(), "b@mail.com".to_string()]];
+        let result = insert(&tables, &headers, &values, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        // las filas de un mismo INSERT tampoco pueden repetir claves
+        let values = [
+            vec!["2".to_string(), "b@mail.com".to_string()],
+            vec!["3".to_string(), "b@mail.com".to_string()],
+        ];
+        let result = insert(&tables, &headers, &values, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        // el ON CONFLICT sobre la clave primaria no evita chocar con el UNIQUE
+        let on_conflict = Some(OnConflict {
+            columns: vec!["id".to_string()],
+            action: ConflictAction::Nothing,
+        });
+        let values = [vec!["2".to_string(), "a@mail.com".to_string()]];
+        let result = insert(&tables, &headers, &values, &on_conflict, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        let values = [
+            vec!["2".to_string(), "b@mail.com".to_string()],
+            vec!["3".to_string(), String::new()],
+        ];
+        assert!(insert(&tables, &headers, &values, &None, &None, db_path).is_ok());
+
+        let content = fs::read_to_string(csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,email\n1,a@mail.com\n2,b@mail.com\n3,\n");
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_insert_generates_auto_increment_values() {
+        let db_path = "./test_insert_db17";
+        let table_name = "test_table";
+        let headers = ["id".to_string(), "nombre".to_string()];
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(db_path, table_name, &headers);
+        fs::write(
+            TableSchema::path(table_name, db_path),
+            "id SERIAL PRIMARY KEY\nnombre TEXT\n",
+        )
+        .expect("Unable to write test schema file");
+
+        let tables = vec![table_name.to_string()];
+        let columns = ["nombre".to_string()];
+        let values = [vec!["Juan".to_string()], vec!["Maria".to_string()]];
+        let returning = Some(vec!["id".to_string()]);
+        let result = insert(&tables, &columns, &values, &None, &returning, db_path);
+        assert_eq!(result.unwrap().output, Some("id\n1\n2\n".to_string()));
+
+        // un valor explicito avanza la secuencia para no repetirlo
+        let values = [vec!["10".to_string(), "Ana".to_string()]];
+        assert!(insert(&tables, &headers, &values, &None, &None, db_path).is_ok());
+        let values = [vec![String::new(), "Pedro".to_string()]];
+        assert!(insert(&tables, &headers, &values, &None, &None, db_path).is_ok());
+
+        let content = fs::read_to_string(format!("{db_path}/{table_name}.csv"))
+            .expect("Failed to read test table file");
+        assert_eq!(content, "id,nombre\n1,Juan\n2,Maria\n10,Ana\n11,Pedro\n");
+        let schema = TableSchema::load(table_name, db_path)
+            .expect("Failed to load schema")
+            .expect("Schema not found");
+        assert_eq!(schema.columns[0].auto_increment, Some(12));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
 }
@@ -1,37 +1,176 @@
+mod alter;
+mod compound;
+mod create;
 mod delete;
+mod drop;
+mod executor;
+mod foreign_keys;
 mod insert;
+mod planner;
 mod select;
+mod sort;
+mod unique_keys;
 mod update;
 mod utils_commands;
 
+use alter::alter_table;
+use compound::{compound, compound_to};
+use create::{create_index, create_table};
 use delete::delete;
-use insert::insert;
-use select::select;
+use drop::drop_table;
+use executor::query_rows;
+use insert::{insert, insert_select};
+use planner::{explain, LogicalPlan};
+use select::{select, select_to};
 use update::update;
 
-use crate::command_types::Commands;
+use crate::command_result::CommandResult;
+use crate::command_types::{literal_limit, literal_updates, Commands, InsertSource, Value};
 use crate::errors::ErrorType;
+use crate::rows::Rows;
+use std::io::Write;
 
-/// Ejecuta el comando
+/// Ejecuta la consulta y devuelve sus filas sin pasarlas a texto, junto al nombre y tipo de
+/// cada columna. Las filas se leen a medida que se recorren. Un select debe leer de una
+/// unica tabla
 #[allow(clippy::missing_errors_doc)]
-pub fn execute(command: &Commands, db_path: &str) -> Result<Option<String>, ErrorType> {
+pub fn query(command: &Commands, db_path: &str) -> Result<Rows, ErrorType> {
+    command.check_bound()?;
+    query_rows(LogicalPlan::query(command, db_path)?, db_path)
+}
+
+/// Ejecuta el comando escribiendo su salida en `out`. Las filas de un SELECT se escriben a
+/// medida que se producen, por lo que su resultado no guarda el output
+#[allow(clippy::missing_errors_doc)]
+pub fn execute_to(
+    command: &Commands,
+    db_path: &str,
+    out: &mut dyn Write,
+) -> Result<CommandResult, ErrorType> {
+    command.check_bound()?;
+    let result = match command {
+        Commands::Select {
+            headers,
+            tables,
+            where_st,
+            order,
+            limit,
+            windows,
+        } => select_to(
+            headers,
+            tables,
+            where_st,
+            order,
+            literal_limit(limit.as_ref())?,
+            windows,
+            db_path,
+            out,
+        )?,
+        Commands::Compound {
+            first,
+            operations,
+            order,
+            limit,
+        } => compound_to(
+            first,
+            operations,
+            order,
+            literal_limit(limit.as_ref())?,
+            db_path,
+            out,
+        )?,
+        _ => {
+            let result = execute(command, db_path)?;
+            if let Some(output) = &result.output {
+                out.write_all(output.as_bytes())
+                    .map_err(|e| ErrorType::OtherError(format!("Error writing result: {e}")))?;
+            }
+            result
+        }
+    };
+    Ok(result)
+}
+
+/// Ejecuta el comando guardando su salida en el resultado
+#[allow(clippy::missing_errors_doc)]
+pub fn execute(command: &Commands, db_path: &str) -> Result<CommandResult, ErrorType> {
+    command.check_bound()?;
     match command {
         Commands::Insert {
             tables,
             headers,
-            values,
-        } => insert(tables, headers, values, db_path),
+            source,
+            on_conflict,
+            returning,
+        } => match source {
+            InsertSource::Values(values) => {
+                let values = values
+                    .iter()
+                    .map(|row| row.iter().map(Value::literal).collect())
+                    .collect::<Result<Vec<Vec<String>>, _>>()?;
+                insert(tables, headers, &values, on_conflict, returning, db_path)
+            }
+            InsertSource::Query(query) => {
+                insert_select(tables, headers, query, on_conflict, returning, db_path)
+            }
+        },
         Commands::Update {
             tables,
             updates,
             where_st,
-        } => update(tables, updates, where_st, db_path),
-        Commands::Delete { tables, where_st } => delete(tables, where_st, db_path),
+            returning,
+        } => update(
+            tables,
+            &literal_updates(updates)?,
+            where_st,
+            returning,
+            db_path,
+        ),
+        Commands::Delete {
+            tables,
+            where_st,
+            returning,
+        } => delete(tables, where_st, returning, db_path),
         Commands::Select {
             headers,
             tables,
             where_st,
             order,
-        } => select(headers, tables, where_st, order, db_path),
+            limit,
+            windows,
+        } => select(
+            headers,
+            tables,
+            where_st,
+            order,
+            literal_limit(limit.as_ref())?,
+            windows,
+            db_path,
+        ),
+        Commands::Compound {
+            first,
+            operations,
+            order,
+            limit,
+        } => compound(
+            first,
+            operations,
+            order,
+            literal_limit(limit.as_ref())?,
+            db_path,
+        ),
+        Commands::CreateTable {
+            table,
+            schema,
+            if_not_exists,
+        } => create_table(table, schema, *if_not_exists, db_path),
+        Commands::CreateIndex {
+            table,
+            column,
+            if_not_exists,
+        } => create_index(table, column, *if_not_exists, db_path),
+        Commands::DropTable { tables, if_exists } => drop_table(tables, *if_exists, db_path),
+        Commands::AlterTable { table, operation } => alter_table(table, operation, db_path),
+        Commands::Explain { query, analyze } => explain(query, *analyze, db_path),
     }
 }
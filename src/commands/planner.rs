@@ -0,0 +1,754 @@
+use super::executor::{analyze_plan, NodeStats};
+use super::select::check_select_headers;
+use super::utils_commands::{create_table_list, open_csv_reader};
+use crate::command_result::{CommandKind, CommandResult};
+use crate::command_types::{literal_limit, Commands, SetOperation, SetOperator};
+use crate::condition::{Condition, Simplified};
+use crate::errors::ErrorType;
+use crate::index::choose_index;
+use crate::order::OrderBy;
+use crate::window::WindowFunction;
+use std::{fmt, time::Instant};
+
+/// Lectura de las filas de una tabla. El optimizador le agrega las columnas que usa el resto
+/// del plan, el filtro del where, la comparacion que se resuelve con un indice y la cantidad
+/// de filas despues de la cual deja de leer
+#[derive(Debug)]
+pub struct TableScan {
+    pub table: String,
+    /// con `None` se leen todas las columnas
+    pub columns: Option<Vec<String>>,
+    pub filter: Option<Condition>,
+    pub index: Option<Condition>,
+    pub limit: Option<usize>,
+}
+
+/// Nodo del plan logico de una consulta, cada nodo procesa las filas que produce su entrada.
+/// No hay nodos de join ni de agregacion porque el parser no acepta JOIN ni GROUP BY, las
+/// funciones de agregacion solo existen como funciones de ventana
+#[derive(Debug)]
+pub enum LogicalPlan<'a> {
+    Scan(TableScan),
+    Filter {
+        condition: Condition,
+        input: Box<LogicalPlan<'a>>,
+    },
+    /// Calcula las funciones de ventana y las agrega como columnas
+    Window {
+        functions: &'a [WindowFunction],
+        input: Box<LogicalPlan<'a>>,
+    },
+    Project {
+        columns: Vec<String>,
+        input: Box<LogicalPlan<'a>>,
+    },
+    Sort {
+        order: &'a [OrderBy],
+        input: Box<LogicalPlan<'a>>,
+    },
+    Limit {
+        limit: usize,
+        input: Box<LogicalPlan<'a>>,
+    },
+    /// Combina los resultados de dos consultas con UNION, INTERSECT o EXCEPT
+    SetOperation {
+        operation: &'a SetOperation,
+        left: Box<LogicalPlan<'a>>,
+        right: Box<LogicalPlan<'a>>,
+    },
+}
+
+impl<'a> LogicalPlan<'a> {
+    /// Arma el plan de un select sobre una tabla: lee la tabla, filtra por el where, calcula
+    /// las funciones de ventana, deja las columnas seleccionadas, ordena y limita
+    #[allow(clippy::missing_errors_doc)]
+    pub fn select(
+        table: &str,
+        headers: &[String],
+        where_st: &Option<Condition>,
+        order: &'a Option<Vec<OrderBy>>,
+        limit: Option<usize>,
+        windows: &'a [WindowFunction],
+        db_path: &str,
+    ) -> Result<Self, ErrorType> {
+        let (_, table_header, _, schema) = open_csv_reader(&table.to_string(), db_path)
+            .map_err(|e| ErrorType::InvalidTable(format!("Error opening table: {e}")))?;
+        for window in windows {
+            window.check_columns(&table_header, schema.as_ref())?;
+        }
+        // el optimizador puede descartar comparaciones, las columnas se verifican antes
+        if let Some(condition) = where_st {
+            condition.check_columns(&table_header)?;
+        }
+        let columns = check_select_headers(&table_header, headers, windows)?;
+
+        let mut plan = LogicalPlan::Scan(TableScan {
+            table: table.to_string(),
+            columns: None,
+            filter: None,
+            index: None,
+            limit: None,
+        });
+        if let Some(condition) = where_st {
+            plan = LogicalPlan::Filter {
+                condition: condition.clone(),
+                input: Box::new(plan),
+            };
+        }
+        if !windows.is_empty() {
+            plan = LogicalPlan::Window {
+                functions: windows,
+                input: Box::new(plan),
+            };
+        }
+        plan = LogicalPlan::Project {
+            columns,
+            input: Box::new(plan),
+        };
+        Ok(plan.sorted_and_limited(order, limit))
+    }
+
+    /// Arma el plan de una consulta que produce un unico resultado, por lo que un select
+    /// debe leer de una unica tabla
+    #[allow(clippy::missing_errors_doc)]
+    pub fn query(command: &'a Commands, db_path: &str) -> Result<Self, ErrorType> {
+        match command {
+            Commands::Select {
+                headers,
+                tables,
+                where_st,
+                order,
+                limit,
+                windows,
+            } => {
+                let table_list = create_table_list(tables, db_path)?;
+                if table_list.len() != 1 {
+                    return Err(ErrorType::InvalidTable(format!(
+                        "Expected a SELECT from a single table, found {} tables",
+                        table_list.len()
+                    )));
+                }
+                Self::select(
+                    &table_list[0],
+                    headers,
+                    where_st,
+                    order,
+                    literal_limit(limit.as_ref())?,
+                    windows,
+                    db_path,
+                )
+            }
+            Commands::Compound {
+                first,
+                operations,
+                order,
+                limit,
+            } => Self::compound(
+                first,
+                operations,
+                order,
+                literal_limit(limit.as_ref())?,
+                db_path,
+            ),
+            _ => Err(ErrorType::InvalidSyntax(
+                "Expected a SELECT command as query".into(),
+            )),
+        }
+    }
+
+    /// Arma el plan de los selects combinados. INTERSECT tiene mayor precedencia que UNION y
+    /// EXCEPT, el resto de las operaciones se evaluan de izquierda a derecha
+    #[allow(clippy::missing_errors_doc)]
+    pub fn compound(
+        first: &'a Commands,
+        operations: &'a [(SetOperation, Commands)],
+        order: &'a Option<Vec<OrderBy>>,
+        limit: Option<usize>,
+        db_path: &str,
+    ) -> Result<Self, ErrorType> {
+        // primero resuelvo los INTERSECT, dejando el resto para despues
+        let mut pending: Vec<(&SetOperation, LogicalPlan)> = Vec::new();
+        let mut current = Self::query(first, db_path)?;
+        for (operation, select) in operations {
+            let plan = Self::query(select, db_path)?;
+            if operation.operator == SetOperator::Intersect {
+                current = LogicalPlan::SetOperation {
+                    operation,
+                    left: Box::new(current),
+                    right: Box::new(plan),
+                };
+            } else {
+                pending.push((operation, current));
+                current = plan;
+            }
+        }
+
+        // cada operacion pendiente guarda el operador que la une con el siguiente termino
+        let mut terms = pending.into_iter();
+        let plan = match terms.next() {
+            Some((mut operation, mut plan)) => {
+                for (next_operation, next_plan) in terms {
+                    plan = LogicalPlan::SetOperation {
+                        operation,
+                        left: Box::new(plan),
+                        right: Box::new(next_plan),
+                    };
+                    operation = next_operation;
+                }
+                LogicalPlan::SetOperation {
+                    operation,
+                    left: Box::new(plan),
+                    right: Box::new(current),
+                }
+            }
+            None => current,
+        };
+        Ok(plan.sorted_and_limited(order, limit))
+    }
+
+    /// Agrega el ORDER BY y el LIMIT sobre el plan
+    fn sorted_and_limited(mut self, order: &'a Option<Vec<OrderBy>>, limit: Option<usize>) -> Self {
+        if let Some(order) = order {
+            self = LogicalPlan::Sort {
+                order,
+                input: Box::new(self),
+            };
+        }
+        if let Some(limit) = limit {
+            self = LogicalPlan::Limit {
+                limit,
+                input: Box::new(self),
+            };
+        }
+        self
+    }
+
+    /// Aplica las reglas del optimizador:
+    /// - el filtro se simplifica, si nunca se cumple la tabla no se lee y si siempre se cumple
+    ///   se descarta
+    /// - el filtro que esta sobre la lectura de la tabla se evalua durante la lectura
+    /// - si el filtro compara una columna indexada se leen solo las filas candidatas
+    /// - la lectura solo guarda las columnas que usan los nodos de arriba
+    /// - sin orden ni ventanas de por medio, la lectura termina al alcanzar el limite
+    #[allow(clippy::missing_errors_doc)]
+    pub fn optimize(self, db_path: &str) -> Result<Self, ErrorType> {
+        Ok(match self {
+            LogicalPlan::Scan(_) => self,
+            LogicalPlan::Filter { condition, input } => match condition.simplify() {
+                Simplified::Constant(true) => input.optimize(db_path)?,
+                // ninguna fila cumple el filtro, la tabla no se recorre
+                Simplified::Constant(false) => match input.optimize(db_path)? {
+                    LogicalPlan::Scan(mut scan) => {
+                        scan.limit = Some(0);
+                        LogicalPlan::Scan(scan)
+                    }
+                    input => LogicalPlan::Limit {
+                        limit: 0,
+                        input: Box::new(input),
+                    },
+                },
+                Simplified::Condition(condition) => Self::filter(condition, *input, db_path)?,
+            },
+            LogicalPlan::Window { functions, input } => LogicalPlan::Window {
+                functions,
+                input: Box::new(input.optimize(db_path)?),
+            },
+            LogicalPlan::Project { columns, input } => {
+                let input = input.optimize(db_path)?.push_down_columns(&columns);
+                LogicalPlan::Project {
+                    columns,
+                    input: Box::new(input),
+                }
+            }
+            LogicalPlan::Sort { order, input } => LogicalPlan::Sort {
+                order,
+                input: Box::new(input.optimize(db_path)?),
+            },
+            LogicalPlan::Limit { limit, input } => LogicalPlan::Limit {
+                limit,
+                input: Box::new(input.optimize(db_path)?.push_down_limit(limit)),
+            },
+            LogicalPlan::SetOperation {
+                operation,
+                left,
+                right,
+            } => LogicalPlan::SetOperation {
+                operation,
+                left: Box::new(left.optimize(db_path)?),
+                right: Box::new(right.optimize(db_path)?),
+            },
+        })
+    }
+
+    /// Optimiza el filtro ya simplificado: si esta sobre la lectura de la tabla se evalua
+    /// durante la lectura, usando un indice si compara una columna indexada
+    fn filter(
+        condition: Condition,
+        input: LogicalPlan<'a>,
+        db_path: &str,
+    ) -> Result<Self, ErrorType> {
+        Ok(match input.optimize(db_path)? {
+            LogicalPlan::Scan(mut scan) if scan.limit.is_none() => {
+                let filter = match scan.filter.take() {
+                    Some(filter) => Condition::And(Box::new(filter), Box::new(condition)),
+                    None => condition,
+                };
+                scan.index = choose_index(&scan.table, &filter, db_path)?.cloned();
+                scan.filter = Some(filter);
+                LogicalPlan::Scan(scan)
+            }
+            input => LogicalPlan::Filter {
+                condition,
+                input: Box::new(input),
+            },
+        })
+    }
+
+    /// Lleva el limite hasta la lectura de la tabla si en el camino solo se eligen columnas
+    fn push_down_limit(self, limit: usize) -> Self {
+        match self {
+            LogicalPlan::Project { columns, input } => LogicalPlan::Project {
+                columns,
+                input: Box::new(input.push_down_limit(limit)),
+            },
+            LogicalPlan::Scan(mut scan) => {
+                scan.limit = Some(scan.limit.map_or(limit, |scan_limit| scan_limit.min(limit)));
+                LogicalPlan::Scan(scan)
+            }
+            plan => plan,
+        }
+    }
+
+    /// Lleva hasta la lectura de la tabla las columnas que necesitan los nodos de arriba.
+    /// Los filtros y ventanas del camino agregan las columnas que usan
+    fn push_down_columns(self, required: &[String]) -> Self {
+        let mut required = required.to_vec();
+        match self {
+            LogicalPlan::Scan(mut scan) => {
+                let mut columns: Vec<String> = Vec::new();
+                for column in required {
+                    if !columns.contains(&column) {
+                        columns.push(column);
+                    }
+                }
+                scan.columns = Some(columns);
+                LogicalPlan::Scan(scan)
+            }
+            LogicalPlan::Filter { condition, input } => {
+                required.extend(condition.columns().into_iter().cloned());
+                LogicalPlan::Filter {
+                    input: Box::new(input.push_down_columns(&required)),
+                    condition,
+                }
+            }
+            LogicalPlan::Window { functions, input } => {
+                // los alias de las ventanas no son columnas de la tabla
+                required.retain(|column| !functions.iter().any(|window| &window.alias == column));
+                for window in functions {
+                    required.extend(window.columns().into_iter().cloned());
+                }
+                LogicalPlan::Window {
+                    functions,
+                    input: Box::new(input.push_down_columns(&required)),
+                }
+            }
+            plan => plan,
+        }
+    }
+
+    /// Devuelve el plan junto a la medicion de cada nodo para mostrarlos juntos
+    #[must_use]
+    pub fn with_stats<'p>(&'p self, stats: &'p [NodeStats]) -> AnalyzedPlan<'p, 'a> {
+        AnalyzedPlan { plan: self, stats }
+    }
+
+    /// Escribe el nodo y debajo sus entradas. Todos los nodos, incluida la raiz, empiezan con
+    /// `-> ` y cada nivel agrega dos espacios de sangria.
+    /// Las mediciones estan en el orden en que se recorre el plan, desde `next`
+    fn fmt_tree(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        depth: usize,
+        stats: Option<&[NodeStats]>,
+        next: &mut usize,
+    ) -> fmt::Result {
+        let id = *next;
+        *next += 1;
+        write!(f, "{}-> ", "  ".repeat(depth))?;
+        let inputs = match self {
+            LogicalPlan::Scan(TableScan {
+                table,
+                columns,
+                filter,
+                index,
+                limit,
+            }) => {
+                match index {
+                    Some(index) => write!(f, "Index Scan {table} using {index}")?,
+                    None => write!(f, "Scan {table}")?,
+                }
+                if let Some(columns) = columns {
+                    write!(f, ", columns: {}", columns.join(", "))?;
+                }
+                if let Some(filter) = filter {
+                    write!(f, ", filter: {filter}")?;
+                }
+                if let Some(limit) = limit {
+                    write!(f, ", limit: {limit}")?;
+                }
+                Vec::new()
+            }
+            LogicalPlan::Filter { condition, input } => {
+                write!(f, "Filter {condition}")?;
+                vec![input]
+            }
+            LogicalPlan::Window { functions, input } => {
+                let aliases: Vec<&str> = functions.iter().map(|w| w.alias.as_str()).collect();
+                write!(f, "Window {}", aliases.join(", "))?;
+                vec![input]
+            }
+            LogicalPlan::Project { columns, input } => {
+                write!(f, "Project {}", columns.join(", "))?;
+                vec![input]
+            }
+            LogicalPlan::Sort { order, input } => {
+                let criteras: Vec<String> = order.iter().map(ToString::to_string).collect();
+                write!(f, "Sort {}", criteras.join(", "))?;
+                vec![input]
+            }
+            LogicalPlan::Limit { limit, input } => {
+                write!(f, "Limit {limit}")?;
+                vec![input]
+            }
+            LogicalPlan::SetOperation {
+                operation,
+                left,
+                right,
+            } => {
+                let operator = match operation.operator {
+                    SetOperator::Union => "Union",
+                    SetOperator::Intersect => "Intersect",
+                    SetOperator::Except => "Except",
+                };
+                write!(f, "{operator}{}", if operation.all { " All" } else { "" })?;
+                vec![left, right]
+            }
+        };
+        if let Some(node) = stats.and_then(|stats| stats.get(id)) {
+            write!(f, " {node}")?;
+        }
+        writeln!(f)?;
+        for input in inputs {
+            input.fmt_tree(f, depth + 1, stats, next)?;
+        }
+        Ok(())
+    }
+}
+
+/// Arbol del plan, un nodo por linea con sus entradas debajo
+impl fmt::Display for LogicalPlan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, 0, None, &mut 0)
+    }
+}
+
+/// Plan ejecutado con EXPLAIN ANALYZE, cada nodo muestra su medicion
+pub struct AnalyzedPlan<'p, 'a> {
+    plan: &'p LogicalPlan<'a>,
+    stats: &'p [NodeStats],
+}
+
+impl fmt::Display for AnalyzedPlan<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.plan.fmt_tree(f, 0, Some(self.stats), &mut 0)
+    }
+}
+
+/// Muestra el plan optimizado de la consulta. Con ANALYZE la ejecuta y muestra la medicion
+/// de cada nodo, sin las filas resultantes. Un select sobre varias tablas tiene un plan por tabla
+pub fn explain(query: &Commands, analyze: bool, db_path: &str) -> Result<CommandResult, ErrorType> {
+    let plans = match query {
+        Commands::Select {
+            headers,
+            tables,
+            where_st,
+            order,
+            limit,
+            windows,
+        } => create_table_list(tables, db_path)?
+            .iter()
+            .map(|table| {
+                let limit = literal_limit(limit.as_ref())?;
+                LogicalPlan::select(table, headers, where_st, order, limit, windows, db_path)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Commands::Compound { .. } => vec![LogicalPlan::query(query, db_path)?],
+        _ => {
+            return Err(ErrorType::InvalidSyntax(
+                "EXPLAIN only supports SELECT queries".into(),
+            ))
+        }
+    };
+
+    let mut output = String::new();
+    for plan in plans {
+        let plan = plan.optimize(db_path)?;
+        if analyze {
+            let start = Instant::now();
+            let stats = analyze_plan(&plan, db_path)?;
+            output.push_str(&plan.with_stats(&stats).to_string());
+            output.push_str(&format!(
+                "Execution time: {:.3} ms\n",
+                start.elapsed().as_secs_f64() * 1000.0
+            ));
+        } else {
+            output.push_str(&plan.to_string());
+        }
+    }
+    let mut result = CommandResult::new(CommandKind::Explain);
+    result.output = Some(output);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_types::Value;
+    use crate::commands::executor::execute_plan;
+    use crate::condition::ComparisonOp;
+    use crate::index::TableIndex;
+    use crate::order::Direction;
+    use crate::window::WindowFunctionKind;
+    use std::fs;
+
+    fn setup_tables(db_path: &str) {
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        fs::write(
+            format!("{db_path}/ordenes.csv"),
+            "id,id_cliente,producto,cantidad\n101,1,Laptop,1\n102,2,Mouse,2\n",
+        )
+        .expect("Failed to write table");
+        fs::write(format!("{db_path}/clientes.csv"), "id,nombre\n1,Juan\n")
+            .expect("Failed to write table");
+    }
+
+    fn comparison(column: &str, op: ComparisonOp, value: &str) -> Condition {
+        Condition::Comparison(column.to_string(), op, value.to_string())
+    }
+
+    #[test]
+    fn test_select_plan_is_optimized() {
+        let db_path = "./test_planner_db1";
+        setup_tables(db_path);
+        let headers = vec!["id".to_string(), "producto".to_string()];
+        let where_st = Some(Condition::And(
+            Box::new(comparison("cantidad", ComparisonOp::Gt, "1")),
+            Box::new(comparison("id_cliente", ComparisonOp::Eq, "2")),
+        ));
+
+        let plan =
+            LogicalPlan::select("ordenes", &headers, &where_st, &None, Some(5), &[], db_path)
+                .unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "-> Limit 5\n  \
+             -> Project id, producto\n    \
+               -> Filter cantidad > 1 AND id_cliente = 2\n      \
+                 -> Scan ordenes\n"
+        );
+
+        // el filtro pasa a la lectura y el limite llega hasta ella
+        let optimized = plan.optimize(db_path).unwrap();
+        assert_eq!(
+            optimized.to_string(),
+            "-> Limit 5\n  \
+             -> Project id, producto\n    \
+               -> Scan ordenes, columns: id, producto, filter: cantidad > 1 AND id_cliente = 2, limit: 5\n"
+        );
+
+        // con un indice se usa la igualdad sobre la columna indexada
+        TableIndex::build("ordenes", "id_cliente", db_path)
+            .unwrap()
+            .save("ordenes", db_path)
+            .unwrap();
+        let order = Some(vec![OrderBy {
+            column: "id".to_string(),
+            direction: Direction::Descending,
+        }]);
+        let plan = LogicalPlan::select(
+            "ordenes",
+            &headers,
+            &where_st,
+            &order,
+            Some(5),
+            &[],
+            db_path,
+        )
+        .unwrap()
+        .optimize(db_path)
+        .unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "-> Limit 5\n  \
+             -> Sort id DESC\n    \
+               -> Project id, producto\n      \
+                 -> Index Scan ordenes using id_cliente = 2, columns: id, producto, filter: cantidad > 1 AND id_cliente = 2\n"
+        );
+
+        let headers = vec!["noexiste".to_string()];
+        assert!(matches!(
+            LogicalPlan::select("ordenes", &headers, &None, &None, None, &[], db_path),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_scan_reads_only_required_columns() {
+        let db_path = "./test_planner_db3";
+        setup_tables(db_path);
+        let headers = vec!["producto".to_string(), "fila".to_string()];
+        let windows = vec![WindowFunction {
+            function: WindowFunctionKind::RowNumber,
+            partition_by: vec!["id_cliente".to_string()],
+            order_by: Vec::new(),
+            alias: "fila".to_string(),
+        }];
+        let where_st = Some(comparison("cantidad", ComparisonOp::Gt, "0"));
+
+        // el alias de la ventana no se lee de la tabla, la columna que particiona si
+        let plan = LogicalPlan::select(
+            "ordenes", &headers, &where_st, &None, None, &windows, db_path,
+        )
+        .unwrap()
+        .optimize(db_path)
+        .unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "-> Project producto, fila\n  \
+             -> Window fila\n    \
+               -> Scan ordenes, columns: producto, id_cliente, filter: cantidad > 0\n"
+        );
+
+        let plan = LogicalPlan::select(
+            "ordenes", &headers, &where_st, &None, None, &windows, db_path,
+        )
+        .unwrap();
+        let (header, rows) = execute_plan(plan, db_path).unwrap();
+        assert_eq!(header, ["producto", "fila"]);
+        assert_eq!(rows, [["Laptop", "1"], ["Mouse", "1"]]);
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_constant_filter_is_folded() {
+        let db_path = "./test_planner_db4";
+        setup_tables(db_path);
+        let headers = vec!["id".to_string()];
+
+        // el filtro que nunca se cumple evita leer la tabla
+        let where_st = Some(Condition::And(
+            Box::new(comparison("1", ComparisonOp::Eq, "0")),
+            Box::new(comparison("cantidad", ComparisonOp::Gt, "1")),
+        ));
+        let plan = LogicalPlan::select("ordenes", &headers, &where_st, &None, None, &[], db_path)
+            .unwrap()
+            .optimize(db_path)
+            .unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "-> Project id\n  -> Scan ordenes, columns: id, limit: 0\n"
+        );
+        let stats = analyze_plan(&plan, db_path).unwrap();
+        assert_eq!((stats[1].rows_in, stats[1].rows_out), (0, 0));
+
+        // el que siempre se cumple se descarta y la doble negacion se elimina
+        let where_st = Some(Condition::Or(
+            Box::new(Condition::Not(Box::new(Condition::Not(Box::new(
+                comparison("cantidad", ComparisonOp::Gt, "1"),
+            ))))),
+            Box::new(comparison("'a'", ComparisonOp::Eq, "a")),
+        ));
+        let plan = LogicalPlan::select("ordenes", &headers, &where_st, &None, None, &[], db_path)
+            .unwrap()
+            .optimize(db_path)
+            .unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "-> Project id\n  -> Scan ordenes, columns: id\n"
+        );
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_compound_plan_respects_intersect_precedence() {
+        let db_path = "./test_planner_db2";
+        setup_tables(db_path);
+        let select = |table: &str| Commands::Select {
+            headers: vec!["id".to_string()],
+            tables: vec![table.to_string()],
+            where_st: None,
+            order: None,
+            limit: None,
+            windows: Vec::new(),
+        };
+        let query = Commands::Compound {
+            first: Box::new(select("ordenes")),
+            operations: vec![
+                (
+                    SetOperation {
+                        operator: SetOperator::Union,
+                        all: true,
+                    },
+                    select("clientes"),
+                ),
+                (
+                    SetOperation {
+                        operator: SetOperator::Intersect,
+                        all: false,
+                    },
+                    select("ordenes"),
+                ),
+            ],
+            order: None,
+            limit: Some(Value::Literal(1)),
+        };
+
+        // el limite no pasa a traves de la operacion de conjuntos
+        let plan = LogicalPlan::query(&query, db_path)
+            .unwrap()
+            .optimize(db_path)
+            .unwrap();
+        assert_eq!(
+            plan.to_string(),
+            "-> Limit 1\n  \
+             -> Union All\n    \
+               -> Project id\n      \
+                 -> Scan ordenes, columns: id\n    \
+               -> Intersect\n      \
+                 -> Project id\n        \
+                   -> Scan clientes, columns: id\n      \
+                 -> Project id\n        \
+                   -> Scan ordenes, columns: id\n"
+        );
+
+        let result = explain(&query, false, db_path).unwrap();
+        assert_eq!(result.output, Some(plan.to_string()));
+        assert!(!result.is_write());
+
+        let delete = Commands::Delete {
+            tables: vec!["ordenes".to_string()],
+            where_st: None,
+            returning: None,
+        };
+        assert!(matches!(
+            explain(&delete, false, db_path),
+            Err(ErrorType::InvalidSyntax(_))
+        ));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
@@ -1,24 +1,31 @@
-use super::utils_commands::{create_row_values_map, create_table_list, open_csv_reader};
-use crate::commands::utils_commands::should_filter;
+use super::executor::write_plan;
+use super::planner::LogicalPlan;
+use super::utils_commands::create_table_list;
+use crate::command_result::{CommandKind, CommandResult};
 use crate::condition::Condition;
 use crate::errors::ErrorType;
-use crate::order::{Direction, OrderBy};
-use std::io::BufRead;
+use crate::order::OrderBy;
+use crate::window::WindowFunction;
+use std::io::Write;
 
-/// Verifica que los headers seleccionados estén en la tabla
-/// Si los headers contienen un asterisco (*), selecciona todos los headers de la tabla.
-fn check_select_headers(
+/// Verifica que los headers seleccionados estén en la tabla o sean el alias de una funcion de ventana
+/// Si los headers contienen un asterisco (*), lo reemplaza por todos los headers de la tabla.
+pub fn check_select_headers(
     table_header: &[String],
     headers: &[String],
+    windows: &[WindowFunction],
 ) -> Result<Vec<String>, ErrorType> {
-    if headers.len() == 1 && headers[0] == "*" {
-        // Si solo hay un asterisco, devolvemos todos los headers de la tabla
-        return Ok(table_header.to_vec());
-    }
+    let mut select_headers = Vec::new();
 
-    // Si no hay asterisco, verificamos que todos los headers existan en la tabla
     for header in headers {
-        if !table_header.contains(header) {
+        if header == "*" {
+            // el asterisco selecciona todos los headers de la tabla
+            select_headers.extend(table_header.iter().cloned());
+        } else if table_header.contains(header)
+            || windows.iter().any(|window| &window.alias == header)
+        {
+            select_headers.push(header.to_string());
+        } else {
             return Err(ErrorType::InvalidColumn(format!(
                 "Column to select '{header}' not found in the table"
             )));
@@ -26,121 +33,63 @@ fn check_select_headers(
     }
 
     // Retornamos los headers seleccionados si son válidos
-    Ok(headers.to_vec())
+    Ok(select_headers)
 }
 
-/// Ordena las filas según lo pedido en la query
-fn sort_rows(
-    selected_rows: &mut [Vec<String>],
+/// Ejecuta el select juntando el resultado en el output, pensado para los tests
+#[allow(clippy::missing_errors_doc)]
+pub fn select(
     headers: &[String],
-    criteras: &[OrderBy],
-) -> Result<(), ErrorType> {
-    // Verificar si todas las columnas de ordenación existen en los headers
-    for OrderBy { column, .. } in criteras {
-        if !headers.contains(column) {
-            return Err(ErrorType::InvalidColumn(format!(
-                "Column '{column}' not found in headers"
-            )));
-        }
-    }
-
-    // ordena si las columnas son válidas
-    selected_rows.sort_by(|a, b| {
-        for OrderBy { column, direction } in criteras {
-            if let Some(idx) = headers.iter().position(|header| header == column) {
-                if idx < a.len() && idx < b.len() {
-                    let value_a = &a[idx];
-                    let value_b = &b[idx];
-
-                    let cmp = value_a.cmp(value_b);
-                    if cmp != std::cmp::Ordering::Equal {
-                        return match direction {
-                            Direction::Ascending => cmp,
-                            Direction::Descending => cmp.reverse(),
-                        };
-                    }
-                }
-            }
-        }
-        std::cmp::Ordering::Equal
-    });
-
-    Ok(())
-}
-
-/// funcion auxiliar para retornar el valor del output y utilizar en testings
-fn get_select_output(select_headers: &[String], selected_rows: &[Vec<String>]) -> String {
-    let mut output = String::new();
-
-    // Agregamos los headers
-    output.push_str(&select_headers.join(","));
-    output.push('\n'); // Añadimos una nueva línea después de los headers
-
-    // Agregamos cada fila
-    for line in selected_rows {
-        output.push_str(&line.join(","));
-        output.push('\n'); // Añadimos una nueva línea después de cada fila
-    }
-
-    output
+    tables: &[String],
+    where_st: &Option<Condition>,
+    order: &Option<Vec<OrderBy>>,
+    limit: Option<usize>,
+    windows: &[WindowFunction],
+    db_path: &str,
+) -> Result<CommandResult, ErrorType> {
+    let mut output = Vec::new();
+    let mut result = select_to(
+        headers,
+        tables,
+        where_st,
+        order,
+        limit,
+        windows,
+        db_path,
+        &mut output,
+    )?;
+    result.output = Some(String::from_utf8_lossy(&output).into_owned());
+    Ok(result)
 }
 
-pub fn select(
+/// Ejecuta el select escribiendo en `out` las filas de cada tabla a medida que se leen
+#[allow(clippy::missing_errors_doc, clippy::too_many_arguments)]
+pub fn select_to(
     headers: &[String],
     tables: &[String],
     where_st: &Option<Condition>,
     order: &Option<Vec<OrderBy>>,
+    limit: Option<usize>,
+    windows: &[WindowFunction],
     db_path: &str,
-) -> Result<Option<String>, ErrorType> {
+    out: &mut dyn Write,
+) -> Result<CommandResult, ErrorType> {
     let table_list = create_table_list(tables, db_path)?;
-    let mut output = String::new();
+    let mut result = CommandResult::new(CommandKind::Select);
 
     for table in table_list {
-        // abro archivo
-        let (mut reader, table_header, _) = open_csv_reader(&table, db_path)
-            .map_err(|e| ErrorType::InvalidTable(format!("Error opening table: {e}")))?;
-
-        let select_headers = check_select_headers(&table_header, headers)?;
-
-        // filtro el where, dejo los headers selccionados
-        let mut selected_rows: Vec<Vec<String>> = Vec::new();
-        let mut buffer = String::new();
-        while reader
-            .read_line(&mut buffer)
-            .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?
-            > 0
-        {
-            let row_values_map = create_row_values_map(&table_header, &buffer);
-            buffer.clear();
-
-            let should_select = should_filter(where_st, &row_values_map);
-
-            if should_select {
-                let selected_row: Vec<String> = select_headers
-                    .iter()
-                    .map(|select_headers| row_values_map[select_headers].to_string())
-                    .collect();
-                selected_rows.push(selected_row);
-            }
-        }
-
-        // con los seleccionados del where ordeno segun criterio pedido
-        if let Some(criteras) = order {
-            sort_rows(&mut selected_rows, &select_headers, criteras)?;
-        }
-        output.push_str(&get_select_output(&select_headers, &selected_rows));
-        println!("{}", select_headers.join(","));
-        for line in selected_rows {
-            println!("{}", line.join(","));
-        }
+        let plan = LogicalPlan::select(&table, headers, where_st, order, limit, windows, db_path)?;
+        let rows = write_plan(plan, db_path, out)?;
+        result.add_table_rows(&table, rows);
     }
-    Ok(Some(output))
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::condition::{ComparisonOp, Condition};
+    use crate::order::Direction;
     use std::fs::{self, File};
     use std::io::Write;
     use std::path::Path;
@@ -180,7 +129,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -198,11 +147,19 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let select_result = select(&headers, &tables, &where_condition, &None, db_path);
+        let select_result = select(
+            &headers,
+            &tables,
+            &where_condition,
+            &None,
+            None,
+            &[],
+            db_path,
+        );
 
         // Verificar el output
         let expected_output = "id,nombre\n1,Juan\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -215,7 +172,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -233,11 +190,19 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let select_result = select(&headers, &tables, &where_condition, &None, db_path);
+        let select_result = select(
+            &headers,
+            &tables,
+            &where_condition,
+            &None,
+            None,
+            &[],
+            db_path,
+        );
 
         // Como no hay filas que coincidan con la condicion, el es solo los headers
         let expected_output = "id,nombre\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -250,7 +215,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -262,11 +227,11 @@ mod tests {
         );
 
         let tables = vec![table_name.to_string()];
-        let select_result = select(&headers, &tables, &None, &None, db_path);
+        let select_result = select(&headers, &tables, &None, &None, None, &[], db_path);
 
         // Verificar el output sin condición WHERE, debería incluir todas las filas
         let expected_output = "id,nombre\n1,Juan\n2,Maria\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -279,7 +244,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -295,11 +260,19 @@ mod tests {
             direction: Direction::Ascending,
         }];
         let tables = vec![table_name.to_string()];
-        let select_result = select(&headers, &tables, &None, &Some(order_by), db_path);
+        let select_result = select(
+            &headers,
+            &tables,
+            &None,
+            &Some(order_by),
+            None,
+            &[],
+            db_path,
+        );
 
         // Verificar el output con ORDER BY ascendente
         let expected_output = "id,nombre\n1,Juan\n2,Maria\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -312,7 +285,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -338,12 +311,14 @@ mod tests {
             &tables,
             &where_condition,
             &Some(order_by),
+            None,
+            &[],
             db_path,
         );
 
         // Verificar el output
         let expected_output = "id,nombre\n1,Juan\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -356,7 +331,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -382,12 +357,14 @@ mod tests {
             &tables,
             &where_condition,
             &Some(order_by),
+            None,
+            &[],
             db_path,
         );
 
         // Como no hay filas que coincidan con la condición WHERE, solo deben aparecer los headers
         let expected_output = "id,nombre\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -400,7 +377,7 @@ mod tests {
         let headers = vec!["id".to_string(), "nombre".to_string()];
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
@@ -420,11 +397,19 @@ mod tests {
         let tables = vec![table_name.to_string()];
 
         // Ejecutar la selección
-        let select_result = select(&headers, &tables, &None, &Some(order_by), db_path);
+        let select_result = select(
+            &headers,
+            &tables,
+            &None,
+            &Some(order_by),
+            None,
+            &[],
+            db_path,
+        );
 
         // Verificar el output
         let expected_output = "id,nombre\n3,Ana\n2,Maria\n1,Juan\n";
-        assert_eq!(select_result.unwrap().unwrap(), expected_output);
+        assert_eq!(select_result.unwrap().output.unwrap(), expected_output);
 
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
@@ -440,7 +425,7 @@ mod tests {
         let headers2 = vec!["id".to_string(), "producto".to_string()];
 
         // Crear las tablas con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name1,
@@ -464,10 +449,10 @@ mod tests {
         let tables = vec![table_name1.to_string(), table_name2.to_string()];
         let headers = vec!["*".to_string()]; // SELECT *
 
-        let select_result = select(&headers, &tables, &None, &None, db_path);
+        let select_result = select(&headers, &tables, &None, &None, None, &[], db_path);
         assert!(select_result.is_ok(), "Select failed: {:?}", select_result);
 
-        let output = select_result.unwrap();
+        let output = select_result.unwrap().output;
         assert!(output.is_some(), "Output is None");
 
         let expected_output = "id,nombre\n1,Juan\n2,Maria\nid,producto\n1,Laptop\n2,Mouse\n";
@@ -0,0 +1,400 @@
+use crate::errors::ErrorType;
+use crate::order::{Direction, OrderBy};
+use crate::schema::{DataType, TableSchema};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    env,
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Write},
+    mem,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+/// Memoria por defecto para ordenar filas antes de volcarlas a disco, 64 MiB
+pub const DEFAULT_SORT_MEMORY: usize = 64 * 1024 * 1024;
+
+/// Variable de entorno con la memoria en bytes para ordenar filas
+pub const SORT_MEMORY_VAR: &str = "SQL_RUSTICO_SORT_MEMORY";
+
+/// Devuelve la memoria para ordenar filas, la de la variable de entorno si es valida
+#[must_use]
+pub fn sort_memory() -> usize {
+    env::var(SORT_MEMORY_VAR)
+        .ok()
+        .and_then(|bytes| bytes.parse().ok())
+        .unwrap_or(DEFAULT_SORT_MEMORY)
+}
+
+/// Criterio de orden resuelto contra el header: posicion, direccion y tipo de cada columna
+#[derive(Debug, Clone)]
+pub struct SortKey {
+    columns: Vec<(usize, Direction, Option<DataType>)>,
+}
+
+impl SortKey {
+    /// Resuelve las columnas del ORDER BY, falla si alguna no esta en el header
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(
+        header: &[String],
+        order: &[OrderBy],
+        schema: Option<&TableSchema>,
+    ) -> Result<Self, ErrorType> {
+        let columns = order
+            .iter()
+            .map(|OrderBy { column, direction }| {
+                let idx = header.iter().position(|name| name == column).ok_or(
+                    ErrorType::InvalidColumn(format!("Column '{column}' not found in headers")),
+                )?;
+                let data_type = schema.and_then(|schema| schema.data_type(column));
+                Ok((idx, *direction, data_type))
+            })
+            .collect::<Result<_, ErrorType>>()?;
+        Ok(SortKey { columns })
+    }
+
+    /// Compara dos filas segun el tipo declarado de cada columna, sin tipo como texto
+    #[must_use]
+    pub fn compare(&self, a: &[String], b: &[String]) -> Ordering {
+        for (idx, direction, data_type) in &self.columns {
+            let (Some(value_a), Some(value_b)) = (a.get(*idx), b.get(*idx)) else {
+                continue;
+            };
+            let cmp = match data_type {
+                Some(data_type) => data_type.compare(value_a, value_b),
+                None => value_a.cmp(value_b),
+            };
+            if cmp != Ordering::Equal {
+                return match direction {
+                    Direction::Ascending => cmp,
+                    Direction::Descending => cmp.reverse(),
+                };
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Ordena las filas en memoria, las filas iguales conservan su orden
+    pub fn sort(&self, rows: &mut [Vec<String>]) {
+        rows.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+/// Archivo temporal con una tanda de filas ordenadas, se borra al dejar de usarse
+struct RunFile {
+    path: String,
+}
+
+impl Drop for RunFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Numera los archivos temporales para que no se pisen entre ordenamientos
+static RUN_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl RunFile {
+    /// Crea un archivo temporal en la base y escribe las filas, una por linea
+    fn write(
+        db_path: &str,
+        rows: impl Iterator<Item = Result<Vec<String>, ErrorType>>,
+    ) -> Result<Self, ErrorType> {
+        let id = RUN_COUNTER.fetch_add(1, AtomicOrdering::Relaxed);
+        let run = RunFile {
+            path: format!("{db_path}/sort_{}_{id}.run", std::process::id()),
+        };
+        let mut writer = BufWriter::new(File::create(&run.path).map_err(|e| io_error(&e))?);
+        for row in rows {
+            writeln!(writer, "{}", row?.join(",")).map_err(|e| io_error(&e))?;
+        }
+        writer.flush().map_err(|e| io_error(&e))?;
+        Ok(run)
+    }
+}
+
+/// Cantidad maxima de archivos temporales que se mezclan a la vez
+pub const DEFAULT_MERGE_FAN_IN: usize = 64;
+
+/// Ordena filas sin superar la memoria indicada: al superarla ordena las filas que tiene y
+/// las vuelca a un archivo temporal en la base. Al terminar mezcla los archivos, de a lo sumo
+/// `fan_in` por vez, en tantas pasadas como haga falta
+pub struct ExternalSorter<'p> {
+    key: SortKey,
+    db_path: &'p str,
+    memory: usize,
+    fan_in: usize,
+    buffer: Vec<Vec<String>>,
+    buffer_bytes: usize,
+    runs: Vec<RunFile>,
+}
+
+/// Estima la memoria que ocupa la fila
+fn row_size(row: &[String]) -> usize {
+    mem::size_of::<Vec<String>>()
+        + row
+            .iter()
+            .map(|field| mem::size_of::<String>() + field.len())
+            .sum::<usize>()
+}
+
+fn io_error(e: &std::io::Error) -> ErrorType {
+    ErrorType::OtherError(format!("Error writing sort run: {e}"))
+}
+
+impl<'p> ExternalSorter<'p> {
+    /// Crea el ordenamiento, se mezclan como minimo dos archivos a la vez
+    #[must_use]
+    pub fn new(key: SortKey, db_path: &'p str, memory: usize, fan_in: usize) -> Self {
+        ExternalSorter {
+            key,
+            db_path,
+            memory,
+            fan_in: fan_in.max(2),
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Agrega una fila, si se supera la memoria vuelca las filas a disco
+    #[allow(clippy::missing_errors_doc)]
+    pub fn push(&mut self, row: Vec<String>) -> Result<(), ErrorType> {
+        self.buffer_bytes += row_size(&row);
+        self.buffer.push(row);
+        if self.buffer_bytes > self.memory {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Cantidad de archivos temporales escritos hasta ahora
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Ordena las filas en memoria y las escribe en un archivo temporal
+    fn spill(&mut self) -> Result<(), ErrorType> {
+        self.key.sort(&mut self.buffer);
+        let run = RunFile::write(self.db_path, self.buffer.drain(..).map(Ok))?;
+        self.runs.push(run);
+        self.buffer_bytes = 0;
+        Ok(())
+    }
+
+    /// Devuelve las filas ordenadas. Si nada se volco a disco se ordenan en memoria
+    #[allow(clippy::missing_errors_doc)]
+    pub fn finish(mut self) -> Result<SortedRows, ErrorType> {
+        if self.runs.is_empty() {
+            self.key.sort(&mut self.buffer);
+            return Ok(SortedRows(Source::Memory(self.buffer.into_iter())));
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        // cada pasada mezcla grupos de archivos consecutivos, asi se mantiene el orden de
+        // llegada entre filas iguales
+        let key = Rc::new(self.key);
+        let mut runs = self.runs;
+        while runs.len() > self.fan_in {
+            let mut merged = Vec::new();
+            let mut pending = runs.into_iter();
+            loop {
+                let group: Vec<RunFile> = pending.by_ref().take(self.fan_in).collect();
+                if group.is_empty() {
+                    break;
+                }
+                let merge = Merge::new(Rc::clone(&key), group)?;
+                merged.push(RunFile::write(self.db_path, merge)?);
+            }
+            runs = merged;
+        }
+        Ok(SortedRows(Source::Merge(Merge::new(key, runs)?)))
+    }
+}
+
+/// Archivo temporal que se esta mezclando
+struct MergeRun {
+    reader: BufReader<File>,
+    _file: RunFile,
+}
+
+impl MergeRun {
+    /// Lee la proxima fila del archivo
+    fn next_row(&mut self) -> Result<Option<Vec<String>>, ErrorType> {
+        let mut line = String::new();
+        let bytes = self
+            .reader
+            .read_line(&mut line)
+            .map_err(|e| ErrorType::OtherError(format!("Error reading sort run: {e}")))?;
+        Ok((bytes > 0).then(|| {
+            line.trim_end_matches('\n')
+                .split(',')
+                .map(ToString::to_string)
+                .collect()
+        }))
+    }
+}
+
+/// Proxima fila de un archivo dentro del heap de la mezcla. El heap de la libreria estandar
+/// devuelve el mayor, por eso el orden esta invertido. Ante filas iguales sale primero la del
+/// archivo anterior, asi el orden es estable
+struct HeapEntry {
+    row: Vec<String>,
+    run: usize,
+    key: Rc<SortKey>,
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .compare(&self.row, &other.row)
+            .then(self.run.cmp(&other.run))
+            .reverse()
+    }
+}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+/// Mezcla de archivos temporales ordenados, el heap tiene la proxima fila de cada archivo
+struct Merge {
+    key: Rc<SortKey>,
+    runs: Vec<MergeRun>,
+    heap: BinaryHeap<HeapEntry>,
+}
+
+impl Merge {
+    /// Abre los archivos y lee la primera fila de cada uno
+    fn new(key: Rc<SortKey>, files: Vec<RunFile>) -> Result<Self, ErrorType> {
+        let mut merge = Merge {
+            key,
+            runs: Vec::with_capacity(files.len()),
+            heap: BinaryHeap::with_capacity(files.len()),
+        };
+        for file in files {
+            let reader = File::open(&file.path)
+                .map_err(|e| ErrorType::OtherError(format!("Error reading sort run: {e}")))?;
+            merge.runs.push(MergeRun {
+                reader: BufReader::new(reader),
+                _file: file,
+            });
+            merge.refill(merge.runs.len() - 1)?;
+        }
+        Ok(merge)
+    }
+
+    /// Agrega al heap la proxima fila del archivo, si le quedan
+    fn refill(&mut self, run: usize) -> Result<(), ErrorType> {
+        if let Some(row) = self.runs[run].next_row()? {
+            self.heap.push(HeapEntry {
+                row,
+                run,
+                key: Rc::clone(&self.key),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Merge {
+    type Item = Result<Vec<String>, ErrorType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let HeapEntry { row, run, .. } = self.heap.pop()?;
+        Some(self.refill(run).map(|()| row))
+    }
+}
+
+/// Filas ordenadas, en memoria o mezclando los archivos temporales
+pub struct SortedRows(Source);
+
+enum Source {
+    Memory(std::vec::IntoIter<Vec<String>>),
+    Merge(Merge),
+}
+
+impl Iterator for SortedRows {
+    type Item = Result<Vec<String>, ErrorType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            Source::Memory(rows) => rows.next().map(Ok),
+            Source::Merge(merge) => merge.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_sort_spills_and_merges() {
+        let db_path = "./test_sort_db1";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let header = vec!["id".to_string(), "nombre".to_string()];
+        let order = vec![OrderBy {
+            column: "nombre".to_string(),
+            direction: Direction::Ascending,
+        }];
+        let key = SortKey::new(&header, &order, None).unwrap();
+
+        // con poca memoria cada par de filas termina en un archivo distinto
+        let memory = 2 * row_size(&["0".to_string(), "a".to_string()]) - 1;
+        let mut sorter = ExternalSorter::new(key, db_path, memory, DEFAULT_MERGE_FAN_IN);
+        let names = ["d", "b", "a", "c", "b", "a", "e"];
+        for (id, name) in names.iter().enumerate() {
+            sorter.push(vec![id.to_string(), name.to_string()]).unwrap();
+        }
+        assert_eq!(sorter.runs(), 3);
+
+        let rows: Vec<Vec<String>> = sorter.finish().unwrap().collect::<Result<_, _>>().unwrap();
+        let ids: Vec<&str> = rows.iter().map(|row| row[0].as_str()).collect();
+        // las filas iguales mantienen el orden en que llegaron
+        assert_eq!(ids, ["2", "5", "1", "4", "3", "0", "6"]);
+
+        // los archivos temporales se borran al terminar
+        assert_eq!(fs::read_dir(db_path).unwrap().count(), 0);
+
+        // mezclando de a dos archivos hacen falta varias pasadas y el orden es el mismo
+        let key = SortKey::new(&header, &order, None).unwrap();
+        let mut sorter = ExternalSorter::new(key, db_path, memory, 2);
+        let names = ["d", "b", "a", "c", "b", "a", "e", "a", "c", "b", "a"];
+        for (id, name) in names.iter().enumerate() {
+            sorter.push(vec![id.to_string(), name.to_string()]).unwrap();
+        }
+        assert_eq!(sorter.runs(), 5);
+        let rows: Vec<Vec<String>> = sorter.finish().unwrap().collect::<Result<_, _>>().unwrap();
+        let ids: Vec<&str> = rows.iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(
+            ids,
+            ["2", "5", "7", "10", "1", "4", "9", "3", "8", "0", "6"]
+        );
+        assert_eq!(fs::read_dir(db_path).unwrap().count(), 0);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+
+        let order = vec![OrderBy {
+            column: "apellido".to_string(),
+            direction: Direction::Ascending,
+        }];
+        assert!(matches!(
+            SortKey::new(&header, &order, None),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::errors::ErrorType;
+use crate::schema::{TableSchema, UniqueConstraint};
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+};
+
+/// Devuelve los valores de la fila en las columnas de una clave, None si alguno es NULL
+pub fn row_key(indexes: &[usize], row: &[String]) -> Option<Vec<String>> {
+    indexes
+        .iter()
+        .map(|&index| row.get(index).filter(|value| !value.is_empty()).cloned())
+        .collect()
+}
+
+/// Claves presentes en la tabla para cada restriccion PRIMARY KEY y UNIQUE.
+/// Se arma leyendo la tabla una vez y despues cada fila se verifica sin volver a leerla
+pub struct UniqueKeys {
+    table: String,
+    /// restriccion, posicion de sus columnas en el header y claves usadas
+    constraints: Vec<(UniqueConstraint, Vec<usize>, HashSet<Vec<String>>)>,
+}
+
+impl UniqueKeys {
+    /// Prepara las restricciones de la tabla, sin claves cargadas
+    pub fn new(
+        table: &str,
+        schema: Option<&TableSchema>,
+        header_index: &HashMap<String, usize>,
+    ) -> Result<Self, ErrorType> {
+        let mut constraints = Vec::new();
+        for key in schema
+            .map(TableSchema::unique_constraints)
+            .unwrap_or_default()
+        {
+            let mut indexes = Vec::new();
+            for column in &key.columns {
+                let index = header_index
+                    .get(column)
+                    .ok_or(ErrorType::InvalidColumn(format!(
+                        "Column '{column}' of {key} not found in the table"
+                    )))?;
+                indexes.push(*index);
+            }
+            constraints.push((key, indexes, HashSet::new()));
+        }
+
+        Ok(Self {
+            table: table.to_string(),
+            constraints,
+        })
+    }
+
+    /// Indica si la tabla no tiene restricciones de unicidad
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// Carga las claves de todas las filas que quedan por leer en el reader
+    pub fn load(&mut self, reader: &mut impl BufRead) -> Result<(), ErrorType> {
+        let mut buffer = String::new();
+        while reader
+            .read_line(&mut buffer)
+            .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?
+            > 0
+        {
+            let row: Vec<String> = buffer.trim().split(',').map(ToString::to_string).collect();
+            self.insert(&row)?;
+            buffer.clear();
+        }
+        Ok(())
+    }
+
+    /// Agrega las claves de la fila. Falla si alguna ya estaba usada por otra fila
+    pub fn insert(&mut self, row: &[String]) -> Result<(), ErrorType> {
+        for (key, indexes, used) in &self.constraints {
+            if let Some(row_key) = row_key(indexes, row) {
+                if used.contains(&row_key) {
+                    return Err(ErrorType::ConstraintViolation(format!(
+                        "Duplicate key ({})=({}) violates {} of table '{}'",
+                        key.columns.join(", "),
+                        row_key.join(", "),
+                        key.kind(),
+                        self.table
+                    )));
+                }
+            }
+        }
+        for (_, indexes, used) in &mut self.constraints {
+            if let Some(row_key) = row_key(indexes, row) {
+                used.insert(row_key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Libera las claves de la fila, por ejemplo antes de actualizarla
+    pub fn remove(&mut self, row: &[String]) {
+        for (_, indexes, used) in &mut self.constraints {
+            if let Some(row_key) = row_key(indexes, row) {
+                used.remove(&row_key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_column_definition;
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_unique_keys() {
+        let mut schema = TableSchema::new(vec![
+            parse_column_definition("id INTEGER PRIMARY KEY").unwrap(),
+            parse_column_definition("email TEXT UNIQUE").unwrap(),
+        ]);
+        schema.keys.push(UniqueConstraint {
+            columns: vec!["id".to_string(), "email".to_string()],
+            primary_key: false,
+        });
+        let header_index = HashMap::from([("id".to_string(), 0), ("email".to_string(), 1)]);
+        let mut keys = UniqueKeys::new("clientes", Some(&schema), &header_index).unwrap();
+        assert!(!keys.is_empty());
+
+        keys.load(&mut "1,a@mail.com\n2,\n".as_bytes()).unwrap();
+
+        // los NULL no chocan entre si
+        assert!(keys.insert(&row(&["3", ""])).is_ok());
+        let result = keys.insert(&row(&["1", "b@mail.com"]));
+        if let Err(ErrorType::ConstraintViolation(message)) = result {
+            assert_eq!(
+                message,
+                "Duplicate key (id)=(1) violates PRIMARY KEY of table 'clientes'"
+            );
+        } else {
+            panic!("Expected a constraint violation");
+        }
+        assert!(matches!(
+            keys.insert(&row(&["4", "a@mail.com"])),
+            Err(ErrorType::ConstraintViolation(_))
+        ));
+
+        // al liberar la fila su clave vuelve a estar disponible
+        keys.remove(&row(&["1", "a@mail.com"]));
+        assert!(keys.insert(&row(&["1", "a@mail.com"])).is_ok());
+
+        assert!(UniqueKeys::new("clientes", None, &header_index)
+            .unwrap()
+            .is_empty());
+    }
+}
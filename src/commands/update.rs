@@ -1,17 +1,22 @@
+use super::foreign_keys::{ForeignKeyCheck, ReferencedKeys};
+use super::unique_keys::UniqueKeys;
 use super::utils_commands::{
-    create_aux_file, create_row_values_map, create_table_list, open_csv_reader, should_filter,
+    compile_where, create_aux_file, create_row_values_map, create_table_list, open_csv_reader,
+    should_filter, ReturningRows,
 };
+use crate::command_result::{CommandKind, CommandResult};
 use crate::condition::Condition;
 use crate::errors::ErrorType;
+use crate::index::{index_scan, rebuild_indexes};
 use std::{
     collections::HashMap,
     fs::File,
-    io::{BufRead, Write},
+    io::{BufRead, Seek, Write},
 };
 
 /// Verifica que los headers a actualizar esten en la tabla
-fn check_update_headers(
-    updates: &HashMap<String, String>,
+pub fn check_update_headers<V>(
+    updates: &HashMap<String, V>,
     header_index: &HashMap<String, usize>,
 ) -> Result<(), ErrorType> {
     for key in updates.keys() {
@@ -26,7 +31,7 @@ fn check_update_headers(
 }
 
 /// Actualiza los valores de la fila
-fn update_row_values(
+pub fn update_row_values(
     table_header: &[String],
     row_values_map: &HashMap<String, String>,
     updates: &HashMap<String, String>,
@@ -42,81 +47,151 @@ fn update_row_values(
         .collect()
 }
 
-// Rearma la fila con los valores actualizados de haber, sino reuso los del buffer
+// Escribe la fila actualizada de haberla, sino reuso los valores del buffer.
 #[allow(clippy::module_name_repetitions)]
 pub fn write_update(
     aux_file: &mut File,
-    should_update: bool,
-    table_header: &[String],
-    row_values_map: &HashMap<String, String>,
-    updates: &HashMap<String, String>,
+    updated_row: Option<&[String]>,
     buffer: &str,
 ) -> Result<(), ErrorType> {
-    if should_update {
-        let updated_row = update_row_values(table_header, row_values_map, updates);
-        writeln!(aux_file, "{}", updated_row.join(","))
-            .map_err(|e| ErrorType::OtherError(format!("writing to auxiliary file {e} failed")))?;
-    } else {
-        writeln!(aux_file, "{}", buffer.trim())
-            .map_err(|e| ErrorType::OtherError(format!("writing to auxiliary file {e} failed")))?;
-    }
-    Ok(())
+    let line = match updated_row {
+        Some(updated_row) => updated_row.join(","),
+        None => buffer.trim().to_string(),
+    };
+    writeln!(aux_file, "{line}")
+        .map_err(|e| ErrorType::OtherError(format!("writing to auxiliary file {e} failed")))
 }
 
 pub fn update(
     tables: &[String],
     updates: &HashMap<String, String>,
     where_st: &Option<Condition>,
+    returning: &Option<Vec<String>>,
     db_path: &str,
-) -> Result<Option<String>, ErrorType> {
+) -> Result<CommandResult, ErrorType> {
     let table_list = create_table_list(tables, db_path)?;
+    let mut result = CommandResult::new(CommandKind::Update);
+    let mut output = String::new();
 
     for table in table_list {
         // abro archivo y auxiliar
-        let (mut reader, table_header, header_index) = open_csv_reader(&table, db_path)
+        let (mut reader, table_header, header_index, schema) = open_csv_reader(&table, db_path)
             .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
 
+        // chequeo que las columnas a actualizar y retornar esten en la tabla
+        check_update_headers(updates, &header_index)?;
+        let predicate = compile_where(where_st, &table_header, schema.as_ref())?;
+        if let Some(schema) = &schema {
+            for (column, value) in updates {
+                schema.check_value(column, value)?;
+            }
+        }
+        let mut returning_rows = ReturningRows::new(&table_header, returning)?;
+        let mut unique_keys = UniqueKeys::new(&table, schema.as_ref(), &header_index)?;
+        let foreign_keys = ForeignKeyCheck::new(&table, schema.as_ref(), &header_index, db_path)?;
+        let mut referenced_keys = ReferencedKeys::new(&table, &header_index, db_path)?;
+
         let (mut aux_file, csv_table, aux_table) = create_aux_file(&table, db_path, &table_header)
             .map_err(|e| ErrorType::OtherError(format!("creating auxiliary file {e} failed")))?;
 
-        // chequeo que las columnas a actualizar esten en la tabla
-        check_update_headers(updates, &header_index)?;
+        // con un indice sobre el where solo se evaluan las filas candidatas
+        let candidates = index_scan(&table, where_st, db_path)?;
+        let mut offset = reader
+            .stream_position()
+            .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?;
 
         // leo linea a linea y corroboro si cumple el where
+        let mut updated_rows = 0;
         let mut buffer = String::new();
-        while reader
-            .read_line(&mut buffer)
-            .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?
-            > 0
-        {
+        loop {
+            let bytes = reader
+                .read_line(&mut buffer)
+                .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?;
+            if bytes == 0 {
+                break;
+            }
             let row_values_map = create_row_values_map(&table_header, &buffer);
+            let fields: Vec<&str> = buffer.trim().split(',').collect();
 
             // evaluo la fila por la condicion where
-            let should_update = should_filter(where_st, &row_values_map);
+            let should_update = candidates
+                .as_ref()
+                .is_none_or(|candidates| candidates.binary_search(&offset).is_ok())
+                && should_filter(predicate.as_ref(), &fields);
+            offset += bytes as u64;
+
+            // actualizo la fila y verifico que cumpla el esquema, no repita claves y
+            // referencie filas existentes
+            let updated_row = should_update.then(|| {
+                let mut row = update_row_values(&table_header, &row_values_map, updates);
+                if let Some(schema) = &schema {
+                    schema.normalize_row(&table_header, &mut row);
+                }
+                row
+            });
+            let checked = match &updated_row {
+                Some(updated_row) => schema
+                    .as_ref()
+                    .map_or(Ok(()), |schema| {
+                        schema.check_row(&table, &table_header, updated_row)
+                    })
+                    .and_then(|()| unique_keys.insert(updated_row))
+                    .and_then(|()| foreign_keys.check(updated_row)),
+                None if unique_keys.is_empty() => Ok(()),
+                None => {
+                    let row: Vec<String> =
+                        buffer.trim().split(',').map(ToString::to_string).collect();
+                    unique_keys.insert(&row)
+                }
+            };
+            if let Err(e) = checked {
+                // descarto el archivo auxiliar para dejar la tabla intacta
+                let _ = std::fs::remove_file(&aux_table);
+                return Err(e);
+            }
+
+            if let Some(updated_row) = updated_row.as_ref().filter(|_| !referenced_keys.is_empty())
+            {
+                let old_row: Vec<String> =
+                    buffer.trim().split(',').map(ToString::to_string).collect();
+                referenced_keys.update(&old_row, updated_row);
+            }
 
             // escribe en el archivo auxiliar la fila actualizada
-            write_update(
-                &mut aux_file,
-                should_update,
-                &table_header,
-                &row_values_map,
-                updates,
-                &buffer,
-            )?;
+            write_update(&mut aux_file, updated_row.as_deref(), &buffer)?;
+            if let Some(updated_row) = updated_row {
+                updated_rows += 1;
+                if let Some(returning_rows) = &mut returning_rows {
+                    returning_rows.push(&updated_row);
+                }
+            }
 
             buffer.clear(); // Limpiar buffer para la próxima línea
         }
 
+        // las claves que cambiaron no pueden seguir referenciadas por otras tablas
+        if let Err(e) = referenced_keys.finish(db_path) {
+            let _ = std::fs::remove_file(&aux_table);
+            return Err(e);
+        }
         std::fs::rename(&aux_table, &csv_table)
             .map_err(|e| ErrorType::OtherError(format!("renaming file {e} failed")))?;
+        rebuild_indexes(&table, db_path)?;
+
+        if let Some(returning_rows) = returning_rows {
+            returning_rows.print(&mut output);
+        }
+        result.add_table_rows(&table, updated_rows);
     }
-    Ok(None)
+    result.output = returning.as_ref().map(|_| output);
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::condition::ComparisonOp;
+    use crate::schema::TableSchema;
     use std::fs::{self, File};
     use std::io::Write;
     use std::path::Path;
@@ -158,11 +233,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
 
@@ -176,7 +251,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &update_data, &where_cond, db_path);
+        let update_result = update(&tables, &update_data, &where_cond, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -197,11 +272,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
 
@@ -211,7 +286,7 @@ mod tests {
         let where_cond = None; // Sin condición
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &update_data, &where_cond, db_path);
+        let update_result = update(&tables, &update_data, &where_cond, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -232,11 +307,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
 
@@ -250,7 +325,7 @@ mod tests {
         )); // No existe el id
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &update_data, &where_cond, db_path);
+        let update_result = update(&tables, &update_data, &where_cond, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -272,17 +347,17 @@ mod tests {
         let table_name2 = "test_table2";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name1,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
         setup_table(
             db_path,
             table_name2,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["2".to_string(), "Maria".to_string()]),
         );
 
@@ -296,7 +371,7 @@ mod tests {
         ));
 
         let tables = vec![table_name1.to_string(), table_name2.to_string()];
-        let update_result = update(&tables, &update_data, &where_cond, db_path);
+        let update_result = update(&tables, &update_data, &where_cond, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -325,11 +400,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
 
@@ -341,7 +416,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &update_data, &where_cond, db_path);
+        let update_result = update(&tables, &update_data, &where_cond, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -362,11 +437,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
 
@@ -380,7 +455,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &updates, &where_condition, db_path);
+        let update_result = update(&tables, &updates, &where_condition, &None, db_path);
 
         assert!(
             update_result.is_err(),
@@ -397,11 +472,11 @@ mod tests {
         let table_name = "test_table";
 
         // Configurar la tabla con datos
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             Some(&["1".to_string(), "Carlos".to_string()]),
         );
 
@@ -415,7 +490,7 @@ mod tests {
         )); // No hay id 2
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &updates, &where_condition, db_path);
+        let update_result = update(&tables, &updates, &where_condition, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -437,11 +512,11 @@ mod tests {
         let table_name = "empty_table";
 
         // Crear una tabla vacía
-        fs::create_dir_all(&db_path).expect("Failed to create test database directory");
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
         setup_table(
             db_path,
             table_name,
-            &vec!["id".to_string(), "nombre".to_string()],
+            &["id".to_string(), "nombre".to_string()],
             None,
         ); // Sin filas
 
@@ -455,7 +530,7 @@ mod tests {
         ));
 
         let tables = vec![table_name.to_string()];
-        let update_result = update(&tables, &updates, &where_condition, db_path);
+        let update_result = update(&tables, &updates, &where_condition, &None, db_path);
 
         assert!(update_result.is_ok(), "Update failed: {:?}", update_result);
 
@@ -470,4 +545,114 @@ mod tests {
         teardown_table(db_path, table_name);
         fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
     }
+
+    #[test]
+    fn test_update_with_returning_shows_new_values() {
+        let db_path = "./test_update_db9";
+        let table_name = "test_table";
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        setup_table(
+            db_path,
+            table_name,
+            &["id".to_string(), "nombre".to_string()],
+            Some(&["1".to_string(), "Carlos".to_string()]),
+        );
+
+        let mut updates = HashMap::new();
+        updates.insert("nombre".to_string(), "Juan".to_string());
+        let returning = Some(vec!["*".to_string()]);
+
+        let tables = vec![table_name.to_string()];
+        let update_result = update(&tables, &updates, &None, &returning, db_path);
+        assert_eq!(
+            update_result.unwrap().output,
+            Some("id,nombre\n1,Juan\n".to_string())
+        );
+
+        teardown_table(db_path, table_name);
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_update_violating_check_leaves_table_unchanged() {
+        let db_path = "./test_update_db5";
+        let table_name = "test_table";
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(
+            db_path,
+            table_name,
+            &["id".to_string(), "edad".to_string()],
+            Some(&["1".to_string(), "20".to_string()]),
+        );
+        fs::write(
+            TableSchema::path(table_name, db_path),
+            "id INTEGER NOT NULL\nedad INTEGER CHECK (edad >= 18)\n",
+        )
+        .expect("Unable to write test schema file");
+
+        let tables = vec![table_name.to_string()];
+        let update_data = HashMap::from([("edad".to_string(), "15".to_string())]);
+        let result = update(&tables, &update_data, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        let update_data = HashMap::from([("id".to_string(), String::new())]);
+        let result = update(&tables, &update_data, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        let content = fs::read_to_string(csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,edad\n1,20\n");
+        assert!(!Path::new(&format!("{db_path}/{table_name}_temp.csv")).exists());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_update_checks_unique_keys() {
+        let db_path = "./test_update_db6";
+        let table_name = "test_table";
+
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        let csv_path = setup_table(
+            db_path,
+            table_name,
+            &["id".to_string(), "email".to_string()],
+            Some(&["1".to_string(), "a@mail.com".to_string()]),
+        );
+        fs::write(&csv_path, "id,email\n1,a@mail.com\n2,b@mail.com\n")
+            .expect("Unable to write test table file");
+        fs::write(
+            TableSchema::path(table_name, db_path),
+            "id INTEGER PRIMARY KEY\nemail TEXT UNIQUE\n",
+        )
+        .expect("Unable to write test schema file");
+
+        // cambiar la clave a una que ya usa otra fila
+        let tables = vec![table_name.to_string()];
+        let update_data = HashMap::from([("id".to_string(), "1".to_string())]);
+        let where_cond = Some(Condition::Comparison(
+            "id".to_string(),
+            ComparisonOp::Eq,
+            "2".to_string(),
+        ));
+        let result = update(&tables, &update_data, &where_cond, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        // los valores repetidos entre filas actualizadas tambien chocan
+        let update_data = HashMap::from([("email".to_string(), "c@mail.com".to_string())]);
+        let result = update(&tables, &update_data, &None, &None, db_path);
+        assert!(matches!(result, Err(ErrorType::ConstraintViolation(_))));
+
+        // una fila puede mantener su propia clave
+        let update_data = HashMap::from([("id".to_string(), "2".to_string())]);
+        assert!(update(&tables, &update_data, &where_cond, &None, db_path).is_ok());
+        let update_data = HashMap::from([("id".to_string(), "3".to_string())]);
+        assert!(update(&tables, &update_data, &where_cond, &None, db_path).is_ok());
+
+        let content = fs::read_to_string(csv_path).expect("Failed to read test table file");
+        assert_eq!(content, "id,email\n1,a@mail.com\n3,b@mail.com\n");
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
 }
@@ -1,12 +1,15 @@
-use crate::condition::Condition;
+use crate::condition::{Condition, Predicate};
 use crate::errors::ErrorType;
+use crate::index::rebuild_indexes;
+use crate::schema::TableSchema;
 use std::io::BufReader;
 use std::{collections::HashMap, error::Error, fs, fs::File, io, io::BufRead, io::Write};
 
 type CsvReader = BufReader<File>;
 type Header = Vec<String>;
 type HeaderIndex = HashMap<String, usize>;
-type OpenCsvReaderResult = Result<(CsvReader, Header, HeaderIndex), Box<dyn Error>>;
+type OpenCsvReaderResult =
+    Result<(CsvReader, Header, HeaderIndex, Option<TableSchema>), Box<dyn Error>>;
 type CreateAuxFileResult = Result<(File, String, String), Box<dyn Error>>;
 
 /// Obtiene los headers de la tabla
@@ -25,7 +28,8 @@ fn get_header<R: BufRead>(reader: &mut R) -> io::Result<Vec<String>> {
     Ok(header)
 }
 
-/// Crea un reader, lo devuelve junto a un Vector con los headers y un `HashMap` con la posicion de cada header
+/// Crea un reader, lo devuelve junto a un Vector con los headers, un `HashMap` con la posicion de cada header
+/// y el esquema de la tabla si tiene uno declarado
 pub fn open_csv_reader(table: &String, db_path: &str) -> OpenCsvReaderResult {
     let csv_table = format!("{db_path}/{table}.csv");
     let file = File::open(&csv_table)?;
@@ -38,7 +42,16 @@ pub fn open_csv_reader(table: &String, db_path: &str) -> OpenCsvReaderResult {
         header_index.insert(header.to_string(), i);
     }
 
-    Ok((reader, table_header, header_index))
+    // el esquema tiene que describir las mismas columnas que el header
+    let schema = TableSchema::load(table, db_path).map_err(|e| e.create_error())?;
+    if let Some(schema) = &schema {
+        let schema_columns: Vec<&String> = schema.columns.iter().map(|c| &c.name).collect();
+        if schema_columns != table_header.iter().collect::<Vec<_>>() {
+            return Err(format!("schema of table '{table}' doesn't match its header").into());
+        }
+    }
+
+    Ok((reader, table_header, header_index, schema))
 }
 
 /// Crea un archivo auxiliar para guardar los datos mientras se controlan las condiciones, agrega el header de la tabla
@@ -55,6 +68,24 @@ pub fn create_aux_file(
     Ok((aux_file, format!("{db_path}/{table}.csv"), aux_table))
 }
 
+/// Reescribe la tabla completa con las filas dadas usando un archivo auxiliar, y sus indices
+pub fn write_table_rows(
+    table: &String,
+    db_path: &str,
+    table_header: &[String],
+    rows: &[Vec<String>],
+) -> Result<(), ErrorType> {
+    let (mut aux_file, csv_table, aux_table) = create_aux_file(table, db_path, table_header)
+        .map_err(|e| ErrorType::OtherError(format!("creating auxiliary file {e} failed")))?;
+    for row in rows {
+        writeln!(aux_file, "{}", row.join(","))
+            .map_err(|e| ErrorType::OtherError(format!("writing to auxiliary file {e} failed")))?;
+    }
+    fs::rename(&aux_table, &csv_table)
+        .map_err(|e| ErrorType::OtherError(format!("renaming file {e} failed")))?;
+    rebuild_indexes(table, db_path)
+}
+
 /// Crea un `HashMap` dado una linea con valores de la tabla y los headers de la misma
 pub fn create_row_values_map(table_header: &[String], buffer: &str) -> HashMap<String, String> {
     let row: Vec<String> = buffer.trim().split(',').map(ToString::to_string).collect();
@@ -68,6 +99,16 @@ pub fn create_row_values_map(table_header: &[String], buffer: &str) -> HashMap<S
     row_values_map
 }
 
+/// Devuelve la posicion de la columna en el header
+pub fn column_position(table_header: &[String], column: &str) -> Result<usize, ErrorType> {
+    table_header
+        .iter()
+        .position(|header| header == column)
+        .ok_or(ErrorType::InvalidColumn(format!(
+            "Column '{column}' not found in the table"
+        )))
+}
+
 /// Crea una lista con las tablas a efectuar los comandos
 pub fn create_table_list(tables: &[String], db_path: &str) -> Result<Vec<String>, ErrorType> {
     if tables.len() == 1 && tables[0] == "*" {
@@ -111,13 +152,98 @@ fn get_all_tables(db_path: &str) -> Result<Vec<String>, ErrorType> {
     Ok(tables)
 }
 
-/// Verifica si pasa las condiciones la fila
-pub fn should_filter(
+/// Compila el where contra el header de la tabla, falla si compara una columna que no existe
+pub fn compile_where(
     where_st: &Option<Condition>,
-    row_values_map: &HashMap<String, String>,
-) -> bool {
-    match where_st {
-        Some(cond) => cond.evaluate(row_values_map),
-        None => true,
+    table_header: &[String],
+    schema: Option<&TableSchema>,
+) -> Result<Option<Predicate>, ErrorType> {
+    where_st
+        .as_ref()
+        .map(|cond| cond.compile(table_header, schema))
+        .transpose()
+}
+
+/// Verifica si pasa las condiciones la fila
+pub fn should_filter<S: AsRef<str>>(predicate: Option<&Predicate>, fields: &[S]) -> bool {
+    predicate.is_none_or(|predicate| predicate.evaluate(fields))
+}
+
+/// funcion auxiliar para retornar el valor del output y utilizar en testings
+fn get_select_output(select_headers: &[String], selected_rows: &[Vec<String>]) -> String {
+    let mut output = String::new();
+
+    // Agregamos los headers
+    output.push_str(&select_headers.join(","));
+    output.push('\n'); // Añadimos una nueva línea después de los headers
+
+    // Agregamos cada fila
+    for line in selected_rows {
+        output.push_str(&line.join(","));
+        output.push('\n'); // Añadimos una nueva línea después de cada fila
+    }
+
+    output
+}
+
+/// Agrega el resultado al output, el CLI lo escribe al terminar el comando
+pub fn print_result_set(output: &mut String, select_headers: &[String], rows: &[Vec<String>]) {
+    output.push_str(&get_select_output(select_headers, rows));
+}
+
+/// Filas afectadas por un comando de escritura que se devuelven con RETURNING
+pub struct ReturningRows {
+    headers: Vec<String>,
+    indexes: Vec<usize>,
+    rows: Vec<Vec<String>>,
+}
+
+impl ReturningRows {
+    /// Verifica que las columnas del RETURNING esten en la tabla, `*` devuelve todas.
+    /// Si el comando no tiene RETURNING devuelve `None`
+    pub fn new(
+        table_header: &[String],
+        returning: &Option<Vec<String>>,
+    ) -> Result<Option<Self>, ErrorType> {
+        let Some(returning) = returning else {
+            return Ok(None);
+        };
+
+        let mut headers = Vec::new();
+        let mut indexes = Vec::new();
+        for column in returning {
+            if column == "*" {
+                headers.extend(table_header.iter().cloned());
+                indexes.extend(0..table_header.len());
+            } else if let Some(index) = table_header.iter().position(|header| header == column) {
+                headers.push(column.to_string());
+                indexes.push(index);
+            } else {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Column to return '{column}' not found in the table"
+                )));
+            }
+        }
+
+        Ok(Some(Self {
+            headers,
+            indexes,
+            rows: Vec::new(),
+        }))
+    }
+
+    /// Guarda las columnas pedidas de una fila afectada, en el orden de la tabla
+    pub fn push(&mut self, row: &[String]) {
+        let returned_row = self
+            .indexes
+            .iter()
+            .map(|&index| row.get(index).cloned().unwrap_or_default())
+            .collect();
+        self.rows.push(returned_row);
+    }
+
+    /// Imprime las filas afectadas con el mismo formato que el select
+    pub fn print(&self, output: &mut String) {
+        print_result_set(output, &self.headers, &self.rows);
     }
 }
@@ -1,15 +1,20 @@
-use std::collections::HashMap;
+use crate::errors::ErrorType;
+use crate::parameters::{Bindings, Placeholder};
+use crate::schema::{DataType, TableSchema};
+use std::{cmp::Ordering, collections::HashMap, fmt};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 /// Representacion de las distintas condiciones logicas
 pub enum Condition {
     And(Box<Condition>, Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
     Not(Box<Condition>),
     Comparison(String, ComparisonOp, String),
+    /// comparacion contra un parametro de una sentencia preparada, sin valor todavia
+    Parameter(String, ComparisonOp, Placeholder),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 /// Representacion de los distintos operandos de comparacion
 pub enum ComparisonOp {
     Eq,  // =
@@ -20,42 +25,383 @@ pub enum ComparisonOp {
     Lte, // <=
 }
 
+impl ComparisonOp {
+    /// Indica si el resultado de comparar el dato con el valor cumple el operador
+    #[must_use]
+    pub fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            ComparisonOp::Eq => ordering == Ordering::Equal,
+            ComparisonOp::Neq => ordering != Ordering::Equal,
+            ComparisonOp::Gt => ordering == Ordering::Greater,
+            ComparisonOp::Lt => ordering == Ordering::Less,
+            ComparisonOp::Gte => ordering != Ordering::Less,
+            ComparisonOp::Lte => ordering != Ordering::Greater,
+        }
+    }
+
+    /// Devuelve el operador que se cumple exactamente cuando este no se cumple
+    #[must_use]
+    pub fn negate(&self) -> ComparisonOp {
+        match self {
+            ComparisonOp::Eq => ComparisonOp::Neq,
+            ComparisonOp::Neq => ComparisonOp::Eq,
+            ComparisonOp::Gt => ComparisonOp::Lte,
+            ComparisonOp::Lt => ComparisonOp::Gte,
+            ComparisonOp::Gte => ComparisonOp::Lt,
+            ComparisonOp::Lte => ComparisonOp::Gt,
+        }
+    }
+}
+
+/// Resultado de simplificar una condicion
+#[derive(Debug, Clone, PartialEq)]
+pub enum Simplified {
+    /// la condicion vale lo mismo para todas las filas
+    Constant(bool),
+    Condition(Condition),
+}
+
+/// Indica si el operando izquierdo de una comparacion es un valor constante en lugar de una
+/// columna: un numero o un texto entre comillas
+fn is_literal(operand: &str) -> bool {
+    operand.parse::<f64>().is_ok()
+        || (operand.len() >= 2 && operand.starts_with('\'') && operand.ends_with('\''))
+}
+
+impl fmt::Display for ComparisonOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            ComparisonOp::Eq => "=",
+            ComparisonOp::Neq => "!=",
+            ComparisonOp::Gt => ">",
+            ComparisonOp::Lt => "<",
+            ComparisonOp::Gte => ">=",
+            ComparisonOp::Lte => "<=",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+/// Condicion compilada contra el header de una tabla: cada columna ya esta resuelta a su
+/// posicion en la fila y a su tipo declarado
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+    Constant(bool),
+    Comparison {
+        index: usize,
+        op: ComparisonOp,
+        value: String,
+        data_type: Option<DataType>,
+    },
+}
+
+impl Predicate {
+    /// Evalua el predicado sobre los campos de la fila, en el orden del header.
+    /// Si la fila no tiene el campo la comparacion es falsa
+    pub fn evaluate<S: AsRef<str>>(&self, fields: &[S]) -> bool {
+        match self {
+            Predicate::And(left, right) => left.evaluate(fields) && right.evaluate(fields),
+            Predicate::Or(left, right) => left.evaluate(fields) || right.evaluate(fields),
+            Predicate::Not(predicate) => !predicate.evaluate(fields),
+            Predicate::Constant(value) => *value,
+            Predicate::Comparison {
+                index,
+                op,
+                value,
+                data_type,
+            } => fields
+                .get(*index)
+                .is_some_and(|field| op.matches(compare_values(*data_type, field.as_ref(), value))),
+        }
+    }
+}
+
+/// Formato de la condicion como en la query: los valores que no son numeros van entre comillas
+/// y se agregan parentesis solo donde la precedencia lo requiere
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::And(left, right) => {
+                for (i, side) in [left, right].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " AND ")?;
+                    }
+                    match side.as_ref() {
+                        Condition::Or(..) => write!(f, "({side})")?,
+                        _ => write!(f, "{side}")?,
+                    }
+                }
+                Ok(())
+            }
+            Condition::Or(left, right) => write!(f, "{left} OR {right}"),
+            Condition::Not(cond) => match cond.as_ref() {
+                Condition::Comparison(..) | Condition::Parameter(..) | Condition::Not(_) => {
+                    write!(f, "NOT {cond}")
+                }
+                _ => write!(f, "NOT ({cond})"),
+            },
+            Condition::Comparison(col, op, val) if val.parse::<f64>().is_ok() => {
+                write!(f, "{col} {op} {val}")
+            }
+            Condition::Comparison(col, op, val) => write!(f, "{col} {op} '{val}'"),
+            Condition::Parameter(col, op, placeholder) => write!(f, "{col} {op} {placeholder}"),
+        }
+    }
+}
+
+/// Compara el dato con el valor segun el tipo declarado de la columna.
+/// Sin tipo se comparan como numeros si ambos lo son, sino como texto
+#[must_use]
+pub fn compare_values(data_type: Option<DataType>, data_val: &str, val: &str) -> Ordering {
+    match data_type {
+        Some(data_type) => data_type.compare(data_val, val),
+        // Chequea sin son ambos numeros y los parsea
+        None => match (data_val.parse::<i32>(), val.parse::<i32>()) {
+            (Ok(data_int), Ok(val_int)) => data_int.cmp(&val_int),
+            _ => data_val.cmp(val),
+        },
+    }
+}
+
 impl Condition {
+    /// Devuelve las condiciones unidas por AND en el primer nivel del arbol
+    #[must_use]
+    pub fn conjuncts(&self) -> Vec<&Condition> {
+        match self {
+            Condition::And(left, right) => {
+                let mut conjuncts = left.conjuncts();
+                conjuncts.extend(right.conjuncts());
+                conjuncts
+            }
+            _ => vec![self],
+        }
+    }
+
+    /// Simplifica la condicion: resuelve las comparaciones entre constantes, elimina las
+    /// dobles negaciones y lleva los NOT hasta las comparaciones aplicando De Morgan
+    #[must_use]
+    pub fn simplify(self) -> Simplified {
+        match self {
+            Condition::And(left, right) => match (left.simplify(), right.simplify()) {
+                (Simplified::Constant(false), _) | (_, Simplified::Constant(false)) => {
+                    Simplified::Constant(false)
+                }
+                (Simplified::Constant(true), other) | (other, Simplified::Constant(true)) => other,
+                (Simplified::Condition(left), Simplified::Condition(right)) => {
+                    Simplified::Condition(Condition::And(Box::new(left), Box::new(right)))
+                }
+            },
+            Condition::Or(left, right) => match (left.simplify(), right.simplify()) {
+                (Simplified::Constant(true), _) | (_, Simplified::Constant(true)) => {
+                    Simplified::Constant(true)
+                }
+                (Simplified::Constant(false), other) | (other, Simplified::Constant(false)) => {
+                    other
+                }
+                (Simplified::Condition(left), Simplified::Condition(right)) => {
+                    Simplified::Condition(Condition::Or(Box::new(left), Box::new(right)))
+                }
+            },
+            Condition::Not(cond) => match *cond {
+                Condition::Not(cond) => cond.simplify(),
+                Condition::And(left, right) => Condition::Or(
+                    Box::new(Condition::Not(left)),
+                    Box::new(Condition::Not(right)),
+                )
+                .simplify(),
+                Condition::Or(left, right) => Condition::And(
+                    Box::new(Condition::Not(left)),
+                    Box::new(Condition::Not(right)),
+                )
+                .simplify(),
+                Condition::Comparison(col, op, val) => {
+                    Condition::Comparison(col, op.negate(), val).simplify()
+                }
+                Condition::Parameter(col, op, placeholder) => {
+                    Simplified::Condition(Condition::Parameter(col, op.negate(), placeholder))
+                }
+            },
+            Condition::Comparison(col, op, val) if is_literal(&col) => {
+                Simplified::Constant(op.matches(compare_values(None, col.trim_matches('\''), &val)))
+            }
+            Condition::Comparison(..) | Condition::Parameter(..) => Simplified::Condition(self),
+        }
+    }
+
+    /// Devuelve las columnas que compara la condicion, sin repetir
+    #[must_use]
+    pub fn columns(&self) -> Vec<&String> {
+        let mut columns = Vec::new();
+        self.collect_columns(&mut columns);
+        columns
+    }
+
+    fn collect_columns<'a>(&'a self, columns: &mut Vec<&'a String>) {
+        match self {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.collect_columns(columns);
+                right.collect_columns(columns);
+            }
+            Condition::Not(cond) => cond.collect_columns(columns),
+            Condition::Comparison(col, _, _) | Condition::Parameter(col, _, _) => {
+                if !is_literal(col) && !columns.contains(&col) {
+                    columns.push(col);
+                }
+            }
+        }
+    }
+
+    /// Verifica que todas las columnas que compara la condicion esten en el header
+    #[allow(clippy::missing_errors_doc)]
+    pub fn check_columns(&self, table_header: &[String]) -> Result<(), ErrorType> {
+        match self
+            .columns()
+            .into_iter()
+            .find(|column| !table_header.contains(column))
+        {
+            Some(column) => Err(ErrorType::InvalidColumn(format!(
+                "Column '{column}' not found in the table"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Cambia el nombre de la columna en todas las comparaciones que la usan
+    pub fn rename_column(&mut self, from: &str, to: &str) {
+        match self {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.rename_column(from, to);
+                right.rename_column(from, to);
+            }
+            Condition::Not(cond) => cond.rename_column(from, to),
+            Condition::Comparison(col, _, _) | Condition::Parameter(col, _, _) => {
+                if col == from {
+                    *col = to.to_string();
+                }
+            }
+        }
+    }
+
+    /// Resuelve las columnas de la condicion a su posicion en el header, una sola vez antes de
+    /// recorrer las filas. La condicion se simplifica antes de compilarla, pero una columna
+    /// inexistente es un error aunque la simplificacion la descarte
+    #[allow(clippy::missing_errors_doc)]
+    pub fn compile(
+        &self,
+        table_header: &[String],
+        schema: Option<&TableSchema>,
+    ) -> Result<Predicate, ErrorType> {
+        self.check_columns(table_header)?;
+        match self.clone().simplify() {
+            Simplified::Constant(value) => Ok(Predicate::Constant(value)),
+            Simplified::Condition(cond) => cond.compile_tree(table_header, schema),
+        }
+    }
+
+    fn compile_tree(
+        &self,
+        table_header: &[String],
+        schema: Option<&TableSchema>,
+    ) -> Result<Predicate, ErrorType> {
+        let compile_both = |left: &Condition, right: &Condition| -> Result<_, ErrorType> {
+            Ok((
+                Box::new(left.compile_tree(table_header, schema)?),
+                Box::new(right.compile_tree(table_header, schema)?),
+            ))
+        };
+        Ok(match self {
+            Condition::And(left, right) => {
+                let (left, right) = compile_both(left, right)?;
+                Predicate::And(left, right)
+            }
+            Condition::Or(left, right) => {
+                let (left, right) = compile_both(left, right)?;
+                Predicate::Or(left, right)
+            }
+            Condition::Not(cond) => {
+                Predicate::Not(Box::new(cond.compile_tree(table_header, schema)?))
+            }
+            Condition::Comparison(col, op, val) => Predicate::Comparison {
+                index: table_header.iter().position(|name| name == col).ok_or(
+                    ErrorType::InvalidColumn(format!("Column '{col}' not found in the table")),
+                )?,
+                op: op.clone(),
+                value: val.to_string(),
+                data_type: schema.and_then(|schema| schema.data_type(col)),
+            },
+            Condition::Parameter(_, _, placeholder) => return Err(placeholder.unbound()),
+        })
+    }
+
+    /// Devuelve los parametros de la condicion, en el orden en que aparecen
+    #[must_use]
+    pub fn placeholders(&self) -> Vec<&Placeholder> {
+        match self {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                let mut placeholders = left.placeholders();
+                placeholders.extend(right.placeholders());
+                placeholders
+            }
+            Condition::Not(cond) => cond.placeholders(),
+            Condition::Comparison(..) => Vec::new(),
+            Condition::Parameter(_, _, placeholder) => vec![placeholder],
+        }
+    }
+
+    /// Reemplaza los parametros por sus valores, en el orden en que aparecen
+    #[allow(clippy::missing_errors_doc)]
+    pub fn bind(&mut self, bindings: &mut Bindings) -> Result<(), ErrorType> {
+        match self {
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.bind(bindings)?;
+                right.bind(bindings)
+            }
+            Condition::Not(cond) => cond.bind(bindings),
+            Condition::Comparison(..) => Ok(()),
+            Condition::Parameter(col, op, placeholder) => {
+                let bound =
+                    Condition::Comparison(col.clone(), op.clone(), bindings.value(placeholder)?);
+                *self = bound;
+                Ok(())
+            }
+        }
+    }
+
     /// Evalua las condiciones del where en forma de arbol, si la columna no existe retorna false
     #[must_use]
     pub fn evaluate(&self, row: &HashMap<String, String>) -> bool {
+        self.evaluate_with_schema(row, None)
+    }
+
+    /// Evalua las condiciones comparando segun el tipo declarado de cada columna.
+    /// Las columnas sin tipo se comparan como numeros si ambos lo son, sino como texto
+    #[must_use]
+    pub fn evaluate_with_schema(
+        &self,
+        row: &HashMap<String, String>,
+        schema: Option<&TableSchema>,
+    ) -> bool {
         match self {
-            Condition::And(left, right) => left.evaluate(row) && right.evaluate(row),
-            Condition::Or(left, right) => left.evaluate(row) || right.evaluate(row),
-            Condition::Not(cond) => !cond.evaluate(row),
+            Condition::And(left, right) => {
+                left.evaluate_with_schema(row, schema) && right.evaluate_with_schema(row, schema)
+            }
+            Condition::Or(left, right) => {
+                left.evaluate_with_schema(row, schema) || right.evaluate_with_schema(row, schema)
+            }
+            Condition::Not(cond) => !cond.evaluate_with_schema(row, schema),
             Condition::Comparison(col, op, val) => {
                 if let Some(data_val) = row.get(col) {
-                    // Chequea sin son ambos numeros y los parsea
-                    if let (Ok(data_int), Ok(val_int)) =
-                        (data_val.parse::<i32>(), val.parse::<i32>())
-                    {
-                        match op {
-                            ComparisonOp::Eq => data_int == val_int,
-                            ComparisonOp::Neq => data_int != val_int,
-                            ComparisonOp::Gt => data_int > val_int,
-                            ComparisonOp::Lt => data_int < val_int,
-                            ComparisonOp::Gte => data_int >= val_int,
-                            ComparisonOp::Lte => data_int <= val_int,
-                        }
-                    } else {
-                        match op {
-                            ComparisonOp::Eq => data_val == val,
-                            ComparisonOp::Neq => data_val != val,
-                            ComparisonOp::Gt => data_val > val,
-                            ComparisonOp::Lt => data_val < val,
-                            ComparisonOp::Gte => data_val >= val,
-                            ComparisonOp::Lte => data_val <= val,
-                        }
-                    }
+                    let data_type = schema.and_then(|schema| schema.data_type(col));
+                    op.matches(compare_values(data_type, data_val, val))
                 } else {
                     false
                 }
             }
+            // un parametro sin valor no se cumple para ninguna fila
+            Condition::Parameter(..) => false,
         }
     }
 }
@@ -64,6 +410,44 @@ impl Condition {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_condition_display() {
+        let comparison = |col: &str, op, val: &str| {
+            Box::new(Condition::Comparison(col.to_string(), op, val.to_string()))
+        };
+        let condition = Condition::And(
+            Box::new(Condition::Or(
+                comparison("cantidad", ComparisonOp::Gt, "1"),
+                comparison("producto", ComparisonOp::Eq, "Laptop"),
+            )),
+            Box::new(Condition::Not(Box::new(Condition::And(
+                comparison("id_cliente", ComparisonOp::Neq, "2"),
+                comparison("id", ComparisonOp::Lte, "105"),
+            )))),
+        );
+        assert_eq!(
+            condition.to_string(),
+            "(cantidad > 1 OR producto = 'Laptop') AND NOT (id_cliente != 2 AND id <= 105)"
+        );
+        assert_eq!(
+            condition.columns(),
+            ["cantidad", "producto", "id_cliente", "id"]
+        );
+        assert_eq!(
+            condition.conjuncts(),
+            [
+                &Condition::Or(
+                    comparison("cantidad", ComparisonOp::Gt, "1"),
+                    comparison("producto", ComparisonOp::Eq, "Laptop"),
+                ),
+                &Condition::Not(Box::new(Condition::And(
+                    comparison("id_cliente", ComparisonOp::Neq, "2"),
+                    comparison("id", ComparisonOp::Lte, "105"),
+                ))),
+            ]
+        );
+    }
+
     #[test]
     fn test_eq_comparison() {
         let mut row = HashMap::new();
@@ -372,4 +756,165 @@ mod tests {
         );
         assert!(condition.evaluate(&row)); // true, quantity <= 5
     }
+
+    #[test]
+    fn test_typed_comparison_with_schema() {
+        use crate::schema::{ColumnDefinition, DataType};
+
+        let mut row = HashMap::new();
+        row.insert("precio".to_string(), "9.5".to_string());
+        let schema = TableSchema::new(vec![ColumnDefinition::new("precio", DataType::Decimal)]);
+
+        // sin esquema "9.5" no es un entero y se compara como texto
+        let condition =
+            Condition::Comparison("precio".to_string(), ComparisonOp::Lt, "10".to_string());
+        assert!(!condition.evaluate(&row));
+        assert!(condition.evaluate_with_schema(&row, Some(&schema)));
+
+        let condition =
+            Condition::Comparison("precio".to_string(), ComparisonOp::Eq, "9.50".to_string());
+        assert!(condition.evaluate_with_schema(&row, Some(&schema)));
+    }
+
+    #[test]
+    fn test_compiled_predicate() {
+        use crate::schema::{ColumnDefinition, DataType};
+
+        let header = vec!["id".to_string(), "precio".to_string()];
+        let schema = TableSchema::new(vec![
+            ColumnDefinition::new("id", DataType::Integer),
+            ColumnDefinition::new("precio", DataType::Decimal),
+        ]);
+        let condition = Condition::And(
+            Box::new(Condition::Comparison(
+                "precio".to_string(),
+                ComparisonOp::Lt,
+                "10".to_string(),
+            )),
+            Box::new(Condition::Not(Box::new(Condition::Comparison(
+                "id".to_string(),
+                ComparisonOp::Eq,
+                "2".to_string(),
+            )))),
+        );
+
+        let predicate = condition.compile(&header, Some(&schema)).unwrap();
+        assert!(predicate.evaluate(&["1", "9.5"]));
+        assert!(!predicate.evaluate(&["2", "9.5"]));
+        assert!(!predicate.evaluate(&["1", "10.0"]));
+        // a la fila le falta el campo
+        assert!(!predicate.evaluate(&["1"]));
+
+        // las columnas inexistentes se informan al compilar
+        let condition =
+            Condition::Comparison("nombre".to_string(), ComparisonOp::Eq, "Juan".to_string());
+        assert!(matches!(
+            condition.compile(&header, None),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_simplify_condition() {
+        let comparison =
+            |col: &str, op, val: &str| Condition::Comparison(col.to_string(), op, val.to_string());
+
+        // NOT (NOT a = 1) es a = 1
+        let condition = Condition::Not(Box::new(Condition::Not(Box::new(comparison(
+            "a",
+            ComparisonOp::Eq,
+            "1",
+        )))));
+        assert_eq!(
+            condition.simplify(),
+            Simplified::Condition(comparison("a", ComparisonOp::Eq, "1"))
+        );
+
+        // 1 = 1 AND x > 3 es x > 3
+        let condition = Condition::And(
+            Box::new(comparison("1", ComparisonOp::Eq, "1")),
+            Box::new(comparison("x", ComparisonOp::Gt, "3")),
+        );
+        assert_eq!(condition.columns(), ["x"]);
+        assert_eq!(
+            condition.simplify(),
+            Simplified::Condition(comparison("x", ComparisonOp::Gt, "3"))
+        );
+
+        // De Morgan lleva el NOT hasta las comparaciones
+        let condition = Condition::Not(Box::new(Condition::Or(
+            Box::new(comparison("a", ComparisonOp::Lt, "1")),
+            Box::new(comparison("b", ComparisonOp::Eq, "x")),
+        )));
+        assert_eq!(
+            condition.simplify(),
+            Simplified::Condition(Condition::And(
+                Box::new(comparison("a", ComparisonOp::Gte, "1")),
+                Box::new(comparison("b", ComparisonOp::Neq, "x")),
+            ))
+        );
+
+        // una comparacion falsa entre constantes anula todo el AND
+        let condition = Condition::Or(
+            Box::new(Condition::And(
+                Box::new(comparison("'a'", ComparisonOp::Eq, "b")),
+                Box::new(comparison("x", ComparisonOp::Gt, "3")),
+            )),
+            Box::new(Condition::Not(Box::new(comparison(
+                "2",
+                ComparisonOp::Lt,
+                "10",
+            )))),
+        );
+        assert_eq!(condition.clone().simplify(), Simplified::Constant(false));
+        let header = vec!["x".to_string()];
+        assert_eq!(
+            condition.compile(&header, None).unwrap(),
+            Predicate::Constant(false)
+        );
+
+        // la columna inexistente es un error aunque la condicion sea constante
+        let condition = Condition::Or(
+            Box::new(comparison("1", ComparisonOp::Eq, "1")),
+            Box::new(comparison("nope", ComparisonOp::Eq, "3")),
+        );
+        assert!(matches!(
+            condition.compile(&header, None),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let parameter = |col: &str, n| {
+            Box::new(Condition::Parameter(
+                col.to_string(),
+                ComparisonOp::Eq,
+                Placeholder::Positional(n),
+            ))
+        };
+        let mut condition = Condition::Or(
+            parameter("id", 1),
+            Box::new(Condition::Not(parameter("name", 2))),
+        );
+        assert_eq!(condition.placeholders().len(), 2);
+        let header = vec!["id".to_string(), "name".to_string()];
+        assert!(matches!(
+            condition.compile(&header, None),
+            Err(ErrorType::InvalidSyntax(_))
+        ));
+
+        let mut bindings = Bindings::new(&[&1, &"Ana"]);
+        condition.bind(&mut bindings).unwrap();
+        bindings.finish().unwrap();
+        assert!(condition.placeholders().is_empty());
+        assert_eq!(condition.to_string(), "id = 1 OR NOT name = 'Ana'");
+
+        let mut bindings = Bindings::new(&[&1]);
+        let mut condition = Condition::And(parameter("id", 1), parameter("name", 2));
+        assert!(condition.bind(&mut bindings).is_err());
+        let mut bindings = Bindings::new(&[&1, &2]);
+        parameter("id", 1).bind(&mut bindings).unwrap();
+        assert!(bindings.finish().is_err());
+    }
 }
@@ -0,0 +1,138 @@
+use crate::command_result::CommandResult;
+use crate::command_types::Commands;
+use crate::commands::{execute, query};
+use crate::errors::ErrorType;
+use crate::parameters::{Bindings, Placeholder, ToValue};
+use crate::parser::parse_query;
+use crate::rows::Rows;
+use std::path::Path;
+
+/// Base de datos abierta: el directorio con las tablas sobre el que se ejecutan los comandos
+#[derive(Debug, Clone)]
+pub struct Database {
+    path: String,
+}
+
+impl Database {
+    /// Abre la base de datos del directorio, falla si el directorio no existe
+    #[allow(clippy::missing_errors_doc)]
+    pub fn open(path: &str) -> Result<Self, ErrorType> {
+        if !Path::new(path).is_dir() {
+            return Err(ErrorType::OtherError(format!(
+                "Database directory '{path}' not found"
+            )));
+        }
+        Ok(Database {
+            path: path.to_string(),
+        })
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Parsea la query una unica vez, los parametros (`?`, `$N`, `:nombre`) se reemplazan
+    /// al ejecutarla. Los parametros por nombre no se pueden mezclar con los que van en orden
+    #[allow(clippy::missing_errors_doc)]
+    pub fn prepare(&self, sql: &str) -> Result<Statement<'_>, ErrorType> {
+        let command = parse_query(&mut sql.to_string())?;
+        // los valores se pasan en orden o por nombre, nunca de las dos formas a la vez
+        let placeholders = command.placeholders();
+        let named = placeholders
+            .iter()
+            .filter(|placeholder| placeholder.position().is_none())
+            .count();
+        if named > 0 && named < placeholders.len() {
+            return Err(ErrorType::InvalidSyntax(
+                "A statement cannot mix named and positional parameters".into(),
+            ));
+        }
+        Ok(Statement {
+            database: self,
+            command,
+        })
+    }
+
+    /// Ejecuta la query, que no puede tener parametros
+    #[allow(clippy::missing_errors_doc)]
+    pub fn execute(&self, sql: &str) -> Result<CommandResult, ErrorType> {
+        self.prepare(sql)?.execute(&[])
+    }
+
+    /// Ejecuta la consulta, que no puede tener parametros, y devuelve sus filas
+    #[allow(clippy::missing_errors_doc)]
+    pub fn query(&self, sql: &str) -> Result<Rows, ErrorType> {
+        self.prepare(sql)?.query(&[])
+    }
+}
+
+/// Sentencia preparada: la query ya parseada, que se puede ejecutar varias veces con
+/// distintos valores para sus parametros
+#[derive(Debug)]
+pub struct Statement<'db> {
+    database: &'db Database,
+    command: Commands,
+}
+
+impl Statement<'_> {
+    /// Cantidad de valores que hay que pasar en orden al ejecutarla: el mayor numero de
+    /// sus parametros `?` y `$N`
+    #[must_use]
+    pub fn parameter_count(&self) -> usize {
+        self.command
+            .placeholders()
+            .iter()
+            .filter_map(|placeholder| placeholder.position())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Nombres de sus parametros `:nombre`, sin repetir
+    #[must_use]
+    pub fn parameter_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        for placeholder in self.command.placeholders() {
+            if let Placeholder::Named(name) = placeholder {
+                if !names.contains(&name.as_str()) {
+                    names.push(name.as_str());
+                }
+            }
+        }
+        names
+    }
+
+    /// Devuelve el comando con los parametros reemplazados por los valores
+    fn bind(&self, mut bindings: Bindings) -> Result<Commands, ErrorType> {
+        let mut command = self.command.clone();
+        command.bind(&mut bindings)?;
+        Ok(command)
+    }
+
+    /// Ejecuta la sentencia con los valores de sus parametros, en orden
+    #[allow(clippy::missing_errors_doc)]
+    pub fn execute(&self, params: &[&dyn ToValue]) -> Result<CommandResult, ErrorType> {
+        execute(&self.bind(Bindings::new(params))?, &self.database.path)
+    }
+
+    /// Ejecuta la sentencia con los valores de sus parametros por nombre
+    #[allow(clippy::missing_errors_doc)]
+    pub fn execute_named(
+        &self,
+        params: &[(&str, &dyn ToValue)],
+    ) -> Result<CommandResult, ErrorType> {
+        execute(&self.bind(Bindings::named(params))?, &self.database.path)
+    }
+
+    /// Ejecuta la consulta con los valores de sus parametros y devuelve sus filas
+    #[allow(clippy::missing_errors_doc)]
+    pub fn query(&self, params: &[&dyn ToValue]) -> Result<Rows, ErrorType> {
+        query(&self.bind(Bindings::new(params))?, &self.database.path)
+    }
+
+    /// Ejecuta la consulta con los valores de sus parametros por nombre y devuelve sus filas
+    #[allow(clippy::missing_errors_doc)]
+    pub fn query_named(&self, params: &[(&str, &dyn ToValue)]) -> Result<Rows, ErrorType> {
+        query(&self.bind(Bindings::named(params))?, &self.database.path)
+    }
+}
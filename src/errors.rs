@@ -6,6 +6,7 @@ pub enum ErrorType {
     InvalidTable(String),
     InvalidColumn(String),
     InvalidSyntax(String),
+    ConstraintViolation(String),
     OtherError(String),
 }
 
@@ -17,6 +18,7 @@ impl ErrorType {
             ErrorType::InvalidTable(msg) => ("INVALID_TABLE", msg),
             ErrorType::InvalidColumn(msg) => ("INVALID_COLUMN", msg),
             ErrorType::InvalidSyntax(msg) => ("INVALID_SYNTAX", msg),
+            ErrorType::ConstraintViolation(msg) => ("CONSTRAINT_VIOLATION", msg),
             ErrorType::OtherError(msg) => ("ERROR", msg),
         };
 
@@ -61,6 +63,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_constraint_violation_error() {
+        let error = ErrorType::ConstraintViolation("Column 'id' can't be NULL".to_string());
+        let formatted_error = error.create_error();
+
+        assert_eq!(
+            formatted_error.to_string(),
+            "[CONSTRAINT_VIOLATION]: Column 'id' can't be NULL"
+        );
+    }
+
     #[test]
     fn test_other_error() {
         let error = ErrorType::OtherError("An unknown error occurred".to_string());
@@ -0,0 +1,576 @@
+use crate::condition::{compare_values, ComparisonOp, Condition};
+use crate::errors::ErrorType;
+use crate::schema::{DataType, TableSchema};
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    time::UNIX_EPOCH,
+};
+
+/// Indice secundario de una columna: cada valor con la posicion en bytes de su fila en el csv.
+/// Las entradas quedan ordenadas con el mismo criterio con el que compara el where
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableIndex {
+    pub column: String,
+    data_type: Option<DataType>,
+    /// con tipo declarado y todos los valores validos se puede buscar en forma binaria
+    ordered: bool,
+    /// estado del csv cuando se armo el indice, para detectar tablas modificadas a mano
+    table_state: TableState,
+    entries: Vec<(String, u64)>,
+}
+
+/// Tamaño y fecha de modificacion del csv. Una tabla modificada a mano puede conservar
+/// el tamaño pero no la fecha
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TableState {
+    size: u64,
+    /// nanosegundos desde 1970, 0 si el sistema no la informa
+    modified: u128,
+}
+
+impl TableState {
+    /// Lee el estado del csv a partir de sus metadatos
+    #[must_use]
+    pub fn of(metadata: &fs::Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        Self {
+            size: metadata.len(),
+            modified,
+        }
+    }
+}
+
+/// Devuelve la ruta del archivo csv de la tabla
+fn csv_path(table: &str, db_path: &str) -> String {
+    format!("{db_path}/{table}.csv")
+}
+
+/// Devuelve el estado actual del csv de la tabla
+fn table_state(table: &str, db_path: &str) -> Result<TableState, ErrorType> {
+    fs::metadata(csv_path(table, db_path))
+        .map(|metadata| TableState::of(&metadata))
+        .map_err(|e| ErrorType::InvalidTable(format!("reading table {e} failed")))
+}
+
+impl TableIndex {
+    /// Devuelve la ruta del archivo del indice
+    #[must_use]
+    pub fn path(table: &str, column: &str, db_path: &str) -> String {
+        format!("{db_path}/{table}.{column}.idx")
+    }
+
+    /// Devuelve las columnas indexadas de la tabla
+    #[allow(clippy::missing_errors_doc)]
+    pub fn columns(table: &str, db_path: &str) -> Result<Vec<String>, ErrorType> {
+        let prefix = format!("{table}.");
+        let mut columns = Vec::new();
+        let paths = fs::read_dir(db_path)
+            .map_err(|e| ErrorType::OtherError(format!("reading directory failed: {e}")))?;
+        for path in paths {
+            let path =
+                path.map_err(|e| ErrorType::OtherError(format!("reading path failed: {e}")))?;
+            let file_name = path.file_name();
+            let Some(column) = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|name| name.strip_suffix(".idx"))
+            else {
+                continue;
+            };
+            if !column.is_empty() && !column.contains('.') {
+                columns.push(column.to_string());
+            }
+        }
+        columns.sort();
+        Ok(columns)
+    }
+
+    /// Arma el indice leyendo la tabla completa
+    #[allow(clippy::missing_errors_doc)]
+    pub fn build(table: &str, column: &str, db_path: &str) -> Result<Self, ErrorType> {
+        let file = File::open(csv_path(table, db_path))
+            .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+        let table_state = file
+            .metadata()
+            .map(|metadata| TableState::of(&metadata))
+            .map_err(|e| ErrorType::InvalidTable(format!("reading table {e} failed")))?;
+        let mut reader = BufReader::new(file);
+        let read_error = |e| ErrorType::OtherError(format!("Error reading line: {e}"));
+
+        let mut buffer = String::new();
+        let mut offset = reader.read_line(&mut buffer).map_err(read_error)? as u64;
+        let position = buffer
+            .trim_end()
+            .split(',')
+            .position(|header| header.trim() == column)
+            .ok_or(ErrorType::InvalidColumn(format!(
+                "Column '{column}' not found in table '{table}'"
+            )))?;
+        buffer.clear();
+
+        let mut index = Self {
+            column: column.to_string(),
+            data_type: TableSchema::load(table, db_path)?
+                .and_then(|schema| schema.data_type(column)),
+            ordered: false,
+            table_state,
+            entries: Vec::new(),
+        };
+        loop {
+            let bytes = reader.read_line(&mut buffer).map_err(read_error)?;
+            if bytes == 0 {
+                break;
+            }
+            if !buffer.trim().is_empty() {
+                let key = buffer.trim().split(',').nth(position).unwrap_or_default();
+                index.entries.push((key.to_string(), offset));
+            }
+            offset += bytes as u64;
+            buffer.clear();
+        }
+        index.sort();
+        Ok(index)
+    }
+
+    /// Lee el indice guardado de la columna
+    #[allow(clippy::missing_errors_doc)]
+    pub fn load(table: &str, column: &str, db_path: &str) -> Result<Self, ErrorType> {
+        let content = fs::read_to_string(Self::path(table, column, db_path))
+            .map_err(|e| ErrorType::OtherError(format!("reading index {e} failed")))?;
+        let invalid = || {
+            ErrorType::OtherError(format!(
+                "Index on column '{column}' of table '{table}' is corrupted"
+            ))
+        };
+
+        let mut lines = content.lines();
+        let header = lines.next().ok_or_else(invalid)?;
+        // un indice sin fecha de modificacion nunca esta al dia y se vuelve a armar
+        let (size, modified) = header.split_once(',').unwrap_or((header, "0"));
+        let table_state = TableState {
+            size: size.parse().map_err(|_| invalid())?,
+            modified: modified.parse().map_err(|_| invalid())?,
+        };
+        let mut entries = Vec::new();
+        for line in lines {
+            let (offset, key) = line.split_once(',').ok_or_else(invalid)?;
+            entries.push((key.to_string(), offset.parse().map_err(|_| invalid())?));
+        }
+
+        let mut index = Self {
+            column: column.to_string(),
+            data_type: TableSchema::load(table, db_path)?
+                .and_then(|schema| schema.data_type(column)),
+            ordered: false,
+            table_state,
+            entries,
+        };
+        index.ordered = index.all_valid();
+        Ok(index)
+    }
+
+    /// Guarda el indice: el tamaño y la fecha de modificacion de la tabla y despues una linea
+    /// `posicion,valor` por fila
+    #[allow(clippy::missing_errors_doc)]
+    pub fn save(&self, table: &str, db_path: &str) -> Result<(), ErrorType> {
+        let TableState { size, modified } = self.table_state;
+        let mut content = format!("{size},{modified}\n");
+        for (key, offset) in &self.entries {
+            content.push_str(&format!("{offset},{key}\n"));
+        }
+        fs::write(Self::path(table, &self.column, db_path), content)
+            .map_err(|e| ErrorType::OtherError(format!("writing index {e} failed")))
+    }
+
+    /// Indica si el indice refleja el contenido actual de la tabla
+    #[allow(clippy::missing_errors_doc)]
+    pub fn is_current(&self, table: &str, db_path: &str) -> Result<bool, ErrorType> {
+        Ok(self.table_state == table_state(table, db_path)?)
+    }
+
+    /// Agrega la fila que se escribio en la posicion dada al final de la tabla
+    pub fn insert(&mut self, key: &str, offset: u64) {
+        self.ordered &= self
+            .data_type
+            .is_some_and(|data_type| data_type.is_valid(key));
+        let position = self
+            .entries
+            .partition_point(|(entry, _)| self.sort_order(entry, key) != Ordering::Greater);
+        self.entries.insert(position, (key.to_string(), offset));
+    }
+
+    /// Devuelve las posiciones, en orden creciente, de las filas cuyo valor cumple la comparacion.
+    /// None si el operador no puede resolverse con el indice
+    #[must_use]
+    pub fn lookup(&self, op: &ComparisonOp, value: &str) -> Option<Vec<u64>> {
+        if *op == ComparisonOp::Neq {
+            return None;
+        }
+        let mut offsets: Vec<u64> = match self.data_type {
+            // con un valor valido para el tipo el orden de las entradas es el del where
+            Some(data_type) if self.ordered && !value.is_empty() && data_type.is_valid(value) => {
+                let compare = |key: &str| data_type.compare(key, value);
+                let start = self
+                    .entries
+                    .partition_point(|(key, _)| compare(key) == Ordering::Less);
+                let end = self
+                    .entries
+                    .partition_point(|(key, _)| compare(key) != Ordering::Greater);
+                let entries = match op {
+                    ComparisonOp::Eq => &self.entries[start..end],
+                    ComparisonOp::Lt => &self.entries[..start],
+                    ComparisonOp::Lte => &self.entries[..end],
+                    ComparisonOp::Gt => &self.entries[end..],
+                    ComparisonOp::Gte | ComparisonOp::Neq => &self.entries[start..],
+                };
+                entries.iter().map(|(_, offset)| *offset).collect()
+            }
+            // sino la comparacion puede mezclar numeros y texto, se recorren las entradas
+            _ => self
+                .entries
+                .iter()
+                .filter(|(key, _)| op.matches(self.compare(key, value)))
+                .map(|(_, offset)| *offset)
+                .collect(),
+        };
+        offsets.sort_unstable();
+        Some(offsets)
+    }
+
+    /// Compara dos valores de la columna como lo hace el where
+    fn compare(&self, key: &str, value: &str) -> Ordering {
+        compare_values(self.data_type, key, value)
+    }
+
+    /// Orden de las entradas: el del tipo si se puede buscar en forma binaria, sino como texto
+    fn sort_order(&self, a: &str, b: &str) -> Ordering {
+        if self.ordered {
+            compare_values(self.data_type, a, b)
+        } else {
+            a.cmp(b)
+        }
+    }
+
+    /// Indica si la columna tiene tipo y todos sus valores son validos para el
+    fn all_valid(&self) -> bool {
+        self.data_type
+            .is_some_and(|data_type| self.entries.iter().all(|(key, _)| data_type.is_valid(key)))
+    }
+
+    /// Ordena las entradas por valor y despues por posicion
+    fn sort(&mut self) {
+        self.ordered = self.all_valid();
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.sort_by(|(a, a_offset), (b, b_offset)| {
+            self.sort_order(a, b).then(a_offset.cmp(b_offset))
+        });
+        self.entries = entries;
+    }
+}
+
+/// Vuelve a armar todos los indices de la tabla despues de reescribirla
+#[allow(clippy::missing_errors_doc)]
+pub fn rebuild_indexes(table: &str, db_path: &str) -> Result<(), ErrorType> {
+    for column in TableIndex::columns(table, db_path)? {
+        TableIndex::build(table, &column, db_path)?.save(table, db_path)?;
+    }
+    Ok(())
+}
+
+/// Agrega a los indices de la tabla las filas escritas al final del csv. `before` es el estado
+/// del csv antes de escribirlas, las filas empiezan donde terminaba
+#[allow(clippy::missing_errors_doc)]
+pub fn append_to_indexes(
+    table: &str,
+    rows: &[Vec<String>],
+    before: TableState,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    let columns = TableIndex::columns(table, db_path)?;
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let after = table_state(table, db_path)?;
+    let file = File::open(csv_path(table, db_path))
+        .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+    let mut header = String::new();
+    BufReader::new(file)
+        .read_line(&mut header)
+        .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?;
+    let header: Vec<&str> = header.trim_end().split(',').map(str::trim).collect();
+
+    for column in columns {
+        let Some(position) = header.iter().position(|name| *name == column) else {
+            continue;
+        };
+        let mut index = TableIndex::load(table, &column, db_path)?;
+        let current = index.table_state == before;
+        let mut row_offset = before.size;
+        for row in rows {
+            let key = row.get(position).map_or("", String::as_str);
+            index.insert(key, row_offset);
+            row_offset += row.join(",").len() as u64 + 1;
+        }
+        index.table_state = after;
+        if !current || row_offset != after.size {
+            // el indice no estaba al dia, se arma de nuevo
+            index = TableIndex::build(table, &column, db_path)?;
+        }
+        index.save(table, db_path)?;
+    }
+    Ok(())
+}
+
+/// Elige la comparacion del where, unida por AND, que se resuelve con un indice.
+/// Prefiere las igualdades a los rangos
+#[allow(clippy::missing_errors_doc)]
+pub fn choose_index<'a>(
+    table: &str,
+    condition: &'a Condition,
+    db_path: &str,
+) -> Result<Option<&'a Condition>, ErrorType> {
+    let columns = TableIndex::columns(table, db_path)?;
+    let indexable: Vec<&Condition> = condition
+        .conjuncts()
+        .into_iter()
+        .filter(|conjunct| {
+            matches!(conjunct, Condition::Comparison(column, op, _)
+                if *op != ComparisonOp::Neq && columns.contains(column))
+        })
+        .collect();
+    Ok(indexable
+        .iter()
+        .find(|conjunct| matches!(conjunct, Condition::Comparison(_, ComparisonOp::Eq, _)))
+        .or(indexable.first())
+        .copied())
+}
+
+/// Devuelve las posiciones de las filas que cumplen la comparacion usando el indice de su columna.
+/// None si no se puede usar el indice, por ejemplo porque la tabla se modifico por fuera
+#[allow(clippy::missing_errors_doc)]
+pub fn index_lookup(
+    table: &str,
+    comparison: &Condition,
+    db_path: &str,
+) -> Result<Option<Vec<u64>>, ErrorType> {
+    let Condition::Comparison(column, op, value) = comparison else {
+        return Ok(None);
+    };
+    let index = TableIndex::load(table, column, db_path)?;
+    if !index.is_current(table, db_path)? {
+        return Ok(None);
+    }
+    Ok(index.lookup(op, value))
+}
+
+/// Busca en el where una comparacion sobre una columna indexada
+/// y devuelve las posiciones de las filas candidatas
+#[allow(clippy::missing_errors_doc)]
+pub fn index_scan(
+    table: &str,
+    where_st: &Option<Condition>,
+    db_path: &str,
+) -> Result<Option<Vec<u64>>, ErrorType> {
+    let Some(condition) = where_st else {
+        return Ok(None);
+    };
+    match choose_index(table, condition, db_path)? {
+        Some(comparison) => index_lookup(table, comparison, db_path),
+        None => Ok(None),
+    }
+}
+
+/// Lee las lineas de la tabla que empiezan en las posiciones dadas
+#[allow(clippy::missing_errors_doc)]
+pub fn read_rows_at(table: &str, offsets: &[u64], db_path: &str) -> Result<Vec<String>, ErrorType> {
+    let file = File::open(csv_path(table, db_path))
+        .map_err(|e| ErrorType::InvalidTable(format!("opening table {e} failed")))?;
+    let mut reader = BufReader::new(file);
+    let mut lines = Vec::new();
+    for offset in offsets {
+        reader
+            .seek(SeekFrom::Start(*offset))
+            .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?;
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|e| ErrorType::OtherError(format!("Error reading line: {e}")))?;
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Borra los indices de las columnas de la tabla, o todos si no se indica ninguna
+#[allow(clippy::missing_errors_doc)]
+pub fn remove_indexes(table: &str, column: Option<&str>, db_path: &str) -> Result<(), ErrorType> {
+    for indexed in TableIndex::columns(table, db_path)? {
+        if column.is_none_or(|column| column == indexed) {
+            fs::remove_file(TableIndex::path(table, &indexed, db_path))
+                .map_err(|e| ErrorType::OtherError(format!("removing index {e} failed")))?;
+        }
+    }
+    Ok(())
+}
+
+/// Renombra los archivos de los indices cuando cambia el nombre de la tabla o de una columna
+#[allow(clippy::missing_errors_doc)]
+pub fn rename_indexes(
+    table: &str,
+    new_table: &str,
+    column: Option<(&str, &str)>,
+    db_path: &str,
+) -> Result<(), ErrorType> {
+    for indexed in TableIndex::columns(table, db_path)? {
+        let new_column = match column {
+            Some((from, to)) if from == indexed => to,
+            _ => indexed.as_str(),
+        };
+        fs::rename(
+            TableIndex::path(table, &indexed, db_path),
+            TableIndex::path(new_table, new_column, db_path),
+        )
+        .map_err(|e| ErrorType::OtherError(format!("renaming index {e} failed")))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(db_path: &str) {
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+        fs::write(
+            format!("{db_path}/ordenes.csv"),
+            "id,id_cliente,producto\n1,2,Laptop\n2,10,Mouse\n3,2,Teclado\n4,,Monitor\n",
+        )
+        .expect("Failed to write table");
+    }
+
+    #[test]
+    fn test_index_lookup() {
+        let db_path = "./test_index_db1";
+        setup(db_path);
+        fs::write(
+            format!("{db_path}/ordenes.schema"),
+            "id INTEGER\nid_cliente INTEGER\nproducto TEXT\n",
+        )
+        .expect("Failed to write schema");
+
+        let index = TableIndex::build("ordenes", "id_cliente", db_path).unwrap();
+        index.save("ordenes", db_path).unwrap();
+        assert_eq!(
+            TableIndex::columns("ordenes", db_path).unwrap(),
+            ["id_cliente"]
+        );
+        let loaded = TableIndex::load("ordenes", "id_cliente", db_path).unwrap();
+        assert_eq!(loaded, index);
+        assert!(loaded.is_current("ordenes", db_path).unwrap());
+
+        let rows = |op, value| {
+            let offsets = index.lookup(&op, value).unwrap();
+            read_rows_at("ordenes", &offsets, db_path)
+                .unwrap()
+                .iter()
+                .map(|line| line.trim().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(ComparisonOp::Eq, "2"), ["1,2,Laptop", "3,2,Teclado"]);
+        // como numeros 10 es mayor a 2
+        assert_eq!(rows(ComparisonOp::Gt, "2"), ["2,10,Mouse"]);
+        assert_eq!(rows(ComparisonOp::Gte, "2").len(), 3);
+        // el NULL queda antes que cualquier valor, igual que en el where
+        assert_eq!(
+            rows(ComparisonOp::Lt, "10"),
+            ["1,2,Laptop", "3,2,Teclado", "4,,Monitor"]
+        );
+        assert!(index.lookup(&ComparisonOp::Neq, "2").is_none());
+
+        // una modificacion a mano que conserva el tamaño se detecta por la fecha
+        let csv = format!("{db_path}/ordenes.csv");
+        let content = fs::read_to_string(&csv)
+            .unwrap()
+            .replace("Laptop", "Tablet");
+        fs::write(&csv, content).unwrap();
+        File::options()
+            .write(true)
+            .open(&csv)
+            .and_then(|file| file.set_modified(UNIX_EPOCH))
+            .unwrap();
+        assert_eq!(
+            table_state("ordenes", db_path).unwrap().size,
+            index.table_state.size
+        );
+        assert!(!loaded.is_current("ordenes", db_path).unwrap());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_index_maintenance() {
+        let db_path = "./test_index_db2";
+        setup(db_path);
+
+        // sin esquema los valores se comparan igual que en el where
+        TableIndex::build("ordenes", "producto", db_path)
+            .unwrap()
+            .save("ordenes", db_path)
+            .unwrap();
+        let before = table_state("ordenes", db_path).unwrap();
+        let offset = before.size;
+        let row: Vec<String> = ["5", "3", "Auriculares"].map(String::from).to_vec();
+        let mut content = fs::read_to_string(format!("{db_path}/ordenes.csv")).unwrap();
+        content.push_str("5,3,Auriculares\n");
+        fs::write(format!("{db_path}/ordenes.csv"), content).unwrap();
+        append_to_indexes("ordenes", &[row], before, db_path).unwrap();
+
+        let loaded = TableIndex::load("ordenes", "producto", db_path).unwrap();
+        assert_eq!(
+            loaded,
+            TableIndex::build("ordenes", "producto", db_path).unwrap()
+        );
+
+        let where_st = Some(Condition::And(
+            Box::new(Condition::Comparison(
+                "id".to_string(),
+                ComparisonOp::Gt,
+                "1".to_string(),
+            )),
+            Box::new(Condition::Comparison(
+                "producto".to_string(),
+                ComparisonOp::Eq,
+                "Auriculares".to_string(),
+            )),
+        ));
+        assert_eq!(
+            index_scan("ordenes", &where_st, db_path).unwrap(),
+            Some(vec![offset])
+        );
+
+        // si la tabla se modifico por fuera el indice no se usa
+        fs::write(format!("{db_path}/ordenes.csv"), "id,id_cliente,producto\n").unwrap();
+        assert_eq!(index_scan("ordenes", &where_st, db_path).unwrap(), None);
+
+        rename_indexes(
+            "ordenes",
+            "pedidos",
+            Some(("producto", "articulo")),
+            db_path,
+        )
+        .unwrap();
+        assert_eq!(
+            TableIndex::columns("pedidos", db_path).unwrap(),
+            ["articulo"]
+        );
+        remove_indexes("pedidos", None, db_path).unwrap();
+        assert!(TableIndex::columns("pedidos", db_path).unwrap().is_empty());
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+}
@@ -1,7 +1,14 @@
 // Declaro modulos
+pub mod command_result;
 pub mod command_types;
 pub mod commands;
 pub mod condition;
+pub mod database;
 pub mod errors;
+pub mod index;
 pub mod order;
+pub mod parameters;
 pub mod parser;
+pub mod rows;
+pub mod schema;
+pub mod window;
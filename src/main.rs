@@ -1,17 +1,10 @@
-// Declaro modulos
-mod command_types;
-mod commands;
-mod condition;
-mod errors;
-mod order;
-mod parser;
-
-use command_types::Commands;
-use commands::execute;
-use errors::ErrorType;
-use parser::parse_query;
+use sql_rustico::command_types::Commands;
+use sql_rustico::commands::execute_to;
+use sql_rustico::errors::ErrorType;
+use sql_rustico::parser::parse_query;
 use std::env;
 use std::error::Error;
+use std::io::{self, BufWriter, Write};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // Leo argumentos y chequeo que hayan mas de uno
@@ -28,8 +21,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     // parseo la query, chequeo errores y obtengo el comando
     let command: Commands = parse_query(&mut query.to_string()).map_err(|e| e.create_error())?;
 
-    // ejecuto el comando y busco errores
-    execute(&command, db_path).map_err(|e| e.create_error())?;
+    // ejecuto el comando escribiendo su salida a medida que se produce
+    let mut out = BufWriter::new(io::stdout().lock());
+    let result = execute_to(&command, db_path, &mut out).map_err(|e| e.create_error())?;
+
+    // los comandos de escritura informan la cantidad de filas afectadas
+    if result.is_write() {
+        writeln!(out, "{result}")?;
+    }
+    out.flush()?;
 
     Ok(())
 }
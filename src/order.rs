@@ -1,15 +1,26 @@
 #![allow(clippy::module_name_repetitions)]
 
-#[derive(Debug)]
+use std::fmt;
+
+#[derive(Debug, Clone, Copy)]
 /// Representacion de las direcciones de ordenamiento
 pub enum Direction {
     Ascending,
     Descending,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Estructura que maneja la direccion de cada columna a ordenar
 pub struct OrderBy {
     pub column: String,
     pub direction: Direction,
 }
+
+impl fmt::Display for OrderBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            Direction::Ascending => write!(f, "{} ASC", self.column),
+            Direction::Descending => write!(f, "{} DESC", self.column),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::errors::ErrorType;
+use std::fmt;
+
+/// Parametro de una sentencia preparada, se reemplaza por un valor antes de ejecutarla
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `?` o `?N`: al parsear cada `?` toma el numero siguiente al mayor visto hasta ahi
+    Positional(usize),
+    /// `$N`, el N-esimo valor
+    Numbered(usize),
+    /// `:nombre`, el valor con ese nombre
+    Named(String),
+}
+
+impl Placeholder {
+    /// Parsea el parametro escrito en la query, `None` si la palabra no es un parametro
+    #[must_use]
+    pub fn parse(word: &str) -> Option<Self> {
+        let number = |digits: &str| digits.parse().ok().filter(|n| *n > 0);
+        if let Some(digits) = word.strip_prefix('?') {
+            number(digits).map(Placeholder::Positional)
+        } else if let Some(digits) = word.strip_prefix('$') {
+            number(digits).map(Placeholder::Numbered)
+        } else {
+            let name = word.strip_prefix(':')?;
+            let valid = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            valid.then(|| Placeholder::Named(name.to_string()))
+        }
+    }
+
+    /// Posicion del valor entre los pasados en orden, los parametros con nombre no tienen
+    #[must_use]
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Placeholder::Positional(n) | Placeholder::Numbered(n) => Some(*n),
+            Placeholder::Named(_) => None,
+        }
+    }
+
+    /// Error de ejecutar la query sin haberle dado valor al parametro
+    #[must_use]
+    pub fn unbound(&self) -> ErrorType {
+        ErrorType::InvalidSyntax(format!("Parameter {self} is not bound"))
+    }
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placeholder::Positional(n) => write!(f, "?{n}"),
+            Placeholder::Numbered(n) => write!(f, "${n}"),
+            Placeholder::Named(name) => write!(f, ":{name}"),
+        }
+    }
+}
+
+/// Conversion de un valor de Rust al texto con el que se guarda en la tabla.
+/// `None` se guarda como el valor vacio, que representa NULL
+pub trait ToValue {
+    fn to_value(&self) -> String;
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> String {
+        self.clone()
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> String {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> String {
+        self.as_ref().map(ToValue::to_value).unwrap_or_default()
+    }
+}
+
+macro_rules! impl_to_value {
+    ($($ty:ty),*) => {
+        $(impl ToValue for $ty {
+            fn to_value(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+impl_to_value!(bool, i32, i64, u32, u64, usize, f32, f64);
+
+/// Valor pasado para un parametro, con su nombre si se pasan por nombre
+struct Bound {
+    name: Option<String>,
+    value: String,
+    used: bool,
+}
+
+/// Valores con los que se reemplazan los parametros de una sentencia
+pub struct Bindings {
+    values: Vec<Bound>,
+}
+
+impl Bindings {
+    /// Valores en orden, para los parametros `?` y `$N`
+    #[must_use]
+    pub fn new(params: &[&dyn ToValue]) -> Self {
+        Bindings {
+            values: params
+                .iter()
+                .map(|param| Bound {
+                    name: None,
+                    value: param.to_value(),
+                    used: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Valores por nombre, para los parametros `:nombre`. El nombre puede incluir el `:`
+    #[must_use]
+    pub fn named(params: &[(&str, &dyn ToValue)]) -> Self {
+        Bindings {
+            values: params
+                .iter()
+                .map(|(name, param)| Bound {
+                    name: Some(name.strip_prefix(':').unwrap_or(name).to_string()),
+                    value: param.to_value(),
+                    used: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Devuelve el valor del parametro
+    #[allow(clippy::missing_errors_doc)]
+    pub fn value(&mut self, placeholder: &Placeholder) -> Result<String, ErrorType> {
+        let bound = match placeholder {
+            Placeholder::Named(name) => self
+                .values
+                .iter_mut()
+                .find(|bound| bound.name.as_ref() == Some(name)),
+            _ => placeholder
+                .position()
+                .and_then(|n| self.values.get_mut(n - 1))
+                .filter(|bound| bound.name.is_none()),
+        };
+        let bound = bound.ok_or_else(|| {
+            ErrorType::InvalidSyntax(format!("Missing value for parameter {placeholder}"))
+        })?;
+        bound.used = true;
+        Ok(bound.value.clone())
+    }
+
+    /// Verifica que se hayan usado todos los valores
+    #[allow(clippy::missing_errors_doc)]
+    pub fn finish(&self) -> Result<(), ErrorType> {
+        match self
+            .values
+            .iter()
+            .enumerate()
+            .find(|(_, bound)| !bound.used)
+        {
+            Some((
+                _,
+                Bound {
+                    name: Some(name), ..
+                },
+            )) => Err(ErrorType::InvalidSyntax(format!(
+                "Parameter :{name} is not used in the query"
+            ))),
+            Some((i, _)) => Err(ErrorType::InvalidSyntax(format!(
+                "Parameter {} is not used in the query",
+                i + 1
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_placeholder() {
+        assert_eq!(Placeholder::parse("?2"), Some(Placeholder::Positional(2)));
+        assert_eq!(Placeholder::parse("$1"), Some(Placeholder::Numbered(1)));
+        assert_eq!(
+            Placeholder::parse(":id_cliente"),
+            Some(Placeholder::Named("id_cliente".to_string()))
+        );
+        for word in ["?", "$0", "$a", ":", ":1a", ":a-b", "id", "'$1'"] {
+            assert_eq!(Placeholder::parse(word), None);
+        }
+        assert_eq!(Placeholder::Numbered(3).to_string(), "$3");
+    }
+
+    #[test]
+    fn test_bindings_by_position_and_name() {
+        let mut bindings = Bindings::new(&[&1, &"Ana"]);
+        assert_eq!(bindings.value(&Placeholder::Numbered(2)).unwrap(), "Ana");
+        assert!(bindings.finish().is_err());
+        // el mismo valor se puede usar mas de una vez
+        assert_eq!(bindings.value(&Placeholder::Positional(1)).unwrap(), "1");
+        assert_eq!(bindings.value(&Placeholder::Numbered(1)).unwrap(), "1");
+        assert!(bindings.value(&Placeholder::Numbered(3)).is_err());
+        assert!(bindings
+            .value(&Placeholder::Named("id".to_string()))
+            .is_err());
+        bindings.finish().unwrap();
+
+        let mut bindings = Bindings::named(&[(":id", &Some(2)), ("nombre", &None::<String>)]);
+        assert_eq!(
+            bindings
+                .value(&Placeholder::Named("id".to_string()))
+                .unwrap(),
+            "2"
+        );
+        assert!(bindings.value(&Placeholder::Positional(1)).is_err());
+        assert!(bindings.finish().is_err());
+        assert_eq!(
+            bindings
+                .value(&Placeholder::Named("nombre".to_string()))
+                .unwrap(),
+            ""
+        );
+        bindings.finish().unwrap();
+    }
+}
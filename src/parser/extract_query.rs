@@ -1,6 +1,8 @@
+use crate::command_types::Value;
 use crate::condition::{ComparisonOp, Condition};
 use crate::errors::ErrorType;
 use crate::order::{Direction, OrderBy};
+use crate::parameters::Placeholder;
 
 /// Corrobora que la siguiente palabra sea la esperada
 pub fn expected_next_word(s: &mut String, expected: &str) -> Result<(), ErrorType> {
@@ -37,6 +39,20 @@ pub fn extract_table_names(s: &mut String) -> Result<Vec<String>, ErrorType> {
     }
 }
 
+/// Verifica que el nombre de la tabla sea un identificador: letras, numeros y '_', sin empezar
+/// con un numero. Asi no puede salir del directorio de la base ni chocar con el `*`
+pub fn check_table_name(table: &str) -> Result<(), ErrorType> {
+    let valid = table.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && table.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ErrorType::InvalidTable(format!(
+            "Invalid table name '{table}'"
+        )))
+    }
+}
+
 /// Verifica si una cadena esta entre comillas simples y, de ser asi, las quita.
 pub fn strip_single_quotes(s: &str) -> String {
     let trimmed = s.trim();
@@ -68,6 +84,101 @@ pub fn extract_next_word(s: &mut String) -> Option<String> {
     }
 }
 
+/// Parsea un valor escrito en la query: un parametro, una referencia `EXCLUDED.columna`, o un
+/// literal al que se le quitan las comillas. Entre comillas siempre es un literal
+pub fn parse_value(raw: &str) -> Value {
+    let raw = raw.trim();
+    if let Some(placeholder) = Placeholder::parse(raw) {
+        return Value::Parameter(placeholder);
+    }
+    match raw.strip_prefix("EXCLUDED.") {
+        Some(column) => Value::Excluded(column.to_string()),
+        None => Value::Literal(strip_single_quotes(raw)),
+    }
+}
+
+/// Extrae la siguiente palabra como un valor, ver `parse_value`
+pub fn extract_next_value(s: &mut String) -> Option<Value> {
+    let quoted = s.trim_start().starts_with('\'');
+    let word = extract_next_word(s)?;
+    match Placeholder::parse(&word) {
+        Some(placeholder) if !quoted => Some(Value::Parameter(placeholder)),
+        _ => Some(Value::Literal(word)),
+    }
+}
+
+/// Numera los `?` que no estan entre comillas como `?N`, con el numero siguiente al mayor
+/// visto hasta ahi. Asi cada parametro sabe que valor le toca sin importar en que orden se
+/// parsean las clausulas
+pub fn number_positional_placeholders(s: &mut String) {
+    let mut numbered = String::with_capacity(s.len());
+    let mut in_quotes = false;
+    let mut last = 0;
+    for (i, c) in s.char_indices() {
+        if c == '\'' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && (c == '?' || c == '$') {
+            let digits = s[i + 1..]
+                .find(|x: char| !x.is_ascii_digit())
+                .map_or(&s[i + 1..], |end| &s[i + 1..i + 1 + end]);
+            match digits.parse::<usize>() {
+                Ok(n) => last = last.max(n),
+                Err(_) if c == '?' => {
+                    last += 1;
+                    numbered.push_str(&format!("?{last}"));
+                    continue;
+                }
+                Err(_) => {}
+            }
+        }
+        numbered.push(c);
+    }
+    *s = numbered;
+}
+
+/// Busca la posicion de una palabra clave que no este entre comillas
+pub fn find_keyword(s: &str, keyword: &str) -> Option<usize> {
+    let mut in_quotes = false;
+    let mut previous = ' ';
+
+    for (i, c) in s.char_indices() {
+        if c == '\'' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && previous.is_whitespace() && s[i..].starts_with(keyword) {
+            let end = i + keyword.len();
+            if s[end..].starts_with(|x: char| x.is_whitespace() || x == ';') || end == s.len() {
+                return Some(i);
+            }
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Extrae y parsea la clausula RETURNING, que debe ser la ultima de la query.
+/// La quita de la query y devuelve las columnas a retornar
+pub fn extract_returning_clause(s: &mut String) -> Result<Option<Vec<String>>, ErrorType> {
+    let Some(start) = find_keyword(s, "RETURNING") else {
+        return Ok(None);
+    };
+
+    let columns: Vec<String> = s[start + "RETURNING".len()..]
+        .trim()
+        .trim_end_matches(';')
+        .split(',')
+        .map(|x| x.trim().to_string())
+        .collect();
+    if columns.iter().any(String::is_empty) {
+        return Err(ErrorType::InvalidSyntax(
+            "Expected columns or '*' after RETURNING".into(),
+        ));
+    }
+
+    s.truncate(start);
+    s.push(';');
+    Ok(Some(columns))
+}
+
 /// Obtiene el siguiente caracter
 pub fn get_next_char(s: &mut str) -> Option<char> {
     let trimmed = s.trim_start();
@@ -76,6 +187,22 @@ pub fn get_next_char(s: &mut str) -> Option<char> {
 
 /// Extrae los valores dentro de un parentesis y los devuelve en un Vector de String
 pub fn extract_between_parenthesis(s: &mut String) -> Result<Vec<String>, ErrorType> {
+    Ok(extract_raw_between_parenthesis(s)?
+        .iter()
+        .map(|value| strip_single_quotes(value))
+        .collect())
+}
+
+/// Extrae los valores entre parentesis, que pueden ser literales o parametros
+pub fn extract_values_between_parenthesis(s: &mut String) -> Result<Vec<Value>, ErrorType> {
+    Ok(extract_raw_between_parenthesis(s)?
+        .iter()
+        .map(|value| parse_value(value))
+        .collect())
+}
+
+/// Extrae lo que esta entre parentesis separado por ',', sin quitar las comillas
+fn extract_raw_between_parenthesis(s: &mut String) -> Result<Vec<String>, ErrorType> {
     let mut result = Vec::new();
     let trimmed = s.trim_start();
 
@@ -87,10 +214,7 @@ pub fn extract_between_parenthesis(s: &mut String) -> Result<Vec<String>, ErrorT
 
     if let Some(closing_parenthesis) = trimmed.find(')') {
         let aux = trimmed[1..closing_parenthesis].trim(); // toma el string detro de '(' ')'
-        result.extend(aux.split(',').map(|x| {
-            let value = x.trim();
-            strip_single_quotes(value) // Retorna el valor sin comillas
-        }));
+        result.extend(aux.split(',').map(|x| x.trim().to_string()));
         *s = trimmed[closing_parenthesis + 1..].to_string();
         Ok(result)
     } else {
@@ -100,10 +224,18 @@ pub fn extract_between_parenthesis(s: &mut String) -> Result<Vec<String>, ErrorT
     }
 }
 
+/// Busca el inicio de la siguiente clausula (ORDER o LIMIT), si no hay se posiciona antes del ';' final
+fn find_clause_end(s: &str) -> usize {
+    ["ORDER", "LIMIT"]
+        .iter()
+        .filter_map(|clause| s.find(clause))
+        .min()
+        .unwrap_or(s.len() - 1)
+}
+
 /// Extrae y parsea la condicion where
 pub fn extract_and_parse_where_clause(s: &mut String) -> Result<Condition, ErrorType> {
-    // Busca ORDER, si no esta se posiciona antes del ';' final
-    let clause_end_pos = s.find("ORDER").unwrap_or(s.len() - 1);
+    let clause_end_pos = find_clause_end(s);
 
     let mut extracted_str = s[..clause_end_pos].trim().to_string();
 
@@ -112,6 +244,19 @@ pub fn extract_and_parse_where_clause(s: &mut String) -> Result<Condition, Error
     parse_or_condition(&mut extracted_str)
 }
 
+/// Parsea una condicion completa, por ejemplo la de un CHECK
+pub fn parse_condition(expression: &str) -> Result<Condition, ErrorType> {
+    let mut s = format!("{expression};");
+    let condition = extract_and_parse_where_clause(&mut s)?;
+    if !matches!(s.trim(), "" | ";") {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Unexpected '{}' in condition '{expression}'",
+            s.trim().trim_end_matches(';')
+        )));
+    }
+    Ok(condition)
+}
+
 /// Busca condicion or
 fn parse_or_condition(s: &mut String) -> Result<Condition, ErrorType> {
     let mut left = parse_and_condition(s)?;
@@ -146,13 +291,18 @@ fn parse_and_condition(s: &mut String) -> Result<Condition, ErrorType> {
 
 /// Busca condicion not
 fn parse_not_condition(s: &mut String) -> Result<Condition, ErrorType> {
+    let quoted = s.trim_start().starts_with('\'');
     if let Some(word) = extract_next_word(s) {
         if word == "NOT" {
             let condition = parse_comparison_condition(s)?;
             return Ok(Condition::Not(Box::new(condition)));
         }
-        // si no hay esta condicion vuelvo a insertar el texto
-        s.insert_str(0, &format!("{word} "));
+        // si no hay esta condicion vuelvo a insertar el texto, con sus comillas
+        if quoted {
+            s.insert_str(0, &format!("'{word}' "));
+        } else {
+            s.insert_str(0, &format!("{word} "));
+        }
     } else {
         // Si no hay más palabras (None), devolvemos un error
         return Err(ErrorType::InvalidSyntax(
@@ -163,7 +313,7 @@ fn parse_not_condition(s: &mut String) -> Result<Condition, ErrorType> {
 }
 
 /// Parsea la comparacion
-/// left: columna
+/// left: columna, o un valor constante que conserva sus comillas
 /// op: operacion
 /// right: valor
 fn parse_comparison_condition(s: &mut String) -> Result<Condition, ErrorType> {
@@ -182,14 +332,22 @@ fn parse_comparison_condition(s: &mut String) -> Result<Condition, ErrorType> {
         return Ok(condition);
     }
 
-    let left =
+    let quoted = trimmed.starts_with('\'');
+    let mut left =
         extract_next_word(s).ok_or(ErrorType::InvalidSyntax("Missing left operand".to_string()))?;
+    // las comillas distinguen un texto constante de una columna con el mismo nombre
+    if quoted {
+        left = format!("'{left}'");
+    } else if Placeholder::parse(&left).is_some() {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Parameter {left} must be on the right side of the comparison"
+        )));
+    }
     let op =
         extract_next_word(s).ok_or(ErrorType::InvalidSyntax("Missing operator".to_string()))?;
-    let right = extract_next_word(s).ok_or(ErrorType::InvalidSyntax(
+    let right = extract_next_value(s).ok_or(ErrorType::InvalidSyntax(
         "Missing right operand".to_string(),
     ))?;
-    let right = strip_single_quotes(&right);
 
     let comparison_op = match op.as_str() {
         "=" => ComparisonOp::Eq,
@@ -205,7 +363,10 @@ fn parse_comparison_condition(s: &mut String) -> Result<Condition, ErrorType> {
         }
     };
 
-    Ok(Condition::Comparison(left, comparison_op, right))
+    Ok(match right {
+        Value::Parameter(placeholder) => Condition::Parameter(left, comparison_op, placeholder),
+        right => Condition::Comparison(left, comparison_op, strip_single_quotes(&right.literal()?)),
+    })
 }
 
 /// Extrae y parsea la condicion order
@@ -218,6 +379,12 @@ pub fn extract_and_parse_order_clause(s: &mut String) -> Result<Vec<OrderBy>, Er
     // para chequear que haya al menos una condicion de orden
     let mut first_word = false;
     while let Some(column) = extract_next_word(s) {
+        // el LIMIT termina la clausula de orden
+        if column == "LIMIT" {
+            s.insert_str(0, "LIMIT ");
+            break;
+        }
+
         if column == ";" && !first_word {
             return Err(ErrorType::InvalidSyntax(
                 "The first word cant be ';'".into(),
@@ -238,6 +405,14 @@ pub fn extract_and_parse_order_clause(s: &mut String) -> Result<Vec<OrderBy>, Er
                     s.insert(0, ';');
                     Direction::Ascending // como default ordena ascendentemente
                 }
+                "LIMIT" => {
+                    s.insert_str(0, "LIMIT ");
+                    order_by.push(OrderBy {
+                        column,
+                        direction: Direction::Ascending,
+                    });
+                    break;
+                }
                 _ => {
                     s.insert_str(0, &format!("{next_word} "));
                     return Err(ErrorType::InvalidSyntax(format!(
@@ -260,3 +435,21 @@ pub fn extract_and_parse_order_clause(s: &mut String) -> Result<Vec<OrderBy>, Er
 
     Ok(order_by)
 }
+
+/// Extrae y parsea la cantidad maxima de filas del LIMIT
+pub fn extract_and_parse_limit_clause(s: &mut String) -> Result<Value<usize>, ErrorType> {
+    let limit = extract_next_value(s).ok_or(ErrorType::InvalidSyntax(
+        "Expected a number after LIMIT".into(),
+    ))?;
+
+    match limit {
+        Value::Parameter(placeholder) => Ok(Value::Parameter(placeholder)),
+        limit => {
+            let limit = limit.literal()?;
+            limit
+                .parse::<usize>()
+                .map(Value::Literal)
+                .map_err(|_| ErrorType::InvalidSyntax(format!("Invalid LIMIT value: {limit}")))
+        }
+    }
+}
@@ -1,17 +1,25 @@
 mod extract_query;
+mod parse_alter;
+mod parse_create;
 mod parse_delete;
+mod parse_drop;
 mod parse_insert;
 mod parse_select;
 mod parse_update;
+mod parse_window;
 
+use parse_alter::alter_parser;
+use parse_create::create_parser;
 use parse_delete::delete_parser;
+use parse_drop::drop_parser;
 use parse_insert::insert_parser;
 use parse_select::select_parser;
 use parse_update::update_parser;
 
 use crate::command_types::Commands;
 use crate::errors::ErrorType;
-use extract_query::extract_next_word;
+use extract_query::{extract_next_word, number_positional_placeholders};
+pub use parse_create::{parse_column_definition, parse_table_constraint};
 use std::str::FromStr;
 
 /// Parsea la query y devuelve el `Commands` inicializado o el `ErrorType` correspondiente
@@ -21,6 +29,7 @@ pub fn parse_query(query: &mut String) -> Result<Commands, ErrorType> {
     if !query.trim_end().ends_with(';') {
         query.push(';');
     }
+    number_positional_placeholders(query);
 
     let command =
         extract_next_word(query).ok_or(ErrorType::InvalidSyntax("No command found".to_string()))?;
@@ -28,7 +37,28 @@ pub fn parse_query(query: &mut String) -> Result<Commands, ErrorType> {
         Ok(Commands::Insert { .. }) => insert_parser(query),
         Ok(Commands::Update { .. }) => update_parser(query),
         Ok(Commands::Delete { .. }) => delete_parser(query),
-        Ok(Commands::Select { .. }) => select_parser(query),
+        Ok(Commands::Select { .. } | Commands::Compound { .. }) => select_parser(query),
+        Ok(Commands::CreateTable { .. } | Commands::CreateIndex { .. }) => create_parser(query),
+        Ok(Commands::DropTable { .. }) => drop_parser(query),
+        Ok(Commands::AlterTable { .. }) => alter_parser(query),
+        Ok(Commands::Explain { .. }) => explain_parser(query),
         Err(e) => Err(ErrorType::InvalidSyntax(e.to_string())),
     }
 }
+
+/// Parsea `EXPLAIN [ANALYZE] <select>`, la consulta puede ser un select simple o combinado
+fn explain_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    let analyze = query.trim_start().starts_with("ANALYZE ");
+    if analyze {
+        extract_next_word(query);
+    }
+    match parse_query(query)? {
+        command @ (Commands::Select { .. } | Commands::Compound { .. }) => Ok(Commands::Explain {
+            query: Box::new(command),
+            analyze,
+        }),
+        _ => Err(ErrorType::InvalidSyntax(
+            "EXPLAIN only supports SELECT queries".to_string(),
+        )),
+    }
+}
@@ -0,0 +1,154 @@
+use super::extract_query::{check_table_name, expected_next_word, extract_next_word};
+use super::parse_create::parse_column_definition;
+use crate::command_types::{AlterOperation, Commands};
+use crate::errors::ErrorType;
+
+/// Consume la palabra COLUMN, que es opcional
+fn skip_column_keyword(query: &mut String) {
+    if query.trim_start().starts_with("COLUMN ") {
+        extract_next_word(query);
+    }
+}
+
+/// Extrae el nombre de una columna o tabla, que no puede estar vacio
+fn extract_name(query: &mut String) -> Result<String, ErrorType> {
+    match extract_next_word(query) {
+        Some(name) if !name.is_empty() && name != ";" => Ok(name),
+        _ => Err(ErrorType::InvalidSyntax(
+            "Expected a name in ALTER TABLE".into(),
+        )),
+    }
+}
+
+/// Verifica que no quede nada despues de la operacion
+fn expect_end_of_query(query: &mut String) -> Result<(), ErrorType> {
+    match extract_next_word(query) {
+        Some(word) if word != ";" => Err(ErrorType::InvalidSyntax(format!(
+            "Expected end of query, found '{word}'"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Parsea `ALTER TABLE tabla` seguido de `ADD [COLUMN] definicion`, `DROP [COLUMN] columna`,
+/// `RENAME [COLUMN] columna TO nombre` o `RENAME TO nombre`
+pub fn alter_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    expected_next_word(query, "TABLE")?;
+    let table = extract_name(query)?;
+    check_table_name(&table)?;
+
+    let operation = match extract_next_word(query) {
+        Some(word) if word == "ADD" => {
+            skip_column_keyword(query);
+            let definition = query.trim().trim_end_matches(';').to_string();
+            query.clear();
+            AlterOperation::AddColumn(parse_column_definition(&definition)?)
+        }
+        Some(word) if word == "DROP" => {
+            skip_column_keyword(query);
+            AlterOperation::DropColumn(extract_name(query)?)
+        }
+        Some(word) if word == "RENAME" => {
+            if query.trim_start().starts_with("TO ") {
+                expected_next_word(query, "TO")?;
+                let name = extract_name(query)?;
+                check_table_name(&name)?;
+                AlterOperation::RenameTable(name)
+            } else {
+                skip_column_keyword(query);
+                let from = extract_name(query)?;
+                expected_next_word(query, "TO")?;
+                AlterOperation::RenameColumn {
+                    from,
+                    to: extract_name(query)?,
+                }
+            }
+        }
+        _ => {
+            return Err(ErrorType::InvalidSyntax(
+                "Expected ADD, DROP or RENAME after ALTER TABLE".into(),
+            ))
+        }
+    };
+    expect_end_of_query(query)?;
+
+    Ok(Commands::AlterTable { table, operation })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alter_parser_add_column_with_default() {
+        let mut query =
+            String::from("TABLE clientes ADD COLUMN ciudad TEXT DEFAULT 'Buenos Aires';");
+        let result = alter_parser(&mut query);
+
+        if let Ok(Commands::AlterTable {
+            table,
+            operation: AlterOperation::AddColumn(column),
+        }) = result
+        {
+            assert_eq!(table, "clientes");
+            assert_eq!(column.name, "ciudad");
+            assert_eq!(column.default, Some("Buenos Aires".to_string()));
+        } else {
+            panic!("Expected an AlterTable command adding a column");
+        }
+    }
+
+    #[test]
+    fn test_alter_parser_drop_and_rename() {
+        let mut query = String::from("TABLE clientes DROP email;");
+        assert!(matches!(
+            alter_parser(&mut query),
+            Ok(Commands::AlterTable {
+                operation: AlterOperation::DropColumn(column),
+                ..
+            }) if column == "email"
+        ));
+
+        let mut query = String::from("TABLE clientes RENAME COLUMN nombre TO nombres;");
+        assert!(matches!(
+            alter_parser(&mut query),
+            Ok(Commands::AlterTable {
+                operation: AlterOperation::RenameColumn { from, to },
+                ..
+            }) if from == "nombre" && to == "nombres"
+        ));
+
+        let mut query = String::from("TABLE clientes RENAME TO compradores;");
+        assert!(matches!(
+            alter_parser(&mut query),
+            Ok(Commands::AlterTable {
+                operation: AlterOperation::RenameTable(name),
+                ..
+            }) if name == "compradores"
+        ));
+    }
+
+    #[test]
+    fn test_alter_parser_errors() {
+        let mut query = String::from("TABLE clientes MODIFY id;");
+        assert!(alter_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes RENAME nombre nombres;");
+        assert!(alter_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes DROP id extra;");
+        assert!(alter_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE ../clientes DROP id;");
+        assert!(matches!(
+            alter_parser(&mut query),
+            Err(ErrorType::InvalidTable(_))
+        ));
+
+        let mut query = String::from("TABLE clientes RENAME TO ../compradores;");
+        assert!(matches!(
+            alter_parser(&mut query),
+            Err(ErrorType::InvalidTable(_))
+        ));
+    }
+}
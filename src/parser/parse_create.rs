@@ -0,0 +1,645 @@
+use super::extract_query::{
+    check_table_name, expected_next_word, extract_next_word, parse_condition, strip_single_quotes,
+};
+use super::parse_window::{find_closing_parenthesis, split_top_level_commas};
+use crate::command_types::Commands;
+use crate::errors::ErrorType;
+use crate::schema::{
+    CheckConstraint, ColumnDefinition, DataType, ForeignKey, ReferentialAction, TableConstraint,
+    TableSchema, UniqueConstraint,
+};
+use std::collections::HashSet;
+
+/// Consume `IF <palabras>` si la query continua con esa condicion
+pub fn extract_if_condition(query: &mut String, words: &[&str]) -> Result<bool, ErrorType> {
+    if !query.trim_start().starts_with("IF ") {
+        return Ok(false);
+    }
+    expected_next_word(query, "IF")?;
+    for word in words {
+        expected_next_word(query, word)?;
+    }
+    Ok(true)
+}
+
+/// Extrae el nombre de la tabla, que termina en un espacio o en el parentesis de las columnas
+fn extract_table_name(query: &mut String) -> Result<String, ErrorType> {
+    let trimmed = query.trim_start();
+    let end = trimmed
+        .find(|c: char| c.is_whitespace() || c == '(' || c == ';')
+        .unwrap_or(trimmed.len());
+    let table = trimmed[..end].to_string();
+    if table.is_empty() {
+        return Err(ErrorType::InvalidSyntax("Expected table name".into()));
+    }
+    check_table_name(&table)?;
+
+    *query = trimmed[end..].to_string();
+    Ok(table)
+}
+
+/// Separa la definicion en palabras, sin cortar los valores entre comillas ni parentesis
+fn split_definition_words(definition: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut in_quotes = false;
+
+    for c in definition.chars() {
+        match c {
+            '\'' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth -= 1,
+            c if c.is_whitespace() && !in_quotes && depth == 0 => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Parsea la condicion de un CHECK, que va entre parentesis
+fn parse_check_constraint(name: &str, check: &str) -> Result<CheckConstraint, ErrorType> {
+    let expression = check
+        .trim()
+        .strip_prefix('(')
+        .and_then(|check| check.strip_suffix(')'))
+        .ok_or(ErrorType::InvalidSyntax(format!(
+            "Expected condition between parenthesis after CHECK in column '{name}'"
+        )))?
+        .trim()
+        .to_string();
+
+    Ok(CheckConstraint {
+        condition: parse_condition(&expression)?,
+        expression,
+    })
+}
+
+/// Parsea una lista de columnas entre parentesis: `(columna, ...)`
+fn parse_column_list(list: &str, definition: &str) -> Result<Vec<String>, ErrorType> {
+    let columns: Vec<String> = list
+        .trim()
+        .strip_prefix('(')
+        .and_then(|columns| columns.strip_suffix(')'))
+        .ok_or(ErrorType::InvalidSyntax(format!(
+            "Expected columns between parenthesis in '{definition}'"
+        )))?
+        .split(',')
+        .map(|column| column.trim().to_string())
+        .collect();
+    if columns.iter().any(String::is_empty) {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected column names in '{definition}'"
+        )));
+    }
+    Ok(columns)
+}
+
+/// Parsea lo que sigue a `REFERENCES`: `tabla (columnas) [ON DELETE accion]`
+fn parse_references<'a>(
+    columns: Vec<String>,
+    words: &mut impl Iterator<Item = &'a String>,
+    definition: &str,
+) -> Result<ForeignKey, ErrorType> {
+    let expected_table = || {
+        ErrorType::InvalidSyntax(format!(
+            "Expected referenced table and columns in '{definition}'"
+        ))
+    };
+    let table = words.next().ok_or_else(expected_table)?;
+    // la tabla puede estar pegada a sus columnas: `tabla(columnas)`
+    let (table, list) = match table.find('(') {
+        Some(open) => (table[..open].to_string(), table[open..].to_string()),
+        None => (
+            table.to_string(),
+            words.next().ok_or_else(expected_table)?.to_string(),
+        ),
+    };
+    check_table_name(&table)?;
+
+    let mut foreign_key = ForeignKey {
+        columns,
+        table,
+        references: parse_column_list(&list, definition)?,
+        on_delete: ReferentialAction::default(),
+    };
+    while let Some(word) = words.next() {
+        if word.to_uppercase() != "ON" {
+            return Err(ErrorType::InvalidSyntax(format!(
+                "Unexpected '{word}' in '{definition}'"
+            )));
+        }
+        foreign_key.on_delete = parse_on_delete(words, definition)?;
+    }
+    Ok(foreign_key)
+}
+
+/// Parsea la accion de `ON DELETE`, ya consumido el ON
+fn parse_on_delete<'a>(
+    words: &mut impl Iterator<Item = &'a String>,
+    definition: &str,
+) -> Result<ReferentialAction, ErrorType> {
+    let mut next_word = || words.next().map(|word| word.to_uppercase());
+    if next_word().as_deref() != Some("DELETE") {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected DELETE after ON in '{definition}'"
+        )));
+    }
+    match (next_word().as_deref(), next_word().as_deref()) {
+        (Some("RESTRICT"), None) | (Some("NO"), Some("ACTION")) => Ok(ReferentialAction::Restrict),
+        (Some("CASCADE"), None) => Ok(ReferentialAction::Cascade),
+        (Some("SET"), Some("NULL")) => Ok(ReferentialAction::SetNull),
+        _ => Err(ErrorType::InvalidSyntax(format!(
+            "Expected RESTRICT, CASCADE or SET NULL after ON DELETE in '{definition}'"
+        ))),
+    }
+}
+
+/// Parsea una restriccion de tabla: `PRIMARY KEY (columnas)`, `UNIQUE (columnas)` o
+/// `FOREIGN KEY (columnas) REFERENCES tabla (columnas) [ON DELETE accion]`.
+/// Devuelve None si la definicion no es una restriccion sino una columna
+pub fn parse_table_constraint(definition: &str) -> Result<Option<TableConstraint>, ErrorType> {
+    let words = split_definition_words(definition);
+    let upper: Vec<String> = words.iter().map(|word| word.to_uppercase()).collect();
+    let upper: Vec<&str> = upper.iter().map(String::as_str).collect();
+
+    let constraint = match upper.as_slice() {
+        ["PRIMARY", "KEY", _] => TableConstraint::Unique(UniqueConstraint {
+            columns: parse_column_list(&words[2], definition)?,
+            primary_key: true,
+        }),
+        ["UNIQUE", _] => TableConstraint::Unique(UniqueConstraint {
+            columns: parse_column_list(&words[1], definition)?,
+            primary_key: false,
+        }),
+        ["FOREIGN", "KEY", _, "REFERENCES", ..] => {
+            let columns = parse_column_list(&words[2], definition)?;
+            TableConstraint::ForeignKey(parse_references(
+                columns,
+                &mut words[4..].iter(),
+                definition,
+            )?)
+        }
+        ["PRIMARY" | "UNIQUE" | "FOREIGN", ..] => {
+            return Err(ErrorType::InvalidSyntax(format!(
+                "Invalid table constraint '{definition}'"
+            )))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(constraint))
+}
+
+/// Parsea la definicion de una columna:
+/// `nombre TIPO [NOT NULL] [DEFAULT valor] [CHECK (condicion)] [AUTO_INCREMENT [proximo]] [PRIMARY KEY] [UNIQUE]
+/// [REFERENCES tabla (columna) [ON DELETE accion]]`
+pub fn parse_column_definition(definition: &str) -> Result<ColumnDefinition, ErrorType> {
+    let words = split_definition_words(definition);
+    let [name, data_type, constraints @ ..] = words.as_slice() else {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected column name and type, found '{definition}'"
+        )));
+    };
+
+    // SERIAL es un entero autoincremental que no admite NULL
+    let mut column = if data_type.to_uppercase() == "SERIAL" {
+        let mut column = ColumnDefinition::new(name, DataType::Integer);
+        column.auto_increment = Some(1);
+        column.not_null = true;
+        column
+    } else {
+        ColumnDefinition::new(name, data_type.parse()?)
+    };
+
+    let mut words = constraints.iter();
+    while let Some(word) = words.next() {
+        match word.to_uppercase().as_str() {
+            "NOT" => match words.next() {
+                Some(null) if null.to_uppercase() == "NULL" => column.not_null = true,
+                _ => {
+                    return Err(ErrorType::InvalidSyntax(format!(
+                        "Expected NULL after NOT in column '{name}'"
+                    )))
+                }
+            },
+            "DEFAULT" => {
+                let value = words.next().ok_or(ErrorType::InvalidSyntax(format!(
+                    "Expected value after DEFAULT in column '{name}'"
+                )))?;
+                let value = strip_single_quotes(value);
+                // el valor se guarda tal cual en cada fila del csv
+                if value.contains([',', '\n', '\r']) {
+                    return Err(ErrorType::InvalidSyntax(format!(
+                        "Default value of column '{name}' can't contain ',' or line breaks"
+                    )));
+                }
+                column.default = Some(value);
+            }
+            "CHECK" => {
+                let check = words.next().ok_or(ErrorType::InvalidSyntax(format!(
+                    "Expected condition after CHECK in column '{name}'"
+                )))?;
+                column.check = Some(parse_check_constraint(name, check)?);
+            }
+            upper if upper.starts_with("CHECK(") => {
+                column.check = Some(parse_check_constraint(name, &word["CHECK".len()..])?);
+            }
+            // la clave primaria no admite NULL
+            "PRIMARY" => match words.next() {
+                Some(key) if key.to_uppercase() == "KEY" => {
+                    column.primary_key = true;
+                    column.not_null = true;
+                }
+                _ => {
+                    return Err(ErrorType::InvalidSyntax(format!(
+                        "Expected KEY after PRIMARY in column '{name}'"
+                    )))
+                }
+            },
+            "UNIQUE" => column.unique = true,
+            // opcionalmente seguido del proximo valor a generar
+            "AUTO_INCREMENT" => {
+                if column.data_type != DataType::Integer {
+                    return Err(ErrorType::InvalidSyntax(format!(
+                        "AUTO_INCREMENT column '{name}' must be of type INTEGER"
+                    )));
+                }
+                let next = words.clone().next().and_then(|next| next.parse().ok());
+                if next.is_some() {
+                    words.next();
+                }
+                column.auto_increment = Some(next.unwrap_or(1));
+            }
+            // el ON DELETE queda como ultima parte de la definicion
+            "REFERENCES" => {
+                column.references = Some(parse_references(
+                    vec![name.to_string()],
+                    &mut words,
+                    definition,
+                )?);
+            }
+            _ => {
+                return Err(ErrorType::InvalidSyntax(format!(
+                    "Unexpected '{word}' in definition of column '{name}'"
+                )))
+            }
+        }
+    }
+
+    Ok(column)
+}
+
+/// Parsea `CREATE TABLE [IF NOT EXISTS] tabla (columna tipo, ..., [restriccion de tabla], ...)`
+pub fn create_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    match extract_next_word(query) {
+        Some(word) if word == "TABLE" => {}
+        Some(word) if word == "INDEX" => return create_index_parser(query),
+        Some(word) => {
+            return Err(ErrorType::InvalidSyntax(format!(
+                "Expected 'TABLE' or 'INDEX', found '{word}'"
+            )))
+        }
+        None => {
+            return Err(ErrorType::InvalidSyntax(
+                "Expected 'TABLE' or 'INDEX', found nothing".to_string(),
+            ))
+        }
+    }
+    let if_not_exists = extract_if_condition(query, &["NOT", "EXISTS"])?;
+    let table = extract_table_name(query)?;
+
+    let trimmed = query.trim_start().to_string();
+    if !trimmed.starts_with('(') {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected column definitions for table '{table}'"
+        )));
+    }
+    let close = find_closing_parenthesis(&trimmed, 0)?;
+    if trimmed[close + 1..].trim() != ";" {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Unexpected '{}' after column definitions",
+            trimmed[close + 1..].trim().trim_end_matches(';')
+        )));
+    }
+
+    let mut columns = Vec::new();
+    let mut keys = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut names = HashSet::new();
+    for definition in split_top_level_commas(&trimmed[1..close]) {
+        match parse_table_constraint(&definition)? {
+            Some(TableConstraint::Unique(key)) => {
+                keys.push(key);
+                continue;
+            }
+            Some(TableConstraint::ForeignKey(key)) => {
+                foreign_keys.push(key);
+                continue;
+            }
+            None => {}
+        }
+        let column = parse_column_definition(&definition)?;
+        if !names.insert(column.name.to_string()) {
+            return Err(ErrorType::InvalidColumn(format!(
+                "Column '{}' is defined more than once",
+                column.name
+            )));
+        }
+        columns.push(column);
+    }
+
+    // las columnas de la clave primaria no admiten NULL
+    for key in keys.iter().filter(|key| key.primary_key) {
+        for column in &mut columns {
+            if key.columns.contains(&column.name) {
+                column.not_null = true;
+            }
+        }
+    }
+    let schema = TableSchema {
+        columns,
+        keys,
+        foreign_keys,
+    };
+    schema.validate_keys()?;
+
+    Ok(Commands::CreateTable {
+        table,
+        schema,
+        if_not_exists,
+    })
+}
+
+/// Parsea `CREATE INDEX [IF NOT EXISTS] ON tabla (columna)`
+fn create_index_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    let if_not_exists = extract_if_condition(query, &["NOT", "EXISTS"])?;
+    expected_next_word(query, "ON")?;
+    let table = extract_table_name(query)?;
+
+    let trimmed = query.trim_start().to_string();
+    if !trimmed.starts_with('(') {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected indexed column for table '{table}'"
+        )));
+    }
+    let close = find_closing_parenthesis(&trimmed, 0)?;
+    if trimmed[close + 1..].trim() != ";" {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Unexpected '{}' after indexed column",
+            trimmed[close + 1..].trim().trim_end_matches(';')
+        )));
+    }
+    let column = trimmed[1..close].trim().to_string();
+    if column.is_empty() || column.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected a single column to index, found '{column}'"
+        )));
+    }
+
+    Ok(Commands::CreateIndex {
+        table,
+        column,
+        if_not_exists,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::DataType;
+
+    #[test]
+    fn test_create_parser() {
+        let mut query = String::from(
+            "TABLE IF NOT EXISTS clientes(id INT NOT NULL CHECK(id > 0), nombre text DEFAULT 'sin nombre');",
+        );
+        let result = create_parser(&mut query);
+
+        if let Ok(Commands::CreateTable {
+            table,
+            schema,
+            if_not_exists,
+        }) = result
+        {
+            let columns = schema.columns;
+            assert_eq!(table, "clientes");
+            assert!(if_not_exists);
+            assert_eq!(columns.len(), 2);
+            assert_eq!(columns[1].name, "nombre");
+            assert_eq!(columns[0].data_type, DataType::Integer);
+            assert!(columns[0].not_null);
+            assert_eq!(
+                columns[0]
+                    .check
+                    .as_ref()
+                    .map(|check| check.expression.as_str()),
+                Some("id > 0")
+            );
+            assert!(!columns[1].not_null);
+            assert_eq!(columns[1].data_type, DataType::Text);
+            assert_eq!(columns[1].default, Some("sin nombre".to_string()));
+        } else {
+            panic!("Expected a CreateTable command");
+        }
+    }
+
+    #[test]
+    fn test_create_index_parser() {
+        let mut query = String::from("INDEX IF NOT EXISTS ON ordenes (id_cliente);");
+        if let Ok(Commands::CreateIndex {
+            table,
+            column,
+            if_not_exists,
+        }) = create_parser(&mut query)
+        {
+            assert_eq!(table, "ordenes");
+            assert_eq!(column, "id_cliente");
+            assert!(if_not_exists);
+        } else {
+            panic!("Expected a CreateIndex command");
+        }
+
+        let mut query = String::from("INDEX ON ordenes(producto);");
+        assert!(matches!(
+            create_parser(&mut query),
+            Ok(Commands::CreateIndex {
+                if_not_exists: false,
+                ..
+            })
+        ));
+
+        for invalid in [
+            "INDEX ordenes (id_cliente);",
+            "INDEX ON ordenes;",
+            "INDEX ON ordenes (id_cliente, producto);",
+            "INDEX ON ordenes ();",
+            "INDEX ON ordenes (id_cliente) extra;",
+            "VIEW ordenes;",
+        ] {
+            let mut query = invalid.to_string();
+            assert!(create_parser(&mut query).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_create_parser_errors() {
+        let mut query = String::from("TABLE clientes;");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id);");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id INT, id TEXT);");
+        assert!(matches!(
+            create_parser(&mut query),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+
+        let mut query = String::from("TABLE clientes (id NUMERO);");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id INT DEFAULT);");
+        assert!(create_parser(&mut query).is_err());
+
+        let result = parse_column_definition("tag TEXT DEFAULT 'a,b'");
+        assert!(matches!(result, Err(ErrorType::InvalidSyntax(_))));
+
+        let mut query = String::from("TABLE clientes (id INT NOT);");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id INT CHECK id > 0);");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id INT) extra;");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id INT PRIMARY KEY, PRIMARY KEY (id));");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes (id INT, UNIQUE (email));");
+        assert!(matches!(
+            create_parser(&mut query),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+
+        let mut query = String::from("TABLE clientes (id INT, UNIQUE id);");
+        assert!(create_parser(&mut query).is_err());
+        let mut query = String::from("TABLE clientes (id TEXT AUTO_INCREMENT);");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query =
+            String::from("TABLE ordenes (id_cliente INT REFERENCES clientes (id) ON DELETE DROP);");
+        assert!(create_parser(&mut query).is_err());
+
+        let mut query = String::from(
+            "TABLE ordenes (id INT, FOREIGN KEY (id, otro) REFERENCES clientes (id));",
+        );
+        assert!(matches!(
+            create_parser(&mut query),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+
+        // el nombre de la tabla no puede salir del directorio de la base
+        for query in [
+            "TABLE ../clientes (id INT);",
+            "TABLE * (id INT);",
+            "TABLE ordenes (id INT REFERENCES ../clientes (id));",
+            "INDEX ON ../ordenes (id);",
+        ] {
+            assert!(matches!(
+                create_parser(&mut query.to_string()),
+                Err(ErrorType::InvalidTable(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_create_parser_foreign_keys() {
+        let mut query = String::from(
+            "TABLE ordenes (id INT, id_cliente INT REFERENCES clientes(id) ON DELETE SET NULL, \
+             codigo TEXT, FOREIGN KEY (codigo) REFERENCES productos (codigo) ON DELETE CASCADE);",
+        );
+        let Ok(Commands::CreateTable { schema, .. }) = create_parser(&mut query) else {
+            panic!("Expected a CreateTable command");
+        };
+
+        assert_eq!(
+            schema.foreign_key_constraints(),
+            vec![
+                ForeignKey {
+                    columns: vec!["id_cliente".to_string()],
+                    table: "clientes".to_string(),
+                    references: vec!["id".to_string()],
+                    on_delete: ReferentialAction::SetNull,
+                },
+                ForeignKey {
+                    columns: vec!["codigo".to_string()],
+                    table: "productos".to_string(),
+                    references: vec!["codigo".to_string()],
+                    on_delete: ReferentialAction::Cascade,
+                },
+            ]
+        );
+        // la definicion se guarda con la misma sintaxis
+        assert_eq!(
+            schema.columns[1].to_string(),
+            "id_cliente INTEGER REFERENCES clientes (id) ON DELETE SET NULL"
+        );
+        assert_eq!(
+            schema.foreign_keys[0].to_string(),
+            "FOREIGN KEY (codigo) REFERENCES productos (codigo) ON DELETE CASCADE"
+        );
+    }
+
+    #[test]
+    fn test_create_parser_keys() {
+        let mut query = String::from(
+            "TABLE ordenes (id INT, id_cliente INT, codigo TEXT UNIQUE, PRIMARY KEY (id, id_cliente));",
+        );
+        let Ok(Commands::CreateTable { schema, .. }) = create_parser(&mut query) else {
+            panic!("Expected a CreateTable command");
+        };
+
+        assert!(schema.columns[0].not_null && schema.columns[1].not_null);
+        assert!(schema.columns[2].unique);
+        assert_eq!(
+            schema.unique_constraints(),
+            vec![
+                UniqueConstraint {
+                    columns: vec!["codigo".to_string()],
+                    primary_key: false,
+                },
+                UniqueConstraint {
+                    columns: vec!["id".to_string(), "id_cliente".to_string()],
+                    primary_key: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_auto_increment_column() {
+        let column = parse_column_definition("id SERIAL PRIMARY KEY").unwrap();
+        assert_eq!(column.data_type, DataType::Integer);
+        assert!(column.not_null);
+        assert_eq!(column.auto_increment, Some(1));
+        assert_eq!(
+            column.to_string(),
+            "id INTEGER NOT NULL AUTO_INCREMENT 1 PRIMARY KEY"
+        );
+
+        // el esquema guarda el proximo valor
+        let column = parse_column_definition("id INTEGER AUTO_INCREMENT 112 UNIQUE").unwrap();
+        assert_eq!(column.auto_increment, Some(112));
+        assert!(column.unique);
+    }
+}
@@ -1,11 +1,13 @@
 pub use super::extract_query::{
-    expected_next_word, extract_and_parse_where_clause, extract_next_word, extract_table_names,
+    expected_next_word, extract_and_parse_where_clause, extract_next_word,
+    extract_returning_clause, extract_table_names,
 };
 use crate::command_types::Commands;
 use crate::condition::Condition;
 use crate::errors::ErrorType;
 
 pub fn delete_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    let returning = extract_returning_clause(query)?;
     expected_next_word(query, "FROM")?;
 
     let tables = extract_table_names(query)?;
@@ -25,12 +27,17 @@ pub fn delete_parser(query: &mut String) -> Result<Commands, ErrorType> {
         }
     };
 
-    Ok(Commands::Delete { tables, where_st })
+    Ok(Commands::Delete {
+        tables,
+        where_st,
+        returning,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parameters::Placeholder;
 
     #[test]
     fn test_delete_parser_with_where() {
@@ -38,7 +45,10 @@ mod tests {
         let result = delete_parser(&mut query);
         assert!(result.is_ok());
 
-        if let Commands::Delete { tables, where_st } = result.unwrap() {
+        if let Commands::Delete {
+            tables, where_st, ..
+        } = result.unwrap()
+        {
             assert_eq!(tables, ["users"]);
             assert!(where_st.is_some());
         } else {
@@ -52,7 +62,10 @@ mod tests {
         let result = delete_parser(&mut query);
         assert!(result.is_ok());
 
-        if let Commands::Delete { tables, where_st } = result.unwrap() {
+        if let Commands::Delete {
+            tables, where_st, ..
+        } = result.unwrap()
+        {
             assert_eq!(tables, ["users"]);
             assert!(where_st.is_none());
         } else {
@@ -60,6 +73,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_delete_parser_with_parameter() {
+        let mut query = String::from("FROM users WHERE id = $1 AND name = ':name' OR age > :age;");
+        let Ok(Commands::Delete {
+            where_st: Some(where_st),
+            ..
+        }) = delete_parser(&mut query)
+        else {
+            panic!("Expected a Delete command with where");
+        };
+        // solo el `?` sin comillas es un parametro
+        assert_eq!(
+            where_st.placeholders(),
+            [
+                &Placeholder::Numbered(1),
+                &Placeholder::Named("age".to_string())
+            ]
+        );
+        assert_eq!(
+            where_st.to_string(),
+            "id = $1 AND name = ':name' OR age > :age"
+        );
+    }
+
     #[test]
     fn test_delete_parser_missing_tables() {
         let mut query = String::from("FROM WHERE id = 1;");
@@ -73,4 +110,32 @@ mod tests {
         let result = delete_parser(&mut query);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_delete_parser_with_returning() {
+        let mut query = String::from("FROM ordenes WHERE cantidad = 0 RETURNING id, producto;");
+        let result = delete_parser(&mut query);
+
+        if let Ok(Commands::Delete {
+            where_st,
+            returning,
+            ..
+        }) = result
+        {
+            assert!(where_st.is_some());
+            assert_eq!(
+                returning,
+                Some(vec!["id".to_string(), "producto".to_string()])
+            );
+        } else {
+            panic!("Expected a Delete command");
+        }
+    }
+
+    #[test]
+    fn test_delete_parser_empty_returning() {
+        let mut query = String::from("FROM ordenes RETURNING;");
+        let result = delete_parser(&mut query);
+        assert!(result.is_err());
+    }
 }
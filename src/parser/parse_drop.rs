@@ -0,0 +1,62 @@
+use super::extract_query::{check_table_name, expected_next_word};
+use super::parse_create::extract_if_condition;
+use crate::command_types::Commands;
+use crate::errors::ErrorType;
+
+/// Parsea `DROP TABLE [IF EXISTS] tabla, ...`
+pub fn drop_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    expected_next_word(query, "TABLE")?;
+    let if_exists = extract_if_condition(query, &["EXISTS"])?;
+
+    let tables: Vec<String> = query
+        .trim()
+        .trim_end_matches(';')
+        .split(',')
+        .map(|table| table.trim().to_string())
+        .collect();
+    if let Some(table) = tables
+        .iter()
+        .find(|table| table.is_empty() || table.contains(char::is_whitespace))
+    {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected table name, found '{table}'"
+        )));
+    }
+    for table in &tables {
+        check_table_name(table)?;
+    }
+
+    Ok(Commands::DropTable { tables, if_exists })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_parser() {
+        let mut query = String::from("TABLE IF EXISTS clientes, ordenes;");
+        let result = drop_parser(&mut query);
+
+        if let Ok(Commands::DropTable { tables, if_exists }) = result {
+            assert_eq!(tables, ["clientes", "ordenes"]);
+            assert!(if_exists);
+        } else {
+            panic!("Expected a DropTable command");
+        }
+
+        let mut query = String::from("TABLE;");
+        assert!(drop_parser(&mut query).is_err());
+
+        let mut query = String::from("TABLE clientes ordenes;");
+        assert!(drop_parser(&mut query).is_err());
+
+        for table in ["../clientes", "*", "1tabla", "tablas/clientes"] {
+            let mut query = format!("TABLE {table};");
+            assert!(matches!(
+                drop_parser(&mut query),
+                Err(ErrorType::InvalidTable(_))
+            ));
+        }
+    }
+}
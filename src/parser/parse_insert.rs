@@ -1,10 +1,54 @@
 use super::extract_query::{
-    expected_next_word, extract_between_parenthesis, extract_table_names, get_next_char,
+    expected_next_word, extract_between_parenthesis, extract_next_word, extract_returning_clause,
+    extract_table_names, extract_values_between_parenthesis, find_keyword, get_next_char,
 };
-use crate::command_types::Commands;
+use super::parse_select::select_parser;
+use super::parse_update::extract_updates;
+use crate::command_types::{Commands, ConflictAction, InsertSource, OnConflict};
 use crate::errors::ErrorType;
 
+/// Extrae y parsea la clausula ON CONFLICT, que va despues de las filas a insertar:
+/// `ON CONFLICT (columnas) DO NOTHING` o `ON CONFLICT (columnas) DO UPDATE SET ...`
+fn extract_on_conflict_clause(query: &mut String) -> Result<Option<OnConflict>, ErrorType> {
+    let Some(start) = find_keyword(query, "ON CONFLICT") else {
+        return Ok(None);
+    };
+    let mut clause = query[start + "ON CONFLICT".len()..].to_string();
+    query.truncate(start);
+    query.push(';');
+
+    let columns = extract_between_parenthesis(&mut clause)?;
+    if columns.iter().any(String::is_empty) {
+        return Err(ErrorType::InvalidSyntax(
+            "Expected columns after ON CONFLICT".into(),
+        ));
+    }
+
+    expected_next_word(&mut clause, "DO")?;
+    let action = match extract_next_word(&mut clause) {
+        Some(word) if word == "NOTHING" => ConflictAction::Nothing,
+        Some(word) if word == "UPDATE" => {
+            expected_next_word(&mut clause, "SET")?;
+            ConflictAction::Update(extract_updates(&mut clause)?)
+        }
+        _ => {
+            return Err(ErrorType::InvalidSyntax(
+                "Expected NOTHING or UPDATE after DO".into(),
+            ))
+        }
+    };
+
+    match extract_next_word(&mut clause) {
+        Some(word) if word != ";" => Err(ErrorType::InvalidSyntax(format!(
+            "Unexpected '{word}' after ON CONFLICT clause"
+        ))),
+        _ => Ok(Some(OnConflict { columns, action })),
+    }
+}
+
 pub fn insert_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    let returning = extract_returning_clause(query)?;
+    let on_conflict = extract_on_conflict_clause(query)?;
     expected_next_word(query, "INTO")?;
 
     let tables = extract_table_names(query)?;
@@ -16,12 +60,40 @@ pub fn insert_parser(query: &mut String) -> Result<Commands, ErrorType> {
         Vec::new()
     };
 
-    expected_next_word(query, "VALUES")?;
+    // las filas pueden venir de un select
+    match extract_next_word(query) {
+        Some(word) if word == "VALUES" => {}
+        Some(word) if word == "SELECT" => {
+            let select = select_parser(query)?;
+            return Ok(Commands::Insert {
+                tables,
+                headers,
+                source: InsertSource::Query(Box::new(select)),
+                on_conflict,
+                returning,
+            });
+        }
+        Some(word) => {
+            return Err(ErrorType::InvalidSyntax(format!(
+                "Expected 'VALUES' or 'SELECT', found '{word}'"
+            )))
+        }
+        None => {
+            return Err(ErrorType::InvalidSyntax(
+                "Expected 'VALUES' or 'SELECT', found nothing".into(),
+            ))
+        }
+    }
 
-    let values = extract_between_parenthesis(query)?;
+    // extraigo las filas a insertar, separadas por ','
+    let mut values = vec![extract_values_between_parenthesis(query)?];
+    while let Some(',') = get_next_char(query) {
+        *query = query.trim_start()[1..].to_string();
+        values.push(extract_values_between_parenthesis(query)?);
+    }
 
     // Valida que el número de headers y values coincide si hay headers
-    if !headers.is_empty() && headers.len() != values.len() {
+    if !headers.is_empty() && values.iter().any(|row| headers.len() != row.len()) {
         return Err(ErrorType::InvalidColumn(
             "Number of headers and values must match".into(),
         ));
@@ -30,13 +102,17 @@ pub fn insert_parser(query: &mut String) -> Result<Commands, ErrorType> {
     Ok(Commands::Insert {
         tables,
         headers,
-        values,
+        source: InsertSource::Values(values),
+        on_conflict,
+        returning,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command_types::Value;
+    use crate::parameters::Placeholder;
 
     #[test]
     fn test_insert_parser_with_headers() {
@@ -47,12 +123,34 @@ mod tests {
         if let Commands::Insert {
             tables,
             headers,
-            values,
+            source: InsertSource::Values(values),
+            ..
         } = result.unwrap()
         {
             assert_eq!(tables, ["users"]);
             assert_eq!(headers, vec!["id", "name"]);
-            assert_eq!(values, vec!["1", "Juan"]);
+            assert_eq!(values, vec![vec!["1", "Juan"]]);
+        } else {
+            panic!("Expected an Insert command");
+        }
+    }
+
+    #[test]
+    fn test_insert_parser_with_parameters() {
+        let mut query = String::from("INTO users (id, name) VALUES ($1, :name), (2, '?');");
+        if let Ok(Commands::Insert {
+            source: InsertSource::Values(values),
+            ..
+        }) = insert_parser(&mut query)
+        {
+            assert_eq!(
+                values[0],
+                [
+                    Value::Parameter(Placeholder::Numbered(1)),
+                    Value::Parameter(Placeholder::Named("name".to_string()))
+                ]
+            );
+            assert_eq!(values[1], ["2", "?"]);
         } else {
             panic!("Expected an Insert command");
         }
@@ -67,12 +165,13 @@ mod tests {
         if let Commands::Insert {
             tables,
             headers,
-            values,
+            source: InsertSource::Values(values),
+            ..
         } = result.unwrap()
         {
             assert_eq!(tables, ["users"]);
             assert!(headers.is_empty());
-            assert_eq!(values, vec!["1", "Juan"]);
+            assert_eq!(values, vec![vec!["1", "Juan"]]);
         } else {
             panic!("Expected an Insert command");
         }
@@ -105,4 +204,129 @@ mod tests {
         let result = insert_parser(&mut query);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_insert_parser_multiple_rows() {
+        let mut query =
+            String::from("INTO users (id, name) VALUES (1, 'Juan'), (2, 'Maria') ,(3, 'Ana');");
+        let result = insert_parser(&mut query);
+
+        if let Ok(Commands::Insert {
+            source: InsertSource::Values(values),
+            ..
+        }) = result
+        {
+            assert_eq!(
+                values,
+                vec![vec!["1", "Juan"], vec!["2", "Maria"], vec!["3", "Ana"]]
+            );
+        } else {
+            panic!("Expected an Insert command");
+        }
+    }
+
+    #[test]
+    fn test_insert_parser_multiple_rows_with_wrong_size() {
+        let mut query = String::from("INTO users (id, name) VALUES (1, 'Juan'), (2);");
+        let result = insert_parser(&mut query);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_insert_parser_trailing_comma() {
+        let mut query = String::from("INTO users VALUES (1, 'Juan'),;");
+        let result = insert_parser(&mut query);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_insert_parser_with_select() {
+        let mut query =
+            String::from("INTO archivo SELECT * FROM ordenes WHERE id < 105 ORDER BY id;");
+        let result = insert_parser(&mut query);
+
+        if let Ok(Commands::Insert {
+            tables,
+            headers,
+            source: InsertSource::Query(select),
+            ..
+        }) = result
+        {
+            assert_eq!(tables, ["archivo"]);
+            assert!(headers.is_empty());
+            assert!(matches!(
+                *select,
+                Commands::Select {
+                    where_st: Some(_),
+                    order: Some(_),
+                    ..
+                }
+            ));
+        } else {
+            panic!("Expected an Insert command with a query");
+        }
+    }
+
+    #[test]
+    fn test_insert_parser_with_returning() {
+        let mut query = String::from("INTO users VALUES (1, 'RETURNING') RETURNING id, name;");
+        let result = insert_parser(&mut query);
+
+        if let Ok(Commands::Insert {
+            source: InsertSource::Values(values),
+            returning,
+            ..
+        }) = result
+        {
+            assert_eq!(values, vec![vec!["1", "RETURNING"]]);
+            assert_eq!(returning, Some(vec!["id".to_string(), "name".to_string()]));
+        } else {
+            panic!("Expected an Insert command");
+        }
+    }
+
+    #[test]
+    fn test_insert_parser_with_on_conflict() {
+        let mut query = String::from(
+            "INTO clientes (id, nombre) VALUES (1, 'Juan') ON CONFLICT (id) DO UPDATE SET nombre = EXCLUDED.nombre, email = 'EXCLUDED.email' RETURNING id;",
+        );
+        let result = insert_parser(&mut query);
+
+        if let Ok(Commands::Insert {
+            source: InsertSource::Values(values),
+            on_conflict: Some(on_conflict),
+            returning,
+            ..
+        }) = result
+        {
+            assert_eq!(values, vec![vec!["1", "Juan"]]);
+            assert_eq!(on_conflict.columns, ["id"]);
+            if let ConflictAction::Update(updates) = on_conflict.action {
+                assert_eq!(updates["nombre"], Value::Excluded("nombre".to_string()));
+                // entre comillas es un literal
+                assert_eq!(updates["email"], "EXCLUDED.email");
+            } else {
+                panic!("Expected an update action");
+            }
+            assert_eq!(returning, Some(vec!["id".to_string()]));
+        } else {
+            panic!("Expected an Insert command with ON CONFLICT");
+        }
+
+        let mut query =
+            String::from("INTO clientes VALUES (1, 'Juan') ON CONFLICT (id) DO NOTHING;");
+        assert!(matches!(
+            insert_parser(&mut query),
+            Ok(Commands::Insert {
+                on_conflict: Some(OnConflict {
+                    action: ConflictAction::Nothing,
+                    ..
+                }),
+                ..
+            })
+        ));
+
+        let mut query = String::from("INTO clientes VALUES (1, 'Juan') ON CONFLICT (id) DO;");
+        assert!(insert_parser(&mut query).is_err());
+    }
 }
@@ -1,26 +1,40 @@
 pub use super::extract_query::{
-    expected_next_word, extract_and_parse_order_clause, extract_and_parse_where_clause,
-    extract_next_word, extract_table_names,
+    expected_next_word, extract_and_parse_limit_clause, extract_and_parse_order_clause,
+    extract_and_parse_where_clause, extract_next_word, extract_table_names,
 };
-use crate::command_types::Commands;
+use super::parse_window::{is_window_function, parse_window_function, split_top_level_commas};
+use crate::command_types::{Commands, SetOperation, SetOperator};
 use crate::errors::ErrorType;
+use crate::window::WindowFunction;
 
-/// Extrae los headers del comando select y los retorna en un Vector de string
-pub fn extract_select_headers(s: &mut String) -> Result<Vec<String>, ErrorType> {
+/// Extrae los headers del comando select y los retorna en un Vector de string,
+/// las funciones de ventana se devuelven aparte y su header es su alias
+pub fn extract_select_headers(
+    s: &mut String,
+) -> Result<(Vec<String>, Vec<WindowFunction>), ErrorType> {
     let mut result = Vec::new();
+    let mut windows = Vec::new();
     let trimmed = s.trim_start();
 
     // obtengo lo que haya entre SELECT y FROM
     if let Some(from) = trimmed.find("FROM") {
         let aux = trimmed[..from].trim();
-        result.extend(aux.split(',').map(|x| x.trim().to_string()));
-        if result.is_empty() || result.first().map_or(true, String::is_empty) {
+        for item in split_top_level_commas(aux) {
+            if is_window_function(&item) {
+                let window = parse_window_function(&item, &result)?;
+                result.push(window.alias.clone());
+                windows.push(window);
+            } else {
+                result.push(item);
+            }
+        }
+        if result.is_empty() || result.first().is_none_or(String::is_empty) {
             return Err(ErrorType::InvalidSyntax(
                 "Expected headers or '*' after 'SELECT' command".into(),
             ));
         }
         *s = trimmed[from..].to_string();
-        Ok(result)
+        Ok((result, windows))
     } else {
         Err(ErrorType::InvalidSyntax(
             "Expected 'FROM' after headers".into(),
@@ -28,22 +42,138 @@ pub fn extract_select_headers(s: &mut String) -> Result<Vec<String>, ErrorType>
     }
 }
 
+/// Busca los operadores de conjuntos que no esten entre comillas ni parentesis.
+/// Devuelve la posicion donde empieza cada operador y donde termina
+fn find_set_operators(query: &str) -> Vec<(usize, usize, SetOperator)> {
+    let mut found = Vec::new();
+    let mut in_quotes = false;
+    let mut depth = 0;
+    let mut previous = ' ';
+
+    for (i, c) in query.char_indices() {
+        match c {
+            '\'' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth -= 1,
+            _ if !in_quotes && depth == 0 && !previous.is_alphanumeric() && previous != '_' => {
+                for (word, operator) in [
+                    ("UNION", SetOperator::Union),
+                    ("INTERSECT", SetOperator::Intersect),
+                    ("EXCEPT", SetOperator::Except),
+                ] {
+                    let end = i + word.len();
+                    if query[i..].starts_with(word)
+                        && query[end..].starts_with(|x: char| x.is_whitespace() || x == '(')
+                    {
+                        found.push((i, end, operator));
+                    }
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    found
+}
+
+/// Agrega el ';' final a una parte de la query si no lo tiene
+fn terminate_query(query: &str) -> String {
+    let mut query = query.trim().to_string();
+    if !query.ends_with(';') {
+        query.push(';');
+    }
+    query
+}
+
+/// Verifica que un select que no es el ultimo de la operacion no tenga ORDER BY ni LIMIT
+fn check_no_trailing_clauses(select: &Commands) -> Result<(), ErrorType> {
+    if let Commands::Select { order, limit, .. } = select {
+        if order.is_some() || limit.is_some() {
+            return Err(ErrorType::InvalidSyntax(
+                "ORDER BY and LIMIT are only allowed at the end of a set operation".into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parsea un select compuesto por operaciones de conjuntos.
+/// El ORDER BY y el LIMIT del ultimo select se aplican al resultado combinado
+fn compound_parser(
+    query: &str,
+    set_operators: &[(usize, usize, SetOperator)],
+) -> Result<Commands, ErrorType> {
+    let mut first_query = terminate_query(&query[..set_operators[0].0]);
+    let first = single_select_parser(&mut first_query)?;
+    check_no_trailing_clauses(&first)?;
+
+    let mut operations = Vec::new();
+    for (i, (_, operator_end, operator)) in set_operators.iter().enumerate() {
+        let select_end = set_operators.get(i + 1).map_or(query.len(), |next| next.0);
+        let mut select_query = terminate_query(&query[*operator_end..select_end]);
+
+        // busco si es la version ALL del operador
+        let all = select_query.starts_with("ALL ");
+        if all {
+            select_query = select_query[4..].to_string();
+        }
+
+        expected_next_word(&mut select_query, "SELECT")?;
+        let select = single_select_parser(&mut select_query)?;
+        if i + 1 < set_operators.len() {
+            check_no_trailing_clauses(&select)?;
+        }
+
+        operations.push((
+            SetOperation {
+                operator: *operator,
+                all,
+            },
+            select,
+        ));
+    }
+
+    // el orden y limite del ultimo select pasan a ser los de la operacion
+    let (order, limit) = match operations.last_mut() {
+        Some((_, Commands::Select { order, limit, .. })) => (order.take(), limit.take()),
+        _ => (None, None),
+    };
+
+    Ok(Commands::Compound {
+        first: Box::new(first),
+        operations,
+        order,
+        limit,
+    })
+}
+
 pub fn select_parser(query: &mut String) -> Result<Commands, ErrorType> {
-    let headers = extract_select_headers(query)?;
+    let set_operators = find_set_operators(query);
+    if set_operators.is_empty() {
+        single_select_parser(query)
+    } else {
+        compound_parser(query, &set_operators)
+    }
+}
+
+fn single_select_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    let (headers, windows) = extract_select_headers(query)?;
     expected_next_word(query, "FROM")?;
     let tables = extract_table_names(query)?;
 
     let mut where_st = None;
     let mut order = None;
+    let mut limit = None;
 
     while let Some(word) = extract_next_word(query) {
         match word.as_str() {
             "WHERE" => where_st = Some(extract_and_parse_where_clause(query)?),
             "ORDER" => order = Some(extract_and_parse_order_clause(query)?),
+            "LIMIT" => limit = Some(extract_and_parse_limit_clause(query)?),
             ";" => break,
             _ => {
                 return Err(ErrorType::InvalidSyntax(format!(
-                    "Expected 'WHERE', 'ORDER BY', or end of query. Found {word}"
+                    "Expected 'WHERE', 'ORDER BY', 'LIMIT', or end of query. Found {word}"
                 )))
             }
         }
@@ -54,12 +184,17 @@ pub fn select_parser(query: &mut String) -> Result<Commands, ErrorType> {
         tables,
         where_st,
         order,
+        limit,
+        windows,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::command_types::Value;
+    use crate::condition::Simplified;
+    use crate::parameters::Placeholder;
 
     #[test]
     fn test_select_parser_with_where_and_order() {
@@ -72,6 +207,7 @@ mod tests {
             tables,
             where_st,
             order,
+            ..
         } = result.unwrap()
         {
             assert_eq!(headers, vec!["name", "age"]);
@@ -83,6 +219,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_select_parser_where_between_constants() {
+        for (query, expected) in [
+            ("id FROM ordenes WHERE 'x' = 'x';", true),
+            ("id FROM ordenes WHERE 'x' = 'y';", false),
+            ("id FROM ordenes WHERE 1 < 2 AND 'b' > 'a';", true),
+        ] {
+            if let Ok(Commands::Select {
+                where_st: Some(condition),
+                ..
+            }) = select_parser(&mut query.to_string())
+            {
+                assert!(condition.columns().is_empty());
+                assert_eq!(condition.simplify(), Simplified::Constant(expected));
+            } else {
+                panic!("Expected a Select command with a where condition");
+            }
+        }
+    }
+
     #[test]
     fn test_select_parser_without_where_and_order() {
         let mut query = String::from("name, age FROM users;");
@@ -94,6 +250,7 @@ mod tests {
             tables,
             where_st,
             order,
+            ..
         } = result.unwrap()
         {
             assert_eq!(headers, vec!["name", "age"]);
@@ -139,4 +296,139 @@ mod tests {
         let result = select_parser(&mut query);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_select_parser_with_limit() {
+        let mut query = String::from("name FROM users ORDER BY name DESC LIMIT 2;");
+        let result = select_parser(&mut query);
+
+        if let Ok(Commands::Select { order, limit, .. }) = result {
+            assert_eq!(order.map(|o| o.len()), Some(1));
+            assert_eq!(limit, Some(Value::Literal(2)));
+        } else {
+            panic!("Expected a Select command");
+        }
+    }
+
+    #[test]
+    fn test_select_parser_limit_parameter() {
+        let mut query = String::from("name FROM users LIMIT :cantidad;");
+        if let Ok(Commands::Select { limit, .. }) = select_parser(&mut query) {
+            assert_eq!(
+                limit,
+                Some(Value::Parameter(Placeholder::Named("cantidad".to_string())))
+            );
+        } else {
+            panic!("Expected a Select command");
+        }
+    }
+
+    #[test]
+    fn test_select_parser_parameter_on_the_left() {
+        // el parametro va a la derecha de la comparacion
+        for query in [
+            "id FROM users WHERE ?1 = id;",
+            "id FROM users WHERE $1 = id;",
+            "id FROM users WHERE age > 3 AND :id = id;",
+        ] {
+            assert!(matches!(
+                select_parser(&mut query.to_string()),
+                Err(ErrorType::InvalidSyntax(_))
+            ));
+        }
+        let mut query = String::from("id FROM users WHERE '?1' = nombre;");
+        assert!(select_parser(&mut query).is_ok());
+    }
+
+    #[test]
+    fn test_select_parser_invalid_limit() {
+        let mut query = String::from("name FROM users LIMIT diez;");
+        let result = select_parser(&mut query);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_select_parser_union_with_order_and_limit() {
+        let mut query = String::from(
+            "id FROM users WHERE id > 1 UNION ALL SELECT id FROM admins INTERSECT SELECT id FROM staff ORDER BY id LIMIT 3;",
+        );
+        let result = select_parser(&mut query);
+
+        if let Ok(Commands::Compound {
+            first,
+            operations,
+            order,
+            limit,
+        }) = result
+        {
+            assert!(matches!(
+                *first,
+                Commands::Select {
+                    where_st: Some(_),
+                    ..
+                }
+            ));
+            assert_eq!(operations.len(), 2);
+            assert_eq!(operations[0].0.operator, SetOperator::Union);
+            assert!(operations[0].0.all);
+            assert_eq!(operations[1].0.operator, SetOperator::Intersect);
+            assert!(!operations[1].0.all);
+            assert!(matches!(
+                operations[1].1,
+                Commands::Select {
+                    order: None,
+                    limit: None,
+                    ..
+                }
+            ));
+            assert!(order.is_some());
+            assert_eq!(limit, Some(Value::Literal(3)));
+        } else {
+            panic!("Expected a Compound command");
+        }
+    }
+
+    #[test]
+    fn test_select_parser_union_ignores_quoted_keywords() {
+        let mut query = String::from("id FROM users WHERE name = 'UNION SELECT';");
+        let result = select_parser(&mut query);
+        assert!(matches!(result, Ok(Commands::Select { .. })));
+    }
+
+    #[test]
+    fn test_select_parser_order_before_union() {
+        let mut query = String::from("id FROM users ORDER BY id EXCEPT SELECT id FROM admins;");
+        let result = select_parser(&mut query);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_select_parser_with_window_function() {
+        let mut query = String::from(
+            "id, RANK() OVER (PARTITION BY id_cliente ORDER BY cantidad DESC) AS puesto FROM ordenes;",
+        );
+        let result = select_parser(&mut query);
+
+        if let Ok(Commands::Select {
+            headers, windows, ..
+        }) = result
+        {
+            assert_eq!(headers, ["id", "puesto"]);
+            assert_eq!(windows.len(), 1);
+            assert_eq!(windows[0].partition_by, ["id_cliente"]);
+        } else {
+            panic!("Expected a Select command");
+        }
+    }
+
+    #[test]
+    fn test_select_parser_window_functions_without_alias() {
+        let mut query = String::from(
+            "id, LAG(cantidad) OVER (ORDER BY id), LAG(id) OVER (ORDER BY id) FROM ordenes;",
+        );
+        let Ok(Commands::Select { headers, .. }) = select_parser(&mut query) else {
+            panic!("Expected a Select command");
+        };
+        assert_eq!(headers, ["id", "lag", "lag_2"]);
+    }
 }
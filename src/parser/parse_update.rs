@@ -1,14 +1,14 @@
 use super::extract_query::{
-    expected_next_word, extract_and_parse_where_clause, extract_next_word, extract_table_names,
-    strip_single_quotes,
+    expected_next_word, extract_and_parse_where_clause, extract_next_word,
+    extract_returning_clause, extract_table_names, parse_value,
 };
-use crate::command_types::Commands;
+use crate::command_types::{Commands, Value};
 use crate::condition::Condition;
 use crate::errors::ErrorType;
 use std::collections::HashMap;
 
 /// Extrae los campos y valores a actualizar y los retorna en un hashmap
-fn extract_updates(s: &mut String) -> Result<HashMap<String, String>, ErrorType> {
+pub fn extract_updates(s: &mut String) -> Result<HashMap<String, Value>, ErrorType> {
     let mut result = HashMap::new();
     let trimmed = s.trim_start();
 
@@ -22,10 +22,8 @@ fn extract_updates(s: &mut String) -> Result<HashMap<String, String>, ErrorType>
         let update = update.trim();
         if let Some(equal) = update.find('=') {
             let key = update[..equal].trim().to_string();
-            let value = update[equal + 1..].trim().to_string();
-
-            //por si el value esta entre ' '
-            let value = strip_single_quotes(&value);
+            // puede ser un parametro, o un literal entre ' '
+            let value = parse_value(&update[equal + 1..]);
 
             if key.is_empty() {
                 return Err(ErrorType::InvalidSyntax(
@@ -46,6 +44,7 @@ fn extract_updates(s: &mut String) -> Result<HashMap<String, String>, ErrorType>
 }
 
 pub fn update_parser(query: &mut String) -> Result<Commands, ErrorType> {
+    let returning = extract_returning_clause(query)?;
     let tables = extract_table_names(query)?;
     expected_next_word(query, "SET")?;
 
@@ -71,12 +70,14 @@ pub fn update_parser(query: &mut String) -> Result<Commands, ErrorType> {
         tables,
         updates,
         where_st,
+        returning,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parameters::Placeholder;
 
     #[test]
     fn test_update_parser_with_where() {
@@ -88,16 +89,36 @@ mod tests {
             tables,
             updates,
             where_st,
+            ..
         } = result.unwrap()
         {
             assert_eq!(tables, ["users"]);
-            assert_eq!(updates.get("name"), Some(&"Juan".to_string()));
+            assert_eq!(updates["name"], "Juan");
             assert!(where_st.is_some());
         } else {
             panic!("Expected an Update command");
         }
     }
 
+    #[test]
+    fn test_update_parser_with_parameters() {
+        let mut query = String::from("users SET name = ?2, email = '$1' WHERE id = ?1;");
+        if let Ok(Commands::Update {
+            updates, where_st, ..
+        }) = update_parser(&mut query)
+        {
+            assert_eq!(
+                updates["name"],
+                Value::Parameter(Placeholder::Positional(2))
+            );
+            // entre comillas es un literal
+            assert_eq!(updates["email"], "$1");
+            assert_eq!(where_st.unwrap().to_string(), "id = ?1");
+        } else {
+            panic!("Expected an Update command");
+        }
+    }
+
     #[test]
     fn test_update_parser_without_where() {
         let mut query = String::from("users SET name = 'Juan';");
@@ -108,10 +129,11 @@ mod tests {
             tables,
             updates,
             where_st,
+            ..
         } = result.unwrap()
         {
             assert_eq!(tables, ["users"]);
-            assert_eq!(updates.get("name"), Some(&"Juan".to_string()));
+            assert_eq!(updates["name"], "Juan");
             assert!(where_st.is_none());
         } else {
             panic!("Expected an Update command");
@@ -157,11 +179,30 @@ mod tests {
             tables,
             updates,
             where_st,
+            ..
         } = result.unwrap()
         {
             assert_eq!(tables, ["users"]);
-            assert_eq!(updates.get("name"), Some(&"".to_string()));
+            assert_eq!(updates["name"], "");
+            assert!(where_st.is_some());
+        } else {
+            panic!("Expected an Update command");
+        }
+    }
+
+    #[test]
+    fn test_update_parser_with_returning() {
+        let mut query = String::from("users SET name = 'Juan' WHERE id = 1 RETURNING *;");
+        let result = update_parser(&mut query);
+
+        if let Ok(Commands::Update {
+            where_st,
+            returning,
+            ..
+        }) = result
+        {
             assert!(where_st.is_some());
+            assert_eq!(returning, Some(vec!["*".to_string()]));
         } else {
             panic!("Expected an Update command");
         }
@@ -0,0 +1,301 @@
+use super::extract_query::{extract_and_parse_order_clause, strip_single_quotes};
+use crate::errors::ErrorType;
+use crate::order::OrderBy;
+use crate::window::{AggregateFunction, WindowFunction, WindowFunctionKind};
+
+/// Separa el string por comas que no esten dentro de parentesis ni comillas
+pub fn split_top_level_commas(s: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut in_quotes = false;
+
+    for c in s.chars() {
+        match c {
+            '\'' => in_quotes = !in_quotes,
+            '(' if !in_quotes => depth += 1,
+            ')' if !in_quotes => depth -= 1,
+            ',' if !in_quotes && depth == 0 => {
+                result.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    result.push(current.trim().to_string());
+    result
+}
+
+/// Devuelve la posicion del parentesis que cierra al que abre en `open`
+pub fn find_closing_parenthesis(s: &str, open: usize) -> Result<usize, ErrorType> {
+    let mut depth = 0;
+    for (i, c) in s[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(ErrorType::InvalidSyntax(
+        "No closing parenthesis found".into(),
+    ))
+}
+
+/// Indica si un elemento del select es una funcion de ventana
+pub fn is_window_function(item: &str) -> bool {
+    item.contains(" OVER ") || item.contains(" OVER(") || item.contains(")OVER")
+}
+
+/// Parsea los argumentos de LAG y LEAD: (columna [, desplazamiento [, default]])
+fn parse_offset_arguments(
+    name: &str,
+    arguments: &[String],
+) -> Result<(String, usize, String), ErrorType> {
+    let column = arguments
+        .first()
+        .filter(|column| !column.is_empty())
+        .ok_or(ErrorType::InvalidSyntax(format!(
+            "{name} expects a column as first argument"
+        )))?;
+
+    let offset = match arguments.get(1) {
+        Some(offset) => offset.parse::<usize>().map_err(|_| {
+            ErrorType::InvalidSyntax(format!("Invalid offset '{offset}' in {name}"))
+        })?,
+        None => 1,
+    };
+
+    let default = arguments
+        .get(2)
+        .map(|default| strip_single_quotes(default))
+        .unwrap_or_default();
+
+    Ok((column.to_string(), offset, default))
+}
+
+/// Parsea la funcion y sus argumentos
+fn parse_function(name: &str, arguments: &[String]) -> Result<WindowFunctionKind, ErrorType> {
+    let no_arguments = arguments.len() == 1 && arguments[0].is_empty();
+    let aggregate = |function| match arguments {
+        [column] if column == "*" && matches!(function, AggregateFunction::Count) => {
+            Ok(WindowFunctionKind::Aggregate(function, None))
+        }
+        [column] if !column.is_empty() && column != "*" => Ok(WindowFunctionKind::Aggregate(
+            function,
+            Some(column.to_string()),
+        )),
+        _ => Err(ErrorType::InvalidSyntax(format!(
+            "{name} expects a single column as argument"
+        ))),
+    };
+
+    match name {
+        "ROW_NUMBER" | "RANK" | "DENSE_RANK" if !no_arguments => Err(ErrorType::InvalidSyntax(
+            format!("{name} doesn't take arguments"),
+        )),
+        "ROW_NUMBER" => Ok(WindowFunctionKind::RowNumber),
+        "RANK" => Ok(WindowFunctionKind::Rank),
+        "DENSE_RANK" => Ok(WindowFunctionKind::DenseRank),
+        "LAG" => {
+            let (column, offset, default) = parse_offset_arguments(name, arguments)?;
+            Ok(WindowFunctionKind::Lag {
+                column,
+                offset,
+                default,
+            })
+        }
+        "LEAD" => {
+            let (column, offset, default) = parse_offset_arguments(name, arguments)?;
+            Ok(WindowFunctionKind::Lead {
+                column,
+                offset,
+                default,
+            })
+        }
+        "SUM" => aggregate(AggregateFunction::Sum),
+        "COUNT" => aggregate(AggregateFunction::Count),
+        "AVG" => aggregate(AggregateFunction::Avg),
+        "MIN" => aggregate(AggregateFunction::Min),
+        "MAX" => aggregate(AggregateFunction::Max),
+        _ => Err(ErrorType::InvalidSyntax(format!(
+            "'{name}' is not a window function"
+        ))),
+    }
+}
+
+/// Parsea el contenido del OVER: [PARTITION BY columnas] [ORDER BY columnas]
+fn parse_window_spec(spec: &str) -> Result<(Vec<String>, Vec<OrderBy>), ErrorType> {
+    let spec = spec.trim();
+    let order_start = spec.find("ORDER").unwrap_or(spec.len());
+
+    let partition = spec[..order_start].trim();
+    let partition_by = if partition.is_empty() {
+        Vec::new()
+    } else if let Some(columns) = partition.strip_prefix("PARTITION BY") {
+        let columns: Vec<String> = columns.split(',').map(|x| x.trim().to_string()).collect();
+        if columns.iter().any(String::is_empty) {
+            return Err(ErrorType::InvalidSyntax(
+                "Expected columns after PARTITION BY".into(),
+            ));
+        }
+        columns
+    } else {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected PARTITION BY or ORDER BY in window, found '{partition}'"
+        )));
+    };
+
+    let order_by = if order_start < spec.len() {
+        let mut order = format!("{};", &spec[order_start + "ORDER".len()..]);
+        extract_and_parse_order_clause(&mut order)?
+    } else {
+        Vec::new()
+    };
+
+    Ok((partition_by, order_by))
+}
+
+/// Devuelve el nombre de la funcion en minusculas, con un sufijo numerico si ya hay una
+/// columna con ese nombre
+fn default_alias(name: &str, used: &[String]) -> String {
+    let name = name.to_lowercase();
+    (1..)
+        .map(|n| {
+            if n == 1 {
+                name.clone()
+            } else {
+                format!("{name}_{n}")
+            }
+        })
+        .find(|alias| !used.contains(alias))
+        .unwrap_or(name)
+}
+
+/// Parsea una funcion de ventana del select: `funcion(args) OVER (...) [AS alias]`.
+/// `used` son los nombres de las columnas anteriores del select
+pub fn parse_window_function(item: &str, used: &[String]) -> Result<WindowFunction, ErrorType> {
+    let open = item.find('(').ok_or(ErrorType::InvalidSyntax(format!(
+        "Expected '(' after window function in '{item}'"
+    )))?;
+    let name = item[..open].trim().to_uppercase();
+    let close = find_closing_parenthesis(item, open)?;
+    let arguments: Vec<String> = item[open + 1..close]
+        .split(',')
+        .map(|x| x.trim().to_string())
+        .collect();
+    let function = parse_function(&name, &arguments)?;
+
+    let rest = item[close + 1..].trim_start();
+    let rest = rest
+        .strip_prefix("OVER")
+        .ok_or(ErrorType::InvalidSyntax(format!(
+            "Expected OVER after {name}"
+        )))?
+        .trim_start();
+    if !rest.starts_with('(') {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Expected '(' after OVER in {name}"
+        )));
+    }
+    let spec_close = find_closing_parenthesis(rest, 0)?;
+    let (partition_by, order_by) = parse_window_spec(&rest[1..spec_close])?;
+
+    // el alias es opcional, por defecto se usa el nombre de la funcion
+    let alias_part = rest[spec_close + 1..].trim();
+    let alias_part = alias_part.strip_prefix("AS ").unwrap_or(alias_part).trim();
+    let alias = if alias_part.is_empty() {
+        default_alias(&name, used)
+    } else if alias_part.contains(char::is_whitespace) {
+        return Err(ErrorType::InvalidSyntax(format!(
+            "Unexpected '{alias_part}' after window function"
+        )));
+    } else {
+        alias_part.to_string()
+    };
+
+    Ok(WindowFunction {
+        function,
+        partition_by,
+        order_by,
+        alias,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::Direction;
+
+    #[test]
+    fn test_split_top_level_commas() {
+        let items = split_top_level_commas("id, LAG(cantidad, 1) OVER (PARTITION BY a, b) AS x");
+        assert_eq!(
+            items,
+            ["id", "LAG(cantidad, 1) OVER (PARTITION BY a, b) AS x"]
+        );
+    }
+
+    #[test]
+    fn test_parse_row_number_with_partition_and_order() {
+        let window = parse_window_function(
+            "ROW_NUMBER() OVER (PARTITION BY id_cliente ORDER BY cantidad DESC, id) AS rn",
+            &[],
+        )
+        .unwrap();
+
+        assert!(matches!(window.function, WindowFunctionKind::RowNumber));
+        assert_eq!(window.partition_by, ["id_cliente"]);
+        assert_eq!(window.order_by.len(), 2);
+        assert!(matches!(
+            window.order_by[0].direction,
+            Direction::Descending
+        ));
+        assert_eq!(window.order_by[1].column, "id");
+        assert_eq!(window.alias, "rn");
+    }
+
+    #[test]
+    fn test_parse_lag_with_default_alias() {
+        let window = parse_window_function("LAG(cantidad, 2, 0) OVER (ORDER BY id)", &[]).unwrap();
+
+        if let WindowFunctionKind::Lag {
+            column,
+            offset,
+            default,
+        } = window.function
+        {
+            assert_eq!(column, "cantidad");
+            assert_eq!(offset, 2);
+            assert_eq!(default, "0");
+        } else {
+            panic!("Expected a LAG function");
+        }
+        assert!(window.partition_by.is_empty());
+        assert_eq!(window.alias, "lag");
+    }
+
+    #[test]
+    fn test_default_alias_is_unique() {
+        let used = ["id".to_string(), "lag".to_string(), "lag_2".to_string()];
+        let window = parse_window_function("LAG(cantidad) OVER (ORDER BY id)", &used).unwrap();
+        assert_eq!(window.alias, "lag_3");
+        // un alias explicito se respeta
+        let window = parse_window_function("LAG(cantidad) OVER () AS lag", &used).unwrap();
+        assert_eq!(window.alias, "lag");
+    }
+
+    #[test]
+    fn test_parse_window_errors() {
+        assert!(parse_window_function("ROW_NUMBER(id) OVER ()", &[]).is_err());
+        assert!(parse_window_function("SUM(cantidad) OVER (GROUP BY id)", &[]).is_err());
+        assert!(parse_window_function("MEDIAN(cantidad) OVER ()", &[]).is_err());
+        assert!(parse_window_function("SUM(cantidad) OVER (ORDER BY id", &[]).is_err());
+    }
+}
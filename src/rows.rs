@@ -0,0 +1,212 @@
+use crate::errors::ErrorType;
+use crate::schema::{parse_boolean, DataType};
+use std::{fmt, sync::Arc};
+
+/// Columna del resultado de una consulta, con el tipo declarado en el esquema si lo tiene
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub data_type: Option<DataType>,
+}
+
+/// Conversion del valor de una columna a un tipo de Rust. El valor vacio representa NULL
+/// y solo se puede leer como `Option` o como texto
+pub trait FromValue: Sized {
+    fn from_value(value: &str) -> Option<Self>;
+}
+
+impl FromValue for String {
+    fn from_value(value: &str) -> Option<Self> {
+        Some(value.to_string())
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &str) -> Option<Self> {
+        parse_boolean(value)
+    }
+}
+
+macro_rules! impl_from_value {
+    ($($ty:ty),*) => {
+        $(impl FromValue for $ty {
+            fn from_value(value: &str) -> Option<Self> {
+                value.parse().ok()
+            }
+        })*
+    };
+}
+
+impl_from_value!(i32, i64, u32, u64, usize, f32, f64);
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &str) -> Option<Self> {
+        if value.is_empty() {
+            return Some(None);
+        }
+        T::from_value(value).map(Some)
+    }
+}
+
+/// Forma de indicar una columna de la fila: por su posicion o por su nombre
+pub trait ColumnIndex: fmt::Display {
+    fn position(&self, columns: &[Column]) -> Option<usize>;
+}
+
+impl ColumnIndex for usize {
+    fn position(&self, columns: &[Column]) -> Option<usize> {
+        (*self < columns.len()).then_some(*self)
+    }
+}
+
+impl ColumnIndex for &str {
+    fn position(&self, columns: &[Column]) -> Option<usize> {
+        columns.iter().position(|column| column.name == *self)
+    }
+}
+
+impl ColumnIndex for &String {
+    fn position(&self, columns: &[Column]) -> Option<usize> {
+        self.as_str().position(columns)
+    }
+}
+
+/// Fila del resultado de una consulta, comparte las columnas con el resto de las filas
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    columns: Arc<[Column]>,
+    values: Vec<String>,
+}
+
+impl Row {
+    #[must_use]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Valores de la fila como texto, en el orden de las columnas
+    #[must_use]
+    pub fn values(&self) -> &[String] {
+        &self.values
+    }
+
+    /// Devuelve el valor de la columna convertido al tipo pedido
+    #[allow(clippy::missing_errors_doc)]
+    pub fn get<T: FromValue>(&self, index: impl ColumnIndex) -> Result<T, ErrorType> {
+        let position = index
+            .position(&self.columns)
+            .ok_or(ErrorType::InvalidColumn(format!(
+                "Column '{index}' not found in the result"
+            )))?;
+        let value = self.values.get(position).map_or("", String::as_str);
+        T::from_value(value).ok_or(ErrorType::InvalidColumn(format!(
+            "Invalid value '{value}' for column '{}' of type {}",
+            self.columns[position].name,
+            std::any::type_name::<T>()
+        )))
+    }
+}
+
+/// Resultado de una consulta: las columnas y las filas, que se recorren como un iterador.
+/// Cada fila se produce recien al pedirla, por lo que leerla puede fallar
+pub struct Rows {
+    columns: Arc<[Column]>,
+    rows: Box<dyn Iterator<Item = Result<Vec<String>, ErrorType>>>,
+}
+
+impl Rows {
+    #[must_use]
+    pub fn new(
+        columns: Vec<Column>,
+        rows: impl Iterator<Item = Result<Vec<String>, ErrorType>> + 'static,
+    ) -> Self {
+        Rows {
+            columns: columns.into(),
+            rows: Box::new(rows),
+        }
+    }
+
+    #[must_use]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+}
+
+impl fmt::Debug for Rows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rows")
+            .field("columns", &self.columns)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for Rows {
+    type Item = Result<Row, ErrorType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let values = self.rows.next()?;
+        Some(values.map(|values| Row {
+            columns: Arc::clone(&self.columns),
+            values,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_get_typed_values() {
+        let columns = vec![
+            Column {
+                name: "id".to_string(),
+                data_type: Some(DataType::Integer),
+            },
+            Column {
+                name: "activo".to_string(),
+                data_type: Some(DataType::Boolean),
+            },
+            Column {
+                name: "precio".to_string(),
+                data_type: None,
+            },
+        ];
+        let mut rows = Rows::new(
+            columns,
+            vec![Ok(vec!["1".to_string(), "TRUE".to_string(), String::new()])].into_iter(),
+        );
+        assert_eq!(rows.size_hint(), (1, Some(1)));
+        assert_eq!(rows.columns()[1].name, "activo");
+
+        let row = rows.next().unwrap().unwrap();
+        assert_eq!(row.get::<i64>("id").unwrap(), 1);
+        assert_eq!(row.get::<String>(0).unwrap(), "1");
+        assert!(row.get::<bool>("activo").unwrap());
+        // NULL se lee como None
+        assert_eq!(row.get::<Option<f64>>(2).unwrap(), None);
+        assert_eq!(row.get::<Option<i32>>("id").unwrap(), Some(1));
+
+        assert!(matches!(
+            row.get::<f64>(2),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            row.get::<i64>("activo"),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            row.get::<i64>("otra"),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(matches!(
+            row.get::<i64>(3),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(rows.next().is_none());
+    }
+}
@@ -0,0 +1,656 @@
+use crate::condition::Condition;
+use crate::errors::ErrorType;
+use crate::parser::{parse_column_definition, parse_table_constraint};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+    str::FromStr,
+};
+
+/// Tipos de datos que se pueden declarar en las columnas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    Integer,
+    Decimal,
+    Text,
+    Boolean,
+    Date,
+}
+
+impl FromStr for DataType {
+    type Err = ErrorType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "INTEGER" | "INT" => Ok(DataType::Integer),
+            "DECIMAL" => Ok(DataType::Decimal),
+            "TEXT" => Ok(DataType::Text),
+            "BOOLEAN" | "BOOL" => Ok(DataType::Boolean),
+            "DATE" => Ok(DataType::Date),
+            _ => Err(ErrorType::InvalidSyntax(format!("Unknown data type '{s}'"))),
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DataType::Integer => "INTEGER",
+            DataType::Decimal => "DECIMAL",
+            DataType::Text => "TEXT",
+            DataType::Boolean => "BOOLEAN",
+            DataType::Date => "DATE",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Interpreta un booleano sin importar mayusculas
+#[must_use]
+pub fn parse_boolean(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Verifica que la fecha tenga el formato YYYY-MM-DD y sea una fecha existente
+fn is_valid_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return false;
+    };
+
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
+impl DataType {
+    /// Indica si el valor es valido para el tipo. El valor vacio representa NULL
+    #[must_use]
+    pub fn is_valid(self, value: &str) -> bool {
+        value.is_empty()
+            || match self {
+                DataType::Integer => value.parse::<i64>().is_ok(),
+                DataType::Decimal => value.parse::<f64>().is_ok_and(f64::is_finite),
+                DataType::Text => true,
+                DataType::Boolean => parse_boolean(value).is_some(),
+                DataType::Date => is_valid_date(value),
+            }
+    }
+
+    /// Escribe el valor de una unica forma, asi dos valores iguales para el tipo tambien son
+    /// iguales como texto: `01` queda `1` y `true` queda `TRUE`. Un valor invalido queda igual
+    #[must_use]
+    pub fn normalize(self, value: &str) -> String {
+        let normalized = match self {
+            DataType::Integer => value.parse::<i64>().ok().map(|n| n.to_string()),
+            DataType::Decimal => value
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map(|n| n.to_string()),
+            DataType::Boolean => {
+                parse_boolean(value).map(|b| if b { "TRUE" } else { "FALSE" }.to_string())
+            }
+            DataType::Text | DataType::Date => None,
+        };
+        normalized.unwrap_or_else(|| value.to_string())
+    }
+
+    /// Compara dos valores segun el tipo. Si alguno no se puede interpretar se comparan como texto
+    #[must_use]
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        let typed = match self {
+            DataType::Integer => match (a.parse::<i64>(), b.parse::<i64>()) {
+                (Ok(a), Ok(b)) => Some(a.cmp(&b)),
+                _ => None,
+            },
+            DataType::Decimal => match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(a), Ok(b)) => a.partial_cmp(&b),
+                _ => None,
+            },
+            DataType::Boolean => match (parse_boolean(a), parse_boolean(b)) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => None,
+            },
+            // las fechas en formato YYYY-MM-DD se ordenan igual que el texto
+            DataType::Text | DataType::Date => None,
+        };
+        typed.unwrap_or_else(|| a.cmp(b))
+    }
+}
+
+/// Restriccion CHECK: la condicion junto al texto con el que se declaro
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckConstraint {
+    pub expression: String,
+    pub condition: Condition,
+}
+
+/// Definicion de una columna en un CREATE TABLE
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub data_type: DataType,
+    pub not_null: bool,
+    pub default: Option<String>,
+    pub check: Option<CheckConstraint>,
+    pub primary_key: bool,
+    pub unique: bool,
+    /// clave foranea declarada en la columna con `REFERENCES`
+    pub references: Option<ForeignKey>,
+    /// proximo valor de una columna AUTO_INCREMENT, None si no es autoincremental
+    pub auto_increment: Option<i64>,
+}
+
+impl ColumnDefinition {
+    /// Crea una columna del tipo dado sin restricciones
+    #[must_use]
+    pub fn new(name: &str, data_type: DataType) -> Self {
+        Self {
+            name: name.to_string(),
+            data_type,
+            not_null: false,
+            default: None,
+            check: None,
+            primary_key: false,
+            unique: false,
+            references: None,
+            auto_increment: None,
+        }
+    }
+
+    /// Devuelve el proximo valor de la columna autoincremental y lo avanza
+    pub fn next_value(&mut self) -> Option<String> {
+        let next = self.auto_increment.as_mut()?;
+        let value = *next;
+        *next += 1;
+        Some(value.to_string())
+    }
+
+    /// Avanza el proximo valor para que no repita uno insertado explicitamente
+    pub fn skip_value(&mut self, value: &str) {
+        if let (Some(next), Ok(value)) = (self.auto_increment.as_mut(), value.parse::<i64>()) {
+            *next = (*next).max(value + 1);
+        }
+    }
+}
+
+/// Escribe la definicion con la misma sintaxis del CREATE TABLE
+impl fmt::Display for ColumnDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.data_type)?;
+        if self.not_null {
+            write!(f, " NOT NULL")?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " DEFAULT '{default}'")?;
+        }
+        if let Some(check) = &self.check {
+            write!(f, " CHECK ({})", check.expression)?;
+        }
+        if let Some(next) = self.auto_increment {
+            write!(f, " AUTO_INCREMENT {next}")?;
+        }
+        if self.primary_key {
+            write!(f, " PRIMARY KEY")?;
+        }
+        if self.unique {
+            write!(f, " UNIQUE")?;
+        }
+        if let Some(references) = &self.references {
+            write!(
+                f,
+                " REFERENCES {} ({}) ON DELETE {}",
+                references.table,
+                references.references.join(", "),
+                references.on_delete
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Restriccion PRIMARY KEY o UNIQUE sobre un conjunto de columnas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniqueConstraint {
+    pub columns: Vec<String>,
+    pub primary_key: bool,
+}
+
+impl UniqueConstraint {
+    /// Nombre de la restriccion para los mensajes de error
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        if self.primary_key {
+            "PRIMARY KEY"
+        } else {
+            "UNIQUE"
+        }
+    }
+}
+
+/// Escribe la restriccion con la misma sintaxis del CREATE TABLE
+impl fmt::Display for UniqueConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.kind(), self.columns.join(", "))
+    }
+}
+
+/// Accion al borrar una fila referenciada por una clave foranea
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReferentialAction {
+    #[default]
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+impl fmt::Display for ReferentialAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReferentialAction::Restrict => "RESTRICT",
+            ReferentialAction::Cascade => "CASCADE",
+            ReferentialAction::SetNull => "SET NULL",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Restriccion FOREIGN KEY: las columnas de la tabla referencian columnas de otra tabla
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub table: String,
+    pub references: Vec<String>,
+    pub on_delete: ReferentialAction,
+}
+
+/// Escribe la restriccion con la misma sintaxis del CREATE TABLE
+impl fmt::Display for ForeignKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {}",
+            self.columns.join(", "),
+            self.table,
+            self.references.join(", "),
+            self.on_delete
+        )
+    }
+}
+
+/// Restriccion declarada aparte de las columnas en un CREATE TABLE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableConstraint {
+    Unique(UniqueConstraint),
+    ForeignKey(ForeignKey),
+}
+
+/// Esquema de una tabla, guardado junto al csv en `{tabla}.schema` con una columna por linea
+/// seguida de las restricciones de la tabla
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableSchema {
+    pub columns: Vec<ColumnDefinition>,
+    /// restricciones PRIMARY KEY y UNIQUE declaradas aparte de las columnas
+    pub keys: Vec<UniqueConstraint>,
+    /// claves foraneas declaradas aparte de las columnas
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+impl TableSchema {
+    /// Crea un esquema con las columnas dadas y sin restricciones de tabla
+    #[must_use]
+    pub fn new(columns: Vec<ColumnDefinition>) -> Self {
+        Self {
+            columns,
+            keys: Vec::new(),
+            foreign_keys: Vec::new(),
+        }
+    }
+
+    /// Devuelve la ruta del archivo de esquema de la tabla
+    #[must_use]
+    pub fn path(table: &str, db_path: &str) -> String {
+        format!("{db_path}/{table}.schema")
+    }
+
+    /// Lee el esquema de la tabla, las tablas creadas a mano no tienen esquema
+    #[allow(clippy::missing_errors_doc)]
+    pub fn load(table: &str, db_path: &str) -> Result<Option<Self>, ErrorType> {
+        let schema_path = Self::path(table, db_path);
+        if !Path::new(&schema_path).exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&schema_path)
+            .map_err(|e| ErrorType::InvalidTable(format!("reading schema {e} failed")))?;
+        let mut schema = Self::new(Vec::new());
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            match parse_table_constraint(line)? {
+                Some(TableConstraint::Unique(key)) => schema.keys.push(key),
+                Some(TableConstraint::ForeignKey(key)) => schema.foreign_keys.push(key),
+                None => schema.columns.push(parse_column_definition(line)?),
+            }
+        }
+
+        Ok(Some(schema))
+    }
+
+    /// Guarda el esquema de la tabla
+    #[allow(clippy::missing_errors_doc)]
+    pub fn save(&self, table: &str, db_path: &str) -> Result<(), ErrorType> {
+        let mut content: String = self
+            .columns
+            .iter()
+            .map(|column| format!("{column}\n"))
+            .collect();
+        for key in &self.keys {
+            content.push_str(&format!("{key}\n"));
+        }
+        for key in &self.foreign_keys {
+            content.push_str(&format!("{key}\n"));
+        }
+        fs::write(Self::path(table, db_path), content)
+            .map_err(|e| ErrorType::OtherError(format!("writing schema {e} failed")))
+    }
+
+    /// Devuelve la definicion de la columna
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<&ColumnDefinition> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// Devuelve la definicion de la columna para modificarla
+    pub fn column_mut(&mut self, name: &str) -> Option<&mut ColumnDefinition> {
+        self.columns.iter_mut().find(|column| column.name == name)
+    }
+
+    /// Indica si alguna columna es autoincremental
+    #[must_use]
+    pub fn has_auto_increment(&self) -> bool {
+        self.columns
+            .iter()
+            .any(|column| column.auto_increment.is_some())
+    }
+
+    /// Devuelve el tipo declarado de la columna
+    #[must_use]
+    pub fn data_type(&self, name: &str) -> Option<DataType> {
+        self.column(name).map(|column| column.data_type)
+    }
+
+    /// Devuelve todas las restricciones PRIMARY KEY y UNIQUE, las de columna y las de tabla
+    #[must_use]
+    pub fn unique_constraints(&self) -> Vec<UniqueConstraint> {
+        let column_keys = self
+            .columns
+            .iter()
+            .filter(|column| column.primary_key || column.unique)
+            .map(|column| UniqueConstraint {
+                columns: vec![column.name.to_string()],
+                primary_key: column.primary_key,
+            });
+        column_keys.chain(self.keys.iter().cloned()).collect()
+    }
+
+    /// Devuelve todas las claves foraneas, las de columna y las de tabla
+    #[must_use]
+    pub fn foreign_key_constraints(&self) -> Vec<ForeignKey> {
+        let column_keys = self
+            .columns
+            .iter()
+            .filter_map(|column| column.references.clone());
+        column_keys
+            .chain(self.foreign_keys.iter().cloned())
+            .collect()
+    }
+
+    /// Verifica que las columnas de una restriccion existan y no se repitan
+    fn check_constraint_columns(
+        &self,
+        columns: &[String],
+        constraint: &dyn fmt::Display,
+    ) -> Result<(), ErrorType> {
+        let mut seen = HashSet::new();
+        for column in columns {
+            if self.column(column).is_none() {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Column '{column}' of {constraint} not found in the table"
+                )));
+            }
+            if !seen.insert(column) {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Column '{column}' appears more than once in {constraint}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifica que las restricciones de la tabla sean consistentes: a lo sumo una
+    /// PRIMARY KEY, columnas existentes y sin repetir en cada restriccion y claves
+    /// foraneas con tantas columnas como las referenciadas
+    #[allow(clippy::missing_errors_doc)]
+    pub fn validate_keys(&self) -> Result<(), ErrorType> {
+        let keys = self.unique_constraints();
+        if keys.iter().filter(|key| key.primary_key).count() > 1 {
+            return Err(ErrorType::InvalidSyntax(
+                "Multiple primary keys are not allowed".into(),
+            ));
+        }
+        for key in &keys {
+            self.check_constraint_columns(&key.columns, key)?;
+        }
+        for key in self.foreign_key_constraints() {
+            self.check_constraint_columns(&key.columns, &key)?;
+            if key.columns.len() != key.references.len() {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Number of columns and referenced columns must match in {key}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Escribe cada valor de la fila de la forma normal del tipo de su columna
+    pub fn normalize_row(&self, table_header: &[String], row: &mut [String]) {
+        for (column, value) in table_header.iter().zip(row.iter_mut()) {
+            if let Some(data_type) = self.data_type(column) {
+                *value = data_type.normalize(value);
+            }
+        }
+    }
+
+    /// Verifica que el valor sea valido para el tipo de la columna
+    #[allow(clippy::missing_errors_doc)]
+    pub fn check_value(&self, column: &str, value: &str) -> Result<(), ErrorType> {
+        match self.data_type(column) {
+            Some(data_type) if !data_type.is_valid(value) => Err(ErrorType::InvalidColumn(
+                format!("Invalid value '{value}' for column '{column}' of type {data_type}"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifica que todos los valores de la fila sean validos para su columna y que la fila
+    /// cumpla las restricciones NOT NULL y CHECK. Un CHECK no se evalua si su columna es NULL
+    #[allow(clippy::missing_errors_doc)]
+    pub fn check_row(
+        &self,
+        table: &str,
+        table_header: &[String],
+        row: &[String],
+    ) -> Result<(), ErrorType> {
+        let row_values_map: HashMap<String, String> = table_header
+            .iter()
+            .cloned()
+            .zip(row.iter().cloned())
+            .collect();
+
+        for (column, value) in table_header.iter().zip(row) {
+            self.check_value(column, value)?;
+            let Some(definition) = self.column(column) else {
+                continue;
+            };
+
+            let violation = if value.is_empty() {
+                definition.not_null.then_some("NOT NULL".to_string())
+            } else {
+                definition
+                    .check
+                    .as_ref()
+                    .filter(|check| {
+                        !check
+                            .condition
+                            .evaluate_with_schema(&row_values_map, Some(self))
+                    })
+                    .map(|check| format!("CHECK ({})", check.expression))
+            };
+            if let Some(constraint) = violation {
+                return Err(ErrorType::ConstraintViolation(format!(
+                    "Column '{column}' of table '{table}' violates {constraint} in row ({})",
+                    row.join(",")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_type_validation() {
+        assert!(DataType::Integer.is_valid("-12"));
+        assert!(!DataType::Integer.is_valid("1.5"));
+        assert!(DataType::Decimal.is_valid("1.5"));
+        assert!(!DataType::Decimal.is_valid("abc"));
+        assert!(DataType::Boolean.is_valid("TRUE"));
+        assert!(!DataType::Boolean.is_valid("si"));
+        assert!(DataType::Date.is_valid("2024-02-29"));
+        assert!(!DataType::Date.is_valid("2023-02-29"));
+        assert!(!DataType::Date.is_valid("29/02/2024"));
+        assert!(DataType::Integer.is_valid(""));
+    }
+
+    #[test]
+    fn test_data_type_comparison() {
+        assert_eq!(DataType::Integer.compare("9", "10"), Ordering::Less);
+        assert_eq!(DataType::Text.compare("9", "10"), Ordering::Greater);
+        assert_eq!(DataType::Decimal.compare("1.50", "1.5"), Ordering::Equal);
+        assert_eq!(DataType::Boolean.compare("TRUE", "true"), Ordering::Equal);
+        assert_eq!(
+            DataType::Date.compare("2023-12-31", "2024-01-01"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_data_type_normalize() {
+        assert_eq!(DataType::Integer.normalize("01"), "1");
+        assert_eq!(DataType::Integer.normalize("+7"), "7");
+        assert_eq!(DataType::Decimal.normalize("1.50"), "1.5");
+        assert_eq!(DataType::Boolean.normalize("true"), "TRUE");
+        assert_eq!(DataType::Text.normalize("01"), "01");
+        // NULL y los valores invalidos no cambian
+        assert_eq!(DataType::Integer.normalize(""), "");
+        assert_eq!(DataType::Integer.normalize("uno"), "uno");
+    }
+
+    #[test]
+    fn test_schema_save_and_load() {
+        let db_path = "./test_schema_db1";
+        fs::create_dir_all(db_path).expect("Failed to create test database directory");
+
+        let mut ciudad = ColumnDefinition::new("ciudad", DataType::Text);
+        ciudad.not_null = true;
+        ciudad.default = Some("Buenos Aires".to_string());
+        let schema = TableSchema::new(vec![ColumnDefinition::new("id", DataType::Integer), ciudad]);
+        schema
+            .save("clientes", db_path)
+            .expect("Failed to save schema");
+
+        let loaded = TableSchema::load("clientes", db_path).expect("Failed to load schema");
+        assert_eq!(loaded, Some(schema.clone()));
+        assert_eq!(TableSchema::load("ordenes", db_path).unwrap(), None);
+
+        let header = ["id".to_string(), "ciudad".to_string()];
+        assert!(schema
+            .check_row(
+                "clientes",
+                &header,
+                &["1".to_string(), "Rosario".to_string()]
+            )
+            .is_ok());
+        assert!(matches!(
+            schema.check_row(
+                "clientes",
+                &header,
+                &["uno".to_string(), "Rosario".to_string()]
+            ),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+
+        fs::remove_dir_all(db_path).expect("Failed to delete test database directory");
+    }
+
+    #[test]
+    fn test_check_row_constraints() {
+        let schema = TableSchema::new(vec![
+            parse_column_definition("id INTEGER NOT NULL").unwrap(),
+            parse_column_definition("edad INTEGER CHECK (edad >= 18)").unwrap(),
+        ]);
+        let header = ["id".to_string(), "edad".to_string()];
+        let row = |id: &str, edad: &str| [id.to_string(), edad.to_string()];
+
+        assert!(schema
+            .check_row("clientes", &header, &row("1", "20"))
+            .is_ok());
+        // el CHECK no se evalua sobre NULL
+        assert!(schema.check_row("clientes", &header, &row("1", "")).is_ok());
+
+        let result = schema.check_row("clientes", &header, &row("", "20"));
+        if let Err(ErrorType::ConstraintViolation(message)) = result {
+            assert_eq!(
+                message,
+                "Column 'id' of table 'clientes' violates NOT NULL in row (,20)"
+            );
+        } else {
+            panic!("Expected a constraint violation");
+        }
+
+        let result = schema.check_row("clientes", &header, &row("1", "9"));
+        if let Err(ErrorType::ConstraintViolation(message)) = result {
+            assert_eq!(
+                message,
+                "Column 'edad' of table 'clientes' violates CHECK (edad >= 18) in row (1,9)"
+            );
+        } else {
+            panic!("Expected a constraint violation");
+        }
+    }
+}
@@ -0,0 +1,541 @@
+use crate::errors::ErrorType;
+use crate::order::{Direction, OrderBy};
+use crate::schema::{DataType, TableSchema};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+/// Representacion de las funciones de agregacion que se pueden usar como ventana
+pub enum AggregateFunction {
+    Sum,
+    Count,
+    Avg,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone)]
+/// Representacion de las distintas funciones de ventana
+pub enum WindowFunctionKind {
+    RowNumber,
+    Rank,
+    DenseRank,
+    Lag {
+        column: String,
+        offset: usize,
+        default: String,
+    },
+    Lead {
+        column: String,
+        offset: usize,
+        default: String,
+    },
+    /// Si la columna es `None` cuenta todas las filas (`COUNT(*)`)
+    Aggregate(AggregateFunction, Option<String>),
+}
+
+#[derive(Debug, Clone)]
+/// Funcion de ventana del select: `funcion OVER (PARTITION BY ... ORDER BY ...) AS alias`
+pub struct WindowFunction {
+    pub function: WindowFunctionKind,
+    pub partition_by: Vec<String>,
+    pub order_by: Vec<OrderBy>,
+    pub alias: String,
+}
+
+/// Compara dos valores, numericamente si ambos son numeros
+fn compare_values(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// Acumula los valores de una columna para las funciones de agregacion
+#[derive(Default)]
+struct Accumulator {
+    count: usize,
+    int_sum: Option<i64>,
+    float_sum: f64,
+    min: Option<String>,
+    max: Option<String>,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: Option<&str>) {
+        // sin columna (COUNT(*)) se cuentan todas las filas
+        let Some(value) = value else {
+            self.count += 1;
+            return;
+        };
+        // los valores vacios son nulos y no se acumulan
+        if value.is_empty() {
+            return;
+        }
+        self.count += 1;
+
+        if let Ok(number) = value.parse::<f64>() {
+            self.float_sum += number;
+            let previous = if self.count == 1 {
+                Some(0)
+            } else {
+                self.int_sum
+            };
+            self.int_sum = previous.zip(value.parse::<i64>().ok()).map(|(a, b)| a + b);
+        } else {
+            self.int_sum = None;
+        }
+
+        if self
+            .min
+            .as_ref()
+            .is_none_or(|min| compare_values(value, min) == Ordering::Less)
+        {
+            self.min = Some(value.to_string());
+        }
+        if self
+            .max
+            .as_ref()
+            .is_none_or(|max| compare_values(value, max) == Ordering::Greater)
+        {
+            self.max = Some(value.to_string());
+        }
+    }
+
+    fn result(&self, function: &AggregateFunction) -> String {
+        match function {
+            AggregateFunction::Count => self.count.to_string(),
+            AggregateFunction::Sum if self.count == 0 => String::new(),
+            AggregateFunction::Sum => self
+                .int_sum
+                .map_or_else(|| self.float_sum.to_string(), |sum| sum.to_string()),
+            AggregateFunction::Avg if self.count == 0 => String::new(),
+            #[allow(clippy::cast_precision_loss)]
+            AggregateFunction::Avg => (self.float_sum / self.count as f64).to_string(),
+            AggregateFunction::Min => self.min.clone().unwrap_or_default(),
+            AggregateFunction::Max => self.max.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl WindowFunction {
+    /// Devuelve las columnas de la tabla que usa la ventana
+    #[must_use]
+    pub fn columns(&self) -> Vec<&String> {
+        let argument = match &self.function {
+            WindowFunctionKind::Lag { column, .. } | WindowFunctionKind::Lead { column, .. } => {
+                Some(column)
+            }
+            WindowFunctionKind::Aggregate(_, column) => column.as_ref(),
+            _ => None,
+        };
+
+        self.partition_by
+            .iter()
+            .chain(self.order_by.iter().map(|order| &order.column))
+            .chain(argument)
+            .collect()
+    }
+
+    /// Verifica que las columnas usadas por la ventana esten en la tabla y que SUM y AVG
+    /// se apliquen sobre columnas numericas
+    pub fn check_columns(
+        &self,
+        table_header: &[String],
+        schema: Option<&TableSchema>,
+    ) -> Result<(), ErrorType> {
+        for column in self.columns() {
+            if !table_header.contains(column) {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Column '{column}' used in window function not found in the table"
+                )));
+            }
+        }
+        if let WindowFunctionKind::Aggregate(
+            AggregateFunction::Sum | AggregateFunction::Avg,
+            Some(column),
+        ) = &self.function
+        {
+            if let Some(data_type) = schema
+                .and_then(|schema| schema.data_type(column))
+                .filter(|data_type| !matches!(data_type, DataType::Integer | DataType::Decimal))
+            {
+                return Err(ErrorType::InvalidColumn(format!(
+                    "Column '{column}' of type {data_type} used in window function is not numeric"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Devuelve el tipo del valor que calcula la ventana. La suma de enteros es entera y el
+    /// promedio siempre es decimal. LAG, LEAD, MIN y MAX tienen el tipo de su columna
+    #[must_use]
+    pub fn data_type(&self, schema: Option<&TableSchema>) -> Option<DataType> {
+        let column_type = |column: &String| schema.and_then(|schema| schema.data_type(column));
+        match &self.function {
+            WindowFunctionKind::RowNumber
+            | WindowFunctionKind::Rank
+            | WindowFunctionKind::DenseRank
+            | WindowFunctionKind::Aggregate(AggregateFunction::Count, _) => Some(DataType::Integer),
+            WindowFunctionKind::Aggregate(AggregateFunction::Sum, column)
+                if column.as_ref().and_then(column_type) == Some(DataType::Integer) =>
+            {
+                Some(DataType::Integer)
+            }
+            WindowFunctionKind::Aggregate(AggregateFunction::Sum | AggregateFunction::Avg, _) => {
+                Some(DataType::Decimal)
+            }
+            WindowFunctionKind::Lag { column, .. }
+            | WindowFunctionKind::Lead { column, .. }
+            | WindowFunctionKind::Aggregate(
+                AggregateFunction::Min | AggregateFunction::Max,
+                Some(column),
+            ) => column_type(column),
+            WindowFunctionKind::Aggregate(_, None) => None,
+        }
+    }
+
+    /// Compara dos filas segun el ORDER BY de la ventana
+    fn compare_order(&self, a: &HashMap<String, String>, b: &HashMap<String, String>) -> Ordering {
+        for OrderBy { column, direction } in &self.order_by {
+            let cmp = compare_values(&a[column], &b[column]);
+            if cmp != Ordering::Equal {
+                return match direction {
+                    Direction::Ascending => cmp,
+                    Direction::Descending => cmp.reverse(),
+                };
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Devuelve la clave de particion de la fila
+    fn partition_key<'a>(&self, row: &'a HashMap<String, String>) -> Vec<&'a String> {
+        self.partition_by
+            .iter()
+            .map(|column| &row[column])
+            .collect()
+    }
+
+    /// Calcula el valor de la ventana para cada fila de una particion ya ordenada
+    fn evaluate_partition(&self, partition: &[&HashMap<String, String>]) -> Vec<String> {
+        let mut values = Vec::with_capacity(partition.len());
+
+        match &self.function {
+            WindowFunctionKind::RowNumber => {
+                values.extend((1..=partition.len()).map(|i| i.to_string()));
+            }
+            WindowFunctionKind::Rank | WindowFunctionKind::DenseRank => {
+                let dense = matches!(self.function, WindowFunctionKind::DenseRank);
+                let mut rank = 0;
+                for (i, row) in partition.iter().enumerate() {
+                    let is_peer =
+                        i > 0 && self.compare_order(partition[i - 1], row) == Ordering::Equal;
+                    if !is_peer {
+                        rank = if dense { rank + 1 } else { i + 1 };
+                    }
+                    values.push(rank.to_string());
+                }
+            }
+            WindowFunctionKind::Lag {
+                column,
+                offset,
+                default,
+            } => {
+                for i in 0..partition.len() {
+                    let value = i.checked_sub(*offset).map(|j| &partition[j][column]);
+                    values.push(value.unwrap_or(default).to_string());
+                }
+            }
+            WindowFunctionKind::Lead {
+                column,
+                offset,
+                default,
+            } => {
+                for i in 0..partition.len() {
+                    let value = partition.get(i + offset).map(|row| &row[column]);
+                    values.push(value.unwrap_or(default).to_string());
+                }
+            }
+            WindowFunctionKind::Aggregate(function, column) => {
+                let mut accumulator = Accumulator::default();
+
+                // sin ORDER BY la ventana es toda la particion
+                if self.order_by.is_empty() {
+                    for row in partition {
+                        accumulator.add(column.as_ref().map(|c| row[c].as_str()));
+                    }
+                    let result = accumulator.result(function);
+                    values.resize(partition.len(), result);
+                    return values;
+                }
+
+                // con ORDER BY se acumula hasta la fila actual, incluyendo sus pares
+                let mut start = 0;
+                while start < partition.len() {
+                    let mut end = start + 1;
+                    while end < partition.len()
+                        && self.compare_order(partition[start], partition[end]) == Ordering::Equal
+                    {
+                        end += 1;
+                    }
+                    for row in &partition[start..end] {
+                        accumulator.add(column.as_ref().map(|c| row[c].as_str()));
+                    }
+                    let result = accumulator.result(function);
+                    values.resize(end, result);
+                    start = end;
+                }
+            }
+        }
+        values
+    }
+
+    /// Calcula el valor de la ventana para cada fila, en el orden en que fueron recibidas
+    fn evaluate(&self, rows: &[HashMap<String, String>]) -> Vec<String> {
+        let mut positions: Vec<usize> = (0..rows.len()).collect();
+
+        // agrupa las filas por particion y las ordena dentro de cada una
+        positions.sort_by(|&a, &b| {
+            self.partition_key(&rows[a])
+                .cmp(&self.partition_key(&rows[b]))
+                .then_with(|| self.compare_order(&rows[a], &rows[b]))
+        });
+
+        let mut result = vec![String::new(); rows.len()];
+        let mut start = 0;
+        while start < positions.len() {
+            let key = self.partition_key(&rows[positions[start]]);
+            let end = positions[start..]
+                .iter()
+                .position(|&i| self.partition_key(&rows[i]) != key)
+                .map_or(positions.len(), |offset| start + offset);
+
+            let partition: Vec<&HashMap<String, String>> =
+                positions[start..end].iter().map(|&i| &rows[i]).collect();
+            for (&position, value) in positions[start..end]
+                .iter()
+                .zip(self.evaluate_partition(&partition))
+            {
+                result[position] = value;
+            }
+            start = end;
+        }
+        result
+    }
+}
+
+/// Agrega a cada fila el valor de las funciones de ventana bajo su alias
+pub fn apply_window_functions(rows: &mut [HashMap<String, String>], windows: &[WindowFunction]) {
+    for window in windows {
+        let values = window.evaluate(rows);
+        for (row, value) in rows.iter_mut().zip(values) {
+            row.insert(window.alias.clone(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::ColumnDefinition;
+
+    fn rows(data: &[(&str, &str, &str)]) -> Vec<HashMap<String, String>> {
+        data.iter()
+            .map(|(id, cliente, cantidad)| {
+                HashMap::from([
+                    ("id".to_string(), id.to_string()),
+                    ("id_cliente".to_string(), cliente.to_string()),
+                    ("cantidad".to_string(), cantidad.to_string()),
+                ])
+            })
+            .collect()
+    }
+
+    fn window(function: WindowFunctionKind, order_by: Vec<OrderBy>) -> WindowFunction {
+        WindowFunction {
+            function,
+            partition_by: vec!["id_cliente".to_string()],
+            order_by,
+            alias: "w".to_string(),
+        }
+    }
+
+    fn by_cantidad_desc() -> Vec<OrderBy> {
+        vec![OrderBy {
+            column: "cantidad".to_string(),
+            direction: Direction::Descending,
+        }]
+    }
+
+    fn results(rows: &[HashMap<String, String>]) -> Vec<&str> {
+        rows.iter().map(|row| row["w"].as_str()).collect()
+    }
+
+    #[test]
+    fn test_row_number_and_ranks_by_partition() {
+        let data = [
+            ("1", "A", "2"),
+            ("2", "A", "5"),
+            ("3", "B", "1"),
+            ("4", "A", "2"),
+            ("5", "A", "1"),
+        ];
+
+        let mut table = rows(&data);
+        apply_window_functions(
+            &mut table,
+            &[window(WindowFunctionKind::RowNumber, by_cantidad_desc())],
+        );
+        assert_eq!(results(&table), ["2", "1", "1", "3", "4"]);
+
+        let mut table = rows(&data);
+        apply_window_functions(
+            &mut table,
+            &[window(WindowFunctionKind::Rank, by_cantidad_desc())],
+        );
+        assert_eq!(results(&table), ["2", "1", "1", "2", "4"]);
+
+        let mut table = rows(&data);
+        apply_window_functions(
+            &mut table,
+            &[window(WindowFunctionKind::DenseRank, by_cantidad_desc())],
+        );
+        assert_eq!(results(&table), ["2", "1", "1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_lag_and_lead() {
+        let data = [("1", "A", "10"), ("2", "A", "20"), ("3", "A", "30")];
+        let by_id = || {
+            vec![OrderBy {
+                column: "id".to_string(),
+                direction: Direction::Ascending,
+            }]
+        };
+
+        let mut table = rows(&data);
+        let lag = WindowFunctionKind::Lag {
+            column: "cantidad".to_string(),
+            offset: 1,
+            default: "0".to_string(),
+        };
+        apply_window_functions(&mut table, &[window(lag, by_id())]);
+        assert_eq!(results(&table), ["0", "10", "20"]);
+
+        let mut table = rows(&data);
+        let lead = WindowFunctionKind::Lead {
+            column: "cantidad".to_string(),
+            offset: 2,
+            default: String::new(),
+        };
+        apply_window_functions(&mut table, &[window(lead, by_id())]);
+        assert_eq!(results(&table), ["30", "", ""]);
+    }
+
+    #[test]
+    fn test_running_sum_includes_peers() {
+        let data = [
+            ("1", "A", "1"),
+            ("2", "A", "2"),
+            ("3", "A", "2"),
+            ("4", "B", "7"),
+        ];
+        let by_cantidad = vec![OrderBy {
+            column: "cantidad".to_string(),
+            direction: Direction::Ascending,
+        }];
+
+        let mut table = rows(&data);
+        let sum = WindowFunctionKind::Aggregate(AggregateFunction::Sum, Some("cantidad".into()));
+        apply_window_functions(&mut table, &[window(sum, by_cantidad)]);
+        assert_eq!(results(&table), ["1", "5", "5", "7"]);
+    }
+
+    #[test]
+    fn test_aggregate_without_order_uses_whole_partition() {
+        let data = [("1", "A", "1"), ("2", "A", "4"), ("3", "B", "7")];
+
+        let mut table = rows(&data);
+        let count = WindowFunctionKind::Aggregate(AggregateFunction::Count, None);
+        apply_window_functions(&mut table, &[window(count, Vec::new())]);
+        assert_eq!(results(&table), ["2", "2", "1"]);
+
+        let mut table = rows(&data);
+        let avg = WindowFunctionKind::Aggregate(AggregateFunction::Avg, Some("cantidad".into()));
+        apply_window_functions(&mut table, &[window(avg, Vec::new())]);
+        assert_eq!(results(&table), ["2.5", "2.5", "7"]);
+    }
+
+    #[test]
+    fn test_check_columns() {
+        let header = ["id".to_string(), "id_cliente".to_string()];
+        let valid = window(WindowFunctionKind::RowNumber, Vec::new());
+        assert!(valid.check_columns(&header, None).is_ok());
+
+        let invalid = window(WindowFunctionKind::RowNumber, by_cantidad_desc());
+        assert!(matches!(
+            invalid.check_columns(&header, None),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+
+        // solo se suman columnas numericas
+        let schema = TableSchema::new(vec![
+            ColumnDefinition::new("id", DataType::Integer),
+            ColumnDefinition::new("id_cliente", DataType::Text),
+        ]);
+        let sum = |column: &str| {
+            window(
+                WindowFunctionKind::Aggregate(AggregateFunction::Sum, Some(column.into())),
+                Vec::new(),
+            )
+        };
+        assert!(sum("id").check_columns(&header, Some(&schema)).is_ok());
+        assert!(matches!(
+            sum("id_cliente").check_columns(&header, Some(&schema)),
+            Err(ErrorType::InvalidColumn(_))
+        ));
+        assert!(sum("id_cliente").check_columns(&header, None).is_ok());
+    }
+
+    #[test]
+    fn test_data_type() {
+        let schema = TableSchema::new(vec![
+            ColumnDefinition::new("id", DataType::Integer),
+            ColumnDefinition::new("precio", DataType::Decimal),
+        ]);
+        let data_type = |function| window(function, Vec::new()).data_type(Some(&schema));
+        let aggregate = |function, column: &str| {
+            data_type(WindowFunctionKind::Aggregate(function, Some(column.into())))
+        };
+
+        assert_eq!(
+            data_type(WindowFunctionKind::RowNumber),
+            Some(DataType::Integer)
+        );
+        assert_eq!(
+            data_type(WindowFunctionKind::Aggregate(
+                AggregateFunction::Count,
+                None
+            )),
+            Some(DataType::Integer)
+        );
+        assert_eq!(
+            aggregate(AggregateFunction::Sum, "id"),
+            Some(DataType::Integer)
+        );
+        assert_eq!(
+            aggregate(AggregateFunction::Sum, "precio"),
+            Some(DataType::Decimal)
+        );
+        assert_eq!(
+            aggregate(AggregateFunction::Avg, "id"),
+            Some(DataType::Decimal)
+        );
+        assert_eq!(
+            aggregate(AggregateFunction::Max, "precio"),
+            Some(DataType::Decimal)
+        );
+    }
+}
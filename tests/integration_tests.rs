@@ -1,15 +1,27 @@
-use sql_rustico::{command_types::Commands, commands::execute, parser::parse_query};
+use sql_rustico::{
+    command_result::CommandResult,
+    command_types::Commands,
+    commands::{execute, execute_to, query},
+    database::Database,
+    errors::ErrorType,
+    parser::parse_query,
+    schema::DataType,
+};
 use std::fs::create_dir_all;
 use std::{error::Error, fs};
 
-fn sql_main_replica(db_path: &str, query: &str) -> Result<Option<String>, Box<dyn Error>> {
+fn sql_main_result(db_path: &str, query: &str) -> Result<CommandResult, Box<dyn Error>> {
     // Parseo la query, chequeo errores y obtengo el comando
     let command: Commands = parse_query(&mut query.to_string()).map_err(|e| e.create_error())?;
 
     // Ejecuto el comando y busco errores
-    let output = execute(&command, db_path).map_err(|e| e.create_error())?;
+    let result = execute(&command, db_path).map_err(|e| e.create_error())?;
+
+    Ok(result)
+}
 
-    Ok(output)
+fn sql_main_replica(db_path: &str, query: &str) -> Result<Option<String>, Box<dyn Error>> {
+    Ok(sql_main_result(db_path, query)?.output)
 }
 #[test]
 fn test_select_ordenes_with_quantity_greater_than_one() -> Result<(), Box<dyn Error>> {
@@ -46,6 +58,12 @@ fn test_select_ordenes_with_complex_conditions() -> Result<(), Box<dyn Error>> {
     ); // Verifica el orden esperado aquí
 
     assert_eq!(output, expected_output);
+
+    // una comparacion entre textos constantes no lee ninguna columna
+    let output = sql_main_replica(db_path, "SELECT id FROM ordenes WHERE 'x' = 'x' LIMIT 1;")?;
+    assert_eq!(output, Some("id\n101\n".to_string()));
+    let output = sql_main_replica(db_path, "SELECT id FROM ordenes WHERE 'x' = 'y';")?;
+    assert_eq!(output, Some("id\n".to_string()));
     Ok(())
 }
 
@@ -66,6 +84,10 @@ fn test_select_clientes_with_last_name_lopez_ordered_by_email_desc() -> Result<(
     );
 
     assert_eq!(output, expected_output);
+
+    // la columna inexistente es un error aunque el where siempre se cumpla
+    let result = sql_main_replica(db_path, "SELECT * FROM clientes WHERE 1 = 1 OR nope = 3;");
+    assert!(result.is_err());
     Ok(())
 }
 
@@ -148,7 +170,13 @@ fn test_delete_cliente() -> Result<(), Box<dyn Error>> {
     // Copia los archivos originales a la carpeta de pruebas
     fs::copy("tablas/clientes.csv", format!("{}/clientes.csv", test_dir))?;
 
-    sql_main_replica(test_dir, "DELETE FROM clientes WHERE id = 4;")?;
+    // la columna inexistente es un error aunque el where nunca se cumpla
+    let result = sql_main_result(test_dir, "DELETE FROM clientes WHERE 1 = 2 AND nope = 3;");
+    assert!(result.is_err());
+
+    let result = sql_main_result(test_dir, "DELETE FROM clientes WHERE id = 4;")?;
+    assert_eq!(result.to_string(), "DELETE 1");
+    assert_eq!(result.rows_in_table("clientes"), Some(1));
 
     let updated_content = fs::read_to_string(format!("{}/clientes.csv", test_dir))?;
     let expected_content = "id,nombre,apellido,email\n\
@@ -165,3 +193,802 @@ fn test_delete_cliente() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_select_union_ordenes_and_clientes() -> Result<(), Box<dyn Error>> {
+    let db_path = "tablas";
+
+    let output = sql_main_replica(
+        db_path,
+        "SELECT id_cliente FROM ordenes WHERE producto = 'Laptop' UNION SELECT id FROM clientes WHERE apellido = 'López' ORDER BY id_cliente;",
+    )?;
+
+    let expected_output = Some(
+        "id_cliente\n\
+                                                1\n\
+                                                2\n\
+                                                5\n"
+        .to_string(),
+    );
+
+    assert_eq!(output, expected_output);
+
+    // las dos columnas son enteras, se ordenan como numeros
+    let output = sql_main_replica(
+        db_path,
+        "SELECT id FROM ordenes WHERE id < 103 UNION ALL SELECT id FROM clientes WHERE id < 3 ORDER BY id;",
+    )?;
+    assert_eq!(output, Some("id\n1\n2\n101\n102\n".to_string()));
+    Ok(())
+}
+
+#[test]
+fn test_select_ordenes_with_window_functions() -> Result<(), Box<dyn Error>> {
+    let db_path = "tablas";
+
+    let output = sql_main_replica(
+        db_path,
+        "SELECT id, id_cliente, ROW_NUMBER() OVER (PARTITION BY id_cliente ORDER BY id) AS n, SUM(cantidad) OVER (PARTITION BY id_cliente ORDER BY id) AS total FROM ordenes WHERE id_cliente = 4 OR id_cliente = 6;",
+    )?;
+
+    let expected_output = Some(
+        "id,id_cliente,n,total\n\
+                                                105,4,1,2\n\
+                                                107,6,1,1\n\
+                                                108,4,2,3\n\
+                                                110,6,2,3\n"
+            .to_string(),
+    );
+
+    assert_eq!(output, expected_output);
+
+    // el numero de fila se ordena como entero
+    let output = sql_main_replica(
+        db_path,
+        "SELECT id, ROW_NUMBER() OVER (ORDER BY id DESC) FROM ordenes ORDER BY row_number LIMIT 3;",
+    )?;
+    assert_eq!(
+        output,
+        Some("id,row_number\n110,1\n109,2\n108,3\n".to_string())
+    );
+
+    // solo se suman columnas numericas
+    let result = sql_main_replica(db_path, "SELECT id, SUM(producto) OVER () FROM ordenes;");
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_insert_ordenes_archivo_from_select() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db4";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia los archivos originales y crea la tabla de archivo vacia
+    fs::copy("tablas/ordenes.csv", format!("{}/ordenes.csv", test_dir))?;
+    fs::write(
+        format!("{}/ordenes_archivo.csv", test_dir),
+        "id,id_cliente,producto,cantidad\n",
+    )?;
+
+    sql_main_replica(
+        test_dir,
+        "INSERT INTO ordenes_archivo SELECT * FROM ordenes WHERE id < 105;",
+    )?;
+
+    let archived_content = fs::read_to_string(format!("{}/ordenes_archivo.csv", test_dir))?;
+    let expected_content = "id,id_cliente,producto,cantidad\n\
+                                101,1,Laptop,1\n\
+                                103,1,Monitor,1\n\
+                                102,2,Teléfono,2\n\
+                                104,3,Teclado,1\n";
+
+    assert_eq!(archived_content, expected_content);
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_ordenes_with_returning() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db5";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia los archivos originales a la carpeta de pruebas
+    fs::copy("tablas/ordenes.csv", format!("{}/ordenes.csv", test_dir))?;
+
+    let output = sql_main_replica(
+        test_dir,
+        "DELETE FROM ordenes WHERE cantidad = 2 RETURNING id, producto;",
+    )?;
+
+    let expected_output = Some(
+        "id,producto\n\
+                                                102,Teléfono\n\
+                                                105,Mouse\n\
+                                                110,Teléfono\n"
+            .to_string(),
+    );
+
+    assert_eq!(output, expected_output);
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_clientes_on_conflict() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db6";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia los archivos originales a la carpeta de pruebas
+    fs::copy("tablas/clientes.csv", format!("{}/clientes.csv", test_dir))?;
+
+    let result = sql_main_result(
+        test_dir,
+        "INSERT INTO clientes (id, nombre, apellido, email) VALUES (6, 'Laura', 'Fernández', 'laura.f@email.com'), (7, 'Pablo', 'Ruiz', 'pablo.ruiz@email.com') ON CONFLICT (id) DO UPDATE SET email = EXCLUDED.email;",
+    )?;
+    assert_eq!(result.to_string(), "INSERT 2");
+
+    let result = sql_main_result(
+        test_dir,
+        "INSERT INTO clientes VALUES (1, 'Juan', 'Pérez', 'otro@email.com') ON CONFLICT (id) DO NOTHING;",
+    )?;
+    assert_eq!(result.to_string(), "INSERT 0");
+
+    let updated_content = fs::read_to_string(format!("{}/clientes.csv", test_dir))?;
+    let expected_content = "id,nombre,apellido,email\n\
+                                1,Juan,Pérez,juan.perez@email.com\n\
+                                2,Ana,López,ana.lopez@email.com\n\
+                                3,Carlos,Gómez,carlos.gomez@email.com\n\
+                                4,María,Rodríguez,mrodriguez@hotmail.com\n\
+                                5,José,López,jose.lopez@email.com\n\
+                                6,Laura,Fernández,laura.f@email.com\n\
+                                7,Pablo,Ruiz,pablo.ruiz@email.com\n";
+
+    assert_eq!(updated_content, expected_content);
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_create_insert_and_drop_table() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db7";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    let result = sql_main_result(
+        test_dir,
+        "CREATE TABLE IF NOT EXISTS proveedores (id INTEGER, nombre TEXT);",
+    )?;
+    assert_eq!(result.to_string(), "CREATE TABLE");
+
+    sql_main_replica(
+        test_dir,
+        "INSERT INTO proveedores VALUES (1, 'Acme'), (2, 'Globex');",
+    )?;
+    let output = sql_main_replica(test_dir, "SELECT nombre FROM proveedores WHERE id = 2;")?;
+    assert_eq!(output, Some("nombre\nGlobex\n".to_string()));
+
+    assert!(sql_main_replica(test_dir, "CREATE TABLE proveedores (id INTEGER);").is_err());
+
+    sql_main_replica(test_dir, "DROP TABLE proveedores;")?;
+    assert!(sql_main_replica(test_dir, "DROP TABLE proveedores;").is_err());
+    sql_main_replica(test_dir, "DROP TABLE IF EXISTS proveedores;")?;
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_columns_from_schema() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db8";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia la tabla y su esquema a la carpeta de pruebas
+    fs::copy("tablas/ordenes.csv", format!("{}/ordenes.csv", test_dir))?;
+    fs::copy(
+        "tablas/ordenes.schema",
+        format!("{}/ordenes.schema", test_dir),
+    )?;
+
+    // cantidad es INTEGER, el valor no se puede insertar
+    let result = sql_main_replica(
+        test_dir,
+        "INSERT INTO ordenes VALUES (112, 2, 'Cable', 'dos');",
+    );
+    assert!(result.is_err());
+
+    // con tipos el 10 se ordena despues del 9
+    sql_main_replica(
+        test_dir,
+        "INSERT INTO ordenes VALUES (112, 2, 'Cable', 10);",
+    )?;
+    let output = sql_main_replica(
+        test_dir,
+        "SELECT id, cantidad FROM ordenes WHERE cantidad > 1 ORDER BY cantidad DESC LIMIT 2;",
+    )?;
+    assert_eq!(output, Some("id,cantidad\n112,10\n102,2\n".to_string()));
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_primary_key_rejects_duplicated_clientes() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db9";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia la tabla y su esquema a la carpeta de pruebas
+    fs::copy("tablas/clientes.csv", format!("{}/clientes.csv", test_dir))?;
+    fs::copy(
+        "tablas/clientes.schema",
+        format!("{}/clientes.schema", test_dir),
+    )?;
+
+    // id es la clave primaria de clientes
+    let result = sql_main_result(
+        test_dir,
+        "INSERT INTO clientes VALUES (1, 'Pablo', 'Ruiz', 'pablo.ruiz@email.com');",
+    );
+    assert!(result.is_err());
+    let result = sql_main_result(test_dir, "UPDATE clientes SET id = 1 WHERE id = 2;");
+    assert!(result.is_err());
+
+    let result = sql_main_result(test_dir, "UPDATE clientes SET id = 7 WHERE id = 2;")?;
+    assert_eq!(result.to_string(), "UPDATE 1");
+
+    let content = fs::read_to_string(format!("{}/clientes.csv", test_dir))?;
+    assert!(content.contains("7,Ana,López,ana.lopez@email.com\n"));
+
+    // 01 es el mismo entero que 1, tambien choca con la clave
+    let result = sql_main_result(
+        test_dir,
+        "INSERT INTO clientes (id, nombre, apellido, email) VALUES (01, 'a', 'b', 'c');",
+    );
+    assert!(result.is_err());
+    let result = sql_main_result(test_dir, "UPDATE clientes SET id = 07 WHERE id = 3;");
+    assert!(result.is_err());
+    let output = sql_main_replica(test_dir, "SELECT nombre FROM clientes WHERE id = 1;")?;
+    assert_eq!(output, Some("nombre\nJuan\n".to_string()));
+
+    // ON CONFLICT encuentra la fila 3 aunque el valor se escriba 03, y el id se guarda normal
+    let output = sql_main_replica(
+        test_dir,
+        "INSERT INTO clientes (id, nombre) VALUES (03, 'Carla'), (008, 'Luis') ON CONFLICT (id) DO UPDATE SET nombre = EXCLUDED.nombre RETURNING id, nombre;",
+    )?;
+    assert_eq!(output, Some("id,nombre\n3,Carla\n8,Luis\n".to_string()));
+    let output = sql_main_replica(test_dir, "SELECT id, nombre FROM clientes WHERE id = 3;")?;
+    assert_eq!(output, Some("id,nombre\n3,Carla\n".to_string()));
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_foreign_key_between_ordenes_and_clientes() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db10";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia las tablas a la carpeta de pruebas, con el esquema de ordenes que declara la clave
+    // foranea a clientes
+    for table in ["clientes", "ordenes"] {
+        fs::copy(
+            format!("tablas/{table}.csv"),
+            format!("{test_dir}/{table}.csv"),
+        )?;
+    }
+    fs::copy(
+        "tablas/clientes.schema",
+        format!("{test_dir}/clientes.schema"),
+    )?;
+    fs::copy(
+        "tests/fixtures_fk/ordenes.schema",
+        format!("{test_dir}/ordenes.schema"),
+    )?;
+
+    // la orden tiene que referenciar un cliente existente
+    let result = sql_main_result(test_dir, "INSERT INTO ordenes VALUES (112, 9, 'Cable', 1);");
+    assert!(result.is_err());
+    let result = sql_main_result(
+        test_dir,
+        "UPDATE ordenes SET id_cliente = 9 WHERE id = 101;",
+    );
+    assert!(result.is_err());
+
+    // el cliente 4 tiene ordenes, no se puede borrar ni cambiar su id
+    let result = sql_main_result(test_dir, "DELETE FROM clientes WHERE id = 4;");
+    assert!(result.is_err());
+    let result = sql_main_result(test_dir, "UPDATE clientes SET id = 40 WHERE id = 4;");
+    assert!(result.is_err());
+    let result = sql_main_result(
+        test_dir,
+        "INSERT INTO clientes (id, nombre) VALUES (4, 'x') ON CONFLICT (id) DO UPDATE SET id = 50;",
+    );
+    assert!(result.is_err());
+    let output = sql_main_replica(test_dir, "SELECT id FROM clientes WHERE id = 4 OR id = 50;")?;
+    assert_eq!(output, Some("id\n4\n".to_string()));
+
+    // sin ordenes el cliente se puede borrar
+    sql_main_result(test_dir, "DELETE FROM ordenes WHERE id_cliente = 4;")?;
+    let result = sql_main_result(test_dir, "DELETE FROM clientes WHERE id = 4;")?;
+    assert_eq!(result.to_string(), "DELETE 1");
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_insert_ordenes_generates_id() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db11";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia las tablas y sus esquemas a la carpeta de pruebas
+    for table in ["clientes", "ordenes"] {
+        fs::copy(
+            format!("tablas/{table}.csv"),
+            format!("{test_dir}/{table}.csv"),
+        )?;
+        fs::copy(
+            format!("tablas/{table}.schema"),
+            format!("{test_dir}/{table}.schema"),
+        )?;
+    }
+
+    // el id de la orden se genera al omitirlo
+    let output = sql_main_replica(
+        test_dir,
+        "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (2, 'Cable', 1), (3, 'Mouse', 2) RETURNING id;",
+    )?;
+    assert_eq!(output, Some("id\n111\n112\n".to_string()));
+
+    let output = sql_main_replica(
+        test_dir,
+        "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (2, 'Parlante', 1) RETURNING id, producto;",
+    )?;
+    assert_eq!(output, Some("id,producto\n113,Parlante\n".to_string()));
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_index_on_ordenes_id_cliente() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db12";
+
+    // Crea el directorio temporal para pruebas
+    create_dir_all(test_dir)?;
+
+    // Copia las tablas y sus esquemas a la carpeta de pruebas
+    for table in ["clientes", "ordenes"] {
+        fs::copy(
+            format!("tablas/{table}.csv"),
+            format!("{test_dir}/{table}.csv"),
+        )?;
+        fs::copy(
+            format!("tablas/{table}.schema"),
+            format!("{test_dir}/{table}.schema"),
+        )?;
+    }
+
+    sql_main_replica(test_dir, "CREATE INDEX ON ordenes (id_cliente);")?;
+    assert!(sql_main_replica(test_dir, "CREATE INDEX ON ordenes (id_cliente);").is_err());
+
+    // el indice se mantiene al insertar, actualizar y borrar
+    sql_main_replica(
+        test_dir,
+        "INSERT INTO ordenes (id_cliente, producto, cantidad) VALUES (4, 'Cable', 3);",
+    )?;
+    sql_main_replica(
+        test_dir,
+        "UPDATE ordenes SET id_cliente = 4 WHERE id = 101;",
+    )?;
+    let result = sql_main_result(test_dir, "DELETE FROM ordenes WHERE id_cliente = 6;")?;
+    assert_eq!(result.affected_rows, 2);
+
+    let output = sql_main_replica(
+        test_dir,
+        "SELECT id, producto FROM ordenes WHERE id_cliente = 4;",
+    )?;
+    assert_eq!(
+        output,
+        Some("id,producto\n101,Laptop\n105,Mouse\n108,Auriculares\n111,Cable\n".to_string())
+    );
+
+    let output = sql_main_replica(
+        test_dir,
+        "SELECT id FROM ordenes WHERE id_cliente >= 4 AND cantidad > 1;",
+    )?;
+    assert_eq!(output, Some("id\n105\n111\n".to_string()));
+
+    // Limpia el directorio temporal
+    fs::remove_dir_all(test_dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_explain_select_ordenes() -> Result<(), Box<dyn Error>> {
+    let db_path = "tablas";
+
+    let result = sql_main_result(
+        db_path,
+        "EXPLAIN SELECT id, producto FROM ordenes WHERE producto = 'Laptop' AND cantidad > 0 ORDER BY id DESC LIMIT 2;",
+    )?;
+    assert_eq!(
+        result.output,
+        Some(
+            "-> Limit 2\n  \
+             -> Sort id DESC\n    \
+               -> Project id, producto\n      \
+                 -> Scan ordenes, columns: id, producto, filter: producto = 'Laptop' AND cantidad > 0\n"
+                .to_string()
+        )
+    );
+    assert!(!result.is_write());
+
+    // EXPLAIN no ejecuta comandos de escritura
+    assert!(sql_main_result(db_path, "EXPLAIN DELETE FROM ordenes;").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_explain_analyze_select_ordenes() -> Result<(), Box<dyn Error>> {
+    let db_path = "tablas";
+
+    let result = sql_main_result(
+        db_path,
+        "EXPLAIN ANALYZE SELECT id FROM ordenes WHERE cantidad > 1 ORDER BY id DESC;",
+    )?;
+    let output = result.output.unwrap_or_default();
+    let lines: Vec<&str> = output.lines().collect();
+
+    // cada nodo muestra las filas que recibe y las que produce, sin imprimir el resultado
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("-> Sort id DESC (rows in=3 out=3, time="));
+    assert!(lines[1].starts_with("  -> Project id (rows in=3 out=3, time="));
+    assert!(lines[2].starts_with(
+        "    -> Scan ordenes, columns: id, filter: cantidad > 1 (rows in=10 out=3, bytes=200, time="
+    ));
+    assert!(lines[2].contains("parse=") && lines[2].contains("evaluate="));
+    assert!(lines[3].starts_with("Execution time: "));
+    Ok(())
+}
+
+#[test]
+fn test_select_streams_to_writer() -> Result<(), Box<dyn Error>> {
+    let db_path = "tablas";
+    let query = "SELECT id, producto FROM ordenes WHERE cantidad > 1 ORDER BY id DESC;";
+    let command = parse_query(&mut query.to_string()).map_err(|e| e.create_error())?;
+
+    // las filas van al writer y el resultado no guarda una copia
+    let mut out = Vec::new();
+    let result = execute_to(&command, db_path, &mut out).map_err(|e| e.create_error())?;
+    assert_eq!(result.output, None);
+    assert_eq!(result.affected_rows, 3);
+    assert_eq!(
+        String::from_utf8(out)?,
+        "id,producto\n110,Teléfono\n105,Mouse\n102,Teléfono\n"
+    );
+    assert_eq!(
+        sql_main_replica(db_path, query)?,
+        Some("id,producto\n110,Teléfono\n105,Mouse\n102,Teléfono\n".to_string())
+    );
+
+    // el resto de los comandos escriben su salida al terminar
+    let command = parse_query(&mut "EXPLAIN SELECT id FROM ordenes;".to_string())
+        .map_err(|e| e.create_error())?;
+    let mut out = Vec::new();
+    let result = execute_to(&command, db_path, &mut out).map_err(|e| e.create_error())?;
+    assert_eq!(Some(String::from_utf8(out)?), result.output);
+    Ok(())
+}
+
+#[test]
+fn test_query_returns_typed_rows() -> Result<(), Box<dyn Error>> {
+    let db_path = "tablas";
+    let command = parse_query(
+        &mut "SELECT id, producto, cantidad FROM ordenes WHERE cantidad > 1 ORDER BY id;"
+            .to_string(),
+    )
+    .map_err(|e| e.create_error())?;
+
+    let rows = query(&command, db_path).map_err(|e| e.create_error())?;
+    let columns: Vec<(&str, Option<DataType>)> = rows
+        .columns()
+        .iter()
+        .map(|column| (column.name.as_str(), column.data_type))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            ("id", Some(DataType::Integer)),
+            ("producto", Some(DataType::Text)),
+            ("cantidad", Some(DataType::Integer)),
+        ]
+    );
+
+    let mut orders = Vec::new();
+    for row in rows {
+        let row = row.map_err(|e| e.create_error())?;
+        let id: i64 = row.get("id").map_err(|e| e.create_error())?;
+        let producto: String = row.get(1).map_err(|e| e.create_error())?;
+        let cantidad: u32 = row.get("cantidad").map_err(|e| e.create_error())?;
+        orders.push((id, producto, cantidad));
+    }
+    assert_eq!(
+        orders,
+        [
+            (102, "Teléfono".to_string(), 2),
+            (105, "Mouse".to_string(), 2),
+            (110, "Teléfono".to_string(), 2),
+        ]
+    );
+
+    // solo las consultas devuelven filas
+    let command = parse_query(&mut "DELETE FROM ordenes WHERE id = 1;".to_string())
+        .map_err(|e| e.create_error())?;
+    assert!(query(&command, db_path).is_err());
+
+    // las filas de varias tablas no tienen un unico header
+    let command =
+        parse_query(&mut "SELECT id FROM *;".to_string()).map_err(|e| e.create_error())?;
+    assert!(matches!(
+        query(&command, db_path),
+        Err(ErrorType::InvalidTable(message))
+            if message == "Expected a SELECT from a single table, found 2 tables"
+    ));
+
+    // las filas se leen al recorrerlas, se puede dejar de leer en cualquier momento
+    let command = parse_query(&mut "SELECT id FROM ordenes ORDER BY id DESC;".to_string())
+        .map_err(|e| e.create_error())?;
+    let mut rows = query(&command, db_path).map_err(|e| e.create_error())?;
+    let first = rows.next().transpose().map_err(|e| e.create_error())?;
+    assert_eq!(
+        first.map(|row| row.values().to_vec()),
+        Some(vec!["110".to_string()])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_prepared_statements_bind_parameters() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db13";
+    create_dir_all(test_dir)?;
+    fs::copy("tablas/clientes.csv", format!("{test_dir}/clientes.csv"))?;
+    fs::copy(
+        "tablas/clientes.schema",
+        format!("{test_dir}/clientes.schema"),
+    )?;
+
+    let db = Database::open(test_dir).map_err(|e| e.create_error())?;
+    let select = db
+        .prepare("SELECT nombre FROM clientes WHERE id = ? OR apellido = ?;")
+        .map_err(|e| e.create_error())?;
+    assert_eq!(select.parameter_count(), 2);
+
+    // la misma sentencia se ejecuta con distintos valores sin volver a parsearla
+    let names = |id: i64, apellido: &str| -> Result<Vec<String>, Box<dyn Error>> {
+        select
+            .query(&[&id, &apellido])
+            .map_err(|e| e.create_error())?
+            .map(|row| {
+                row.and_then(|row| row.get::<String>("nombre"))
+                    .map_err(|e| e.create_error())
+            })
+            .collect()
+    };
+    assert_eq!(names(1, "López")?, ["Juan", "Ana", "José"]);
+    assert_eq!(names(3, "O'Neil")?, ["Carlos"]);
+
+    // el valor nunca se interpreta como parte de la query
+    let delete = db
+        .prepare("DELETE FROM clientes WHERE nombre = ?;")
+        .map_err(|e| e.create_error())?;
+    let result = delete
+        .execute(&[&"x' OR id > '0"])
+        .map_err(|e| e.create_error())?;
+    assert_eq!(result.affected_rows, 0);
+    let result = delete.execute(&[&"Ana"]).map_err(|e| e.create_error())?;
+    assert_eq!(result.affected_rows, 1);
+
+    // todos los parametros deben tener valor, y no pueden sobrar valores
+    assert!(select.query(&[&1]).is_err());
+    assert!(select.query(&[&1, &"López", &2]).is_err());
+    assert!(db
+        .query("SELECT nombre FROM clientes WHERE id = ?;")
+        .is_err());
+    assert!(Database::open("tests/no_existe").is_err());
+
+    fs::remove_dir_all(test_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_placeholders_in_values_updates_and_limit() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db14";
+    create_dir_all(test_dir)?;
+    fs::copy("tablas/clientes.csv", format!("{test_dir}/clientes.csv"))?;
+    fs::copy(
+        "tablas/clientes.schema",
+        format!("{test_dir}/clientes.schema"),
+    )?;
+    let db = Database::open(test_dir).map_err(|e| e.create_error())?;
+
+    let insert = db
+        .prepare("INSERT INTO clientes (id, nombre, apellido, email) VALUES (?, ?, ?, ?);")
+        .map_err(|e| e.create_error())?;
+    assert_eq!(insert.parameter_count(), 4);
+    insert
+        .execute(&[&7, &"Pedro", &"Ruiz", &"pedro@email.com"])
+        .map_err(|e| e.create_error())?;
+    // un valor con ',' romperia la tabla
+    assert!(insert.execute(&[&8, &"Ana, Luz", &"Ruiz", &""]).is_err());
+
+    // cada `?` toma su valor en el orden de la query, aunque el SET se guarde sin orden
+    let update = db
+        .prepare("UPDATE clientes SET email = ?, apellido = ? WHERE id = ?;")
+        .map_err(|e| e.create_error())?;
+    assert_eq!(update.parameter_count(), 3);
+    let result = update
+        .execute(&[&"pr@email.com", &"Ríos", &7])
+        .map_err(|e| e.create_error())?;
+    assert_eq!(result.affected_rows, 1);
+
+    let select = db
+        .prepare("SELECT nombre, email FROM clientes WHERE apellido = :apellido LIMIT :cantidad;")
+        .map_err(|e| e.create_error())?;
+    assert_eq!(select.parameter_names(), ["apellido", "cantidad"]);
+    let rows: Vec<(String, String)> = select
+        .query_named(&[(":apellido", &"Ríos"), (":cantidad", &1)])
+        .map_err(|e| e.create_error())?
+        .map(|row| {
+            let row = row?;
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .collect::<Result<_, ErrorType>>()
+        .map_err(|e| e.create_error())?;
+    assert_eq!(rows, [("Pedro".to_string(), "pr@email.com".to_string())]);
+    let rows = select
+        .query_named(&[("apellido", &"López"), ("cantidad", &1)])
+        .map_err(|e| e.create_error())?;
+    assert_eq!(rows.count(), 1);
+    assert!(select
+        .query_named(&[("apellido", &"López"), ("cantidad", &"diez")])
+        .is_err());
+    assert!(select.query_named(&[("apellido", &"López")]).is_err());
+
+    // un comando con parametros sin valor no se ejecuta
+    let command = parse_query(&mut "DELETE FROM clientes WHERE id = $1;".to_string())
+        .map_err(|e| e.create_error())?;
+    assert!(matches!(
+        execute(&command, test_dir),
+        Err(ErrorType::InvalidSyntax(message)) if message == "Parameter $1 is not bound"
+    ));
+    let command = parse_query(&mut "SELECT nombre FROM clientes LIMIT ?;".to_string())
+        .map_err(|e| e.create_error())?;
+    assert!(query(&command, test_dir).is_err());
+
+    // un valor ligado siempre es un literal, aunque parezca una referencia a EXCLUDED
+    let upsert = db
+        .prepare("INSERT INTO clientes (id, nombre) VALUES (?, ?) ON CONFLICT (id) DO UPDATE SET email = ?;")
+        .map_err(|e| e.create_error())?;
+    upsert
+        .execute(&[&7, &"Pedro", &"EXCLUDED.email"])
+        .map_err(|e| e.create_error())?;
+    let output = db
+        .query("SELECT email FROM clientes WHERE id = 7;")
+        .map_err(|e| e.create_error())?
+        .map(|row| row.and_then(|row| row.get::<String>(0)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.create_error())?;
+    assert_eq!(output, ["EXCLUDED.email"]);
+
+    // los parametros van a la derecha de la comparacion y no se mezclan por nombre y en orden
+    assert!(matches!(
+        db.prepare("DELETE FROM clientes WHERE ? = id;"),
+        Err(ErrorType::InvalidSyntax(_))
+    ));
+    assert!(matches!(
+        db.prepare("UPDATE clientes SET email = ? WHERE id = :id;"),
+        Err(ErrorType::InvalidSyntax(message))
+            if message == "A statement cannot mix named and positional parameters"
+    ));
+
+    fs::remove_dir_all(test_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_alter_table_clientes() -> Result<(), Box<dyn Error>> {
+    let test_dir = "tests/temp_db15";
+    create_dir_all(test_dir)?;
+    for file in ["clientes.csv", "clientes.schema"] {
+        fs::copy(format!("tablas/{file}"), format!("{test_dir}/{file}"))?;
+    }
+    sql_main_replica(test_dir, "CREATE TABLE proveedores (id INTEGER);")?;
+
+    // una coma en el valor por defecto partiria las filas del csv
+    let result = sql_main_result(
+        test_dir,
+        "ALTER TABLE clientes ADD COLUMN tag TEXT DEFAULT 'a,b';",
+    );
+    assert!(result.is_err());
+
+    let result = sql_main_result(
+        test_dir,
+        "ALTER TABLE clientes ADD COLUMN ciudad TEXT DEFAULT 'Rosario';",
+    )?;
+    assert_eq!(result.to_string(), "ALTER TABLE");
+    let output = sql_main_replica(
+        test_dir,
+        "SELECT nombre, ciudad FROM clientes WHERE id = 1;",
+    )?;
+    assert_eq!(output, Some("nombre,ciudad\nJuan,Rosario\n".to_string()));
+
+    sql_main_replica(test_dir, "ALTER TABLE clientes DROP COLUMN email;")?;
+    assert!(sql_main_replica(test_dir, "SELECT email FROM clientes;").is_err());
+
+    sql_main_replica(
+        test_dir,
+        "ALTER TABLE clientes RENAME COLUMN apellido TO apellidos;",
+    )?;
+    let output = sql_main_replica(test_dir, "SELECT apellidos FROM clientes WHERE id = 2;")?;
+    assert_eq!(output, Some("apellidos\nLópez\n".to_string()));
+
+    // no se puede pisar una tabla que ya existe
+    assert!(matches!(
+        parse_query(&mut "ALTER TABLE clientes RENAME TO proveedores;".to_string())
+            .and_then(|command| execute(&command, test_dir)),
+        Err(ErrorType::InvalidTable(_))
+    ));
+    assert!(matches!(
+        parse_query(&mut "ALTER TABLE clientes RENAME TO ../clientes;".to_string()),
+        Err(ErrorType::InvalidTable(_))
+    ));
+
+    sql_main_replica(test_dir, "ALTER TABLE clientes RENAME TO compradores;")?;
+    assert!(sql_main_replica(test_dir, "SELECT * FROM clientes;").is_err());
+    let output = sql_main_replica(
+        test_dir,
+        "SELECT nombre, apellidos, ciudad FROM compradores WHERE id = 1;",
+    )?;
+    assert_eq!(
+        output,
+        Some("nombre,apellidos,ciudad\nJuan,Pérez,Rosario\n".to_string())
+    );
+
+    fs::remove_dir_all(test_dir)?;
+    Ok(())
+}